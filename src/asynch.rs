@@ -0,0 +1,424 @@
+//! Asynchronous versions of [`Miim`] and [`Phy`].
+//!
+//! Every blocking [`Miim`] is also an [`AsyncMiim`], so all drivers in this
+//! crate can be used from async code regardless of whether the underlying
+//! MDIO transactions complete synchronously or through an interrupt.
+//!
+//! [`Phy`]: crate::Phy
+
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{
+    registers::{Ane, AutoNegCap, Bcr, Bsr, Esr, GbCtrl, GbStatus},
+    AutoNegotiationAdvertisement, AutoNegotiationResult, ExtendedPhyStatus, Miim, PhyIdent,
+    PhyStatus,
+};
+
+/// An asynchronous version of [`Miim`].
+///
+/// This trait is implemented for every [`Miim`].
+#[allow(async_fn_in_trait)]
+pub trait AsyncMiim {
+    /// The error that can occur while accessing the bus.
+    type Error: core::fmt::Debug;
+
+    /// Read an MII register
+    async fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error>;
+
+    /// Write to an MII register
+    async fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error>;
+
+    /// Read the [`Bsr::ABILITIES`] bits of the BSR of the PHY at `phy`. The
+    /// other bits are returned as 0.
+    ///
+    /// See [`Miim::read_abilities`].
+    async fn read_abilities(&mut self, phy: u8) -> Result<u16, Self::Error> {
+        Ok(self.read(phy, Bsr::ADDRESS).await? & Bsr::ABILITIES.bits())
+    }
+}
+
+impl<M: Miim> AsyncMiim for M {
+    type Error = M::Error;
+
+    async fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error> {
+        Miim::read(self, phy, reg)
+    }
+
+    async fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error> {
+        Miim::write(self, phy, reg, data)
+    }
+
+    async fn read_abilities(&mut self, phy: u8) -> Result<u16, Self::Error> {
+        Miim::read_abilities(self, phy)
+    }
+}
+
+/// Yield to the executor once, so that polling loops do not starve
+/// other tasks.
+pub(crate) fn yield_now() -> impl Future<Output = ()> {
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                Poll::Ready(())
+            } else {
+                self.0 = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+
+    YieldNow(false)
+}
+
+/// An asynchronous version of [`Phy`](crate::Phy).
+///
+/// Methods that wait for the PHY to reach a certain state yield to the
+/// executor between polls instead of busy-spinning.
+#[allow(async_fn_in_trait)]
+pub trait AsyncPhy<M: AsyncMiim> {
+    /// The best advertisement this PHY can send out.
+    ///
+    /// "Best", in this case, means largest amount of supported features
+    fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement;
+
+    /// Get a mutable reference to the [`AsyncMiim`] for this PHY
+    fn get_miim(&mut self) -> &mut M;
+
+    /// Get the address of this PHY
+    fn get_phy_addr(&self) -> u8;
+
+    /// Read a PHY register over MIIM
+    async fn read(&mut self, address: u8) -> Result<u16, M::Error> {
+        let phy = self.get_phy_addr();
+        let miim = self.get_miim();
+        miim.read(phy, address).await
+    }
+
+    /// Write a PHY register over MIIM
+    async fn write(&mut self, address: u8, value: u16) -> Result<(), M::Error> {
+        let phy = self.get_phy_addr();
+        let miim = self.get_miim();
+        miim.write(phy, address, value).await
+    }
+
+    /// Get the raw value of the Base Control Register of this PHY
+    async fn bcr(&mut self) -> Result<Bcr, M::Error> {
+        Ok(Bcr::from_bits_truncate(self.read(Bcr::ADDRESS).await?))
+    }
+
+    /// Modify the Base Control Register of this PHY
+    async fn modify_bcr<F>(&mut self, f: F) -> Result<(), M::Error>
+    where
+        F: FnOnce(&mut Bcr),
+    {
+        let bcr = &mut self.bcr().await?;
+        f(bcr);
+        self.write(Bcr::ADDRESS, bcr.bits()).await
+    }
+
+    /// Check if the PHY is currently resetting
+    async fn is_resetting(&mut self) -> Result<bool, M::Error> {
+        Ok(self.bcr().await?.is_resetting())
+    }
+
+    /// Reset the PHY. Verify that the reset by checking
+    /// [`Self::is_resetting`] == false before continuing usage
+    async fn reset(&mut self) -> Result<(), M::Error> {
+        self.modify_bcr(|bcr| {
+            bcr.reset(true);
+        })
+        .await
+    }
+
+    /// Perform a reset, waiting until the reset is completed.
+    ///
+    /// This is the asynchronous version of
+    /// [`Phy::blocking_reset`](crate::Phy::blocking_reset).
+    async fn reset_and_wait(&mut self) -> Result<(), M::Error> {
+        self.reset().await?;
+        while self.is_resetting().await? {
+            yield_now().await;
+        }
+        Ok(())
+    }
+
+    /// Get the raw value of the Base Status Register of this PHY
+    async fn bsr(&mut self) -> Result<Bsr, M::Error> {
+        Ok(Bsr::from_bits_truncate(self.read(Bsr::ADDRESS).await?))
+    }
+
+    /// Check if the PHY reports its link as being up
+    async fn phy_link_up(&mut self) -> Result<bool, M::Error> {
+        Ok(self.bsr().await?.phy_link_up())
+    }
+
+    /// Check if the PHY reports its autonegotiation process
+    /// as having completed
+    async fn autoneg_completed(&mut self) -> Result<bool, M::Error> {
+        Ok(self.bsr().await?.autoneg_completed())
+    }
+
+    /// Wait until the PHY reports its autonegotiation process
+    /// as having completed
+    async fn wait_for_autoneg(&mut self) -> Result<(), M::Error> {
+        while !self.autoneg_completed().await? {
+            yield_now().await;
+        }
+        Ok(())
+    }
+
+    /// Wait until a link is established.
+    ///
+    /// By default, a link is established once autonegotiation has completed
+    /// and the PHY reports its link as being up.
+    async fn wait_for_link(&mut self) -> Result<(), M::Error> {
+        loop {
+            let bsr = self.bsr().await?;
+            if bsr.autoneg_completed() && bsr.phy_link_up() {
+                return Ok(());
+            }
+            yield_now().await;
+        }
+    }
+
+    /// Read the status register for this PHY
+    async fn status(&mut self) -> Result<PhyStatus, M::Error> {
+        let phy = self.get_phy_addr();
+        let abilities = self.get_miim().read_abilities(phy).await?;
+        Ok(Bsr::from_bits_truncate(abilities).into())
+    }
+
+    /// Read the ESR for this PHY. Will return `None` if
+    /// `extended_status` in [`Self::status`] is false.
+    async fn esr(&mut self) -> Result<Option<Esr>, M::Error> {
+        if self.status().await?.extended_status {
+            Ok(Some(Esr::from_bits_truncate(
+                self.read(Esr::ADDRESS).await?,
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Read the Extended Status Register for this PHY.
+    ///
+    /// Returns `None` if `extended_status` in [`Self::status`] is false.
+    async fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, M::Error> {
+        Ok(self.esr().await?.map(|esr| ExtendedPhyStatus {
+            fd_1000base_x: esr.contains(Esr::_1000BASEXFD),
+            hd_1000base_x: esr.contains(Esr::_1000BASEXHD),
+            fd_1000base_t: esr.contains(Esr::_1000BASETFD),
+            hd_1000base_t: esr.contains(Esr::_1000BASETHD),
+        }))
+    }
+
+    /// Read the PHY identifier for this PHY.
+    ///
+    /// Returns `None` if `extended_capabilities` in [`Self::status`] is false
+    async fn phy_ident(&mut self) -> Result<Option<PhyIdent>, M::Error> {
+        if self.status().await?.extended_caps {
+            let msb = self.read(2).await?;
+            let lsb = self.read(3).await?;
+            Ok(Some(PhyIdent::new(msb, lsb)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Set the autonegotiation advertisement and restarts the autonegotiation
+    /// process
+    ///
+    /// This is a no-op if `extended_caps` in [`Self::status`] is false
+    async fn set_autonegotiation_advertisement(
+        &mut self,
+        ad: AutoNegotiationAdvertisement,
+    ) -> Result<(), M::Error> {
+        let status = self.status().await?;
+        if !status.extended_caps {
+            return Ok(());
+        }
+
+        let ana = ad.local_cap(&status);
+        self.write(AutoNegCap::LOCAL_CAP_ADDRESS, ana.bits())
+            .await?;
+
+        if let Some(ext) = self.extended_status().await? {
+            if ext.fd_1000base_t || ext.hd_1000base_t {
+                let mut gb_ctrl = GbCtrl::from_bits_truncate(self.read(GbCtrl::ADDRESS).await?);
+                ad.update_gb_ctrl(&ext, &mut gb_ctrl);
+                self.write(GbCtrl::ADDRESS, gb_ctrl.bits()).await?;
+            }
+        }
+
+        self.modify_bcr(|bcr| {
+            bcr.set_autonegotiation(true).restart_autonegotiation();
+        })
+        .await
+    }
+
+    /// Get the advertised capabilities of this PHY
+    ///
+    /// This is a no-op if `extended_caps` in [`Self::status`] is false
+    async fn get_autonegotiation_caps(
+        &mut self,
+    ) -> Result<Option<AutoNegotiationAdvertisement>, M::Error> {
+        if !self.status().await?.extended_caps {
+            return Ok(None);
+        }
+        let ana = AutoNegCap::from_bits_truncate(self.read(AutoNegCap::LOCAL_CAP_ADDRESS).await?);
+        let mut ad: AutoNegotiationAdvertisement = ana.into();
+
+        if let Some(ext) = self.extended_status().await? {
+            if ext.fd_1000base_t || ext.hd_1000base_t {
+                let gb_ctrl = GbCtrl::from_bits_truncate(self.read(GbCtrl::ADDRESS).await?);
+                ad.fd_1000base_t = gb_ctrl.advertise_1000base_t_fd();
+                ad.hd_1000base_t = gb_ctrl.advertise_1000base_t_hd();
+            }
+        }
+
+        Ok(Some(ad))
+    }
+
+    /// Get the capabilites of the autonegotiation partner of this PHY
+    ///
+    /// This is a no-op if `extended_caps` in [`Self::status`] is false
+    async fn get_autonegotiation_partner_caps(
+        &mut self,
+    ) -> Result<Option<AutoNegotiationAdvertisement>, M::Error> {
+        if !self.status().await?.extended_caps {
+            return Ok(None);
+        }
+        let ana = AutoNegCap::from_bits_truncate(self.read(AutoNegCap::PARTNER_CAP_ADDRESS).await?);
+        let mut ad: AutoNegotiationAdvertisement = ana.into();
+
+        if let Some(ext) = self.extended_status().await? {
+            if ext.fd_1000base_t || ext.hd_1000base_t {
+                let gb_status = GbStatus::from_bits_truncate(self.read(GbStatus::ADDRESS).await?);
+                ad.fd_1000base_t = gb_status.partner_1000base_t_fd();
+                ad.hd_1000base_t = gb_status.partner_1000base_t_hd();
+            }
+        }
+
+        Ok(Some(ad))
+    }
+
+    /// Disable autonegotiation, and force the PHY to operate at `speed`.
+    ///
+    /// Returns [`ForceLinkError::Unsupported`](crate::ForceLinkError::Unsupported)
+    /// if [`Self::status`] and [`Self::extended_status`] report that the PHY does
+    /// not support `speed`, or if `speed` can not be forced (see
+    /// [`AdvancedPhySpeed::can_be_forced`](crate::phy::AdvancedPhySpeed::can_be_forced)).
+    #[cfg(feature = "phy")]
+    async fn force_link(
+        &mut self,
+        speed: crate::phy::AdvancedPhySpeed,
+    ) -> Result<(), crate::ForceLinkError<M::Error>> {
+        use crate::{Duplex, ForceLinkError, IllegalLinkSpeed};
+
+        if !speed.can_be_forced() {
+            return Err(ForceLinkError::Unsupported);
+        }
+
+        let status = self.status().await.map_err(ForceLinkError::Miim)?;
+        let ext = self.extended_status().await.map_err(ForceLinkError::Miim)?;
+        if !speed.is_supported(&status, ext.as_ref()) {
+            return Err(ForceLinkError::Unsupported);
+        }
+
+        let speed_sel =
+            Bcr::try_from(speed.speed()).map_err(|IllegalLinkSpeed| ForceLinkError::Unsupported)?;
+
+        self.modify_bcr(|bcr| {
+            bcr.remove(Bcr::SPEED_SEL_MSB | Bcr::SPEED_SEL_LSB);
+            bcr.insert(speed_sel);
+            bcr.set_autonegotiation(false)
+                .set_full_duplex(speed.duplex() == Duplex::Full);
+        })
+        .await
+        .map_err(ForceLinkError::Miim)
+    }
+
+    /// Resolve the outcome of autonegotiation from the local and link
+    /// partner advertisements, using [`AutoNegotiationAdvertisement::resolve`].
+    ///
+    /// Returns `None` if autonegotiation has not completed, or if there is
+    /// no common mode.
+    async fn resolve_autonegotiation(&mut self) -> Result<Option<AutoNegotiationResult>, M::Error> {
+        if !self.autoneg_completed().await? {
+            return Ok(None);
+        }
+
+        let local = self.get_autonegotiation_caps().await?;
+        let partner = self.get_autonegotiation_partner_caps().await?;
+
+        match (local, partner) {
+            (Some(local), Some(partner)) => Ok(local.resolve(&partner)),
+            _ => Ok(None),
+        }
+    }
+
+    /// This returns `None` if `extended_caps` in `Self::status` is `false`
+    async fn ane(&mut self) -> Result<Option<Ane>, M::Error> {
+        if self.status().await?.extended_caps {
+            Ok(Some(Ane::from_bits_truncate(
+                self.read(Ane::ADDRESS).await?,
+            )))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Read an MMD register
+    ///
+    /// This always uses the indirect access through registers 13 and 14.
+    #[cfg(feature = "mmd")]
+    async fn mmd_read(&mut self, device_address: u8, reg_address: u16) -> Result<u16, M::Error> {
+        use crate::mmd::MmdAddress;
+
+        let mut mmd_address = MmdAddress::device_address(device_address);
+        self.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits())
+            .await?;
+        self.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_address)
+            .await?;
+
+        mmd_address.remove(MmdAddress::ADDRESS);
+        mmd_address.insert(MmdAddress::DATA_NO_POSTINC);
+        self.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits())
+            .await?;
+        self.read(MmdAddress::DATA_ADRESS_ADDRESS).await
+    }
+
+    /// Write an MMD register
+    ///
+    /// This always uses the indirect access through registers 13 and 14.
+    #[cfg(feature = "mmd")]
+    async fn mmd_write(
+        &mut self,
+        device_address: u8,
+        reg_address: u16,
+        reg_value: u16,
+    ) -> Result<(), M::Error> {
+        use crate::mmd::MmdAddress;
+
+        let mut mmd_address = MmdAddress::device_address(device_address);
+        self.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits())
+            .await?;
+        self.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_address)
+            .await?;
+
+        mmd_address.remove(MmdAddress::ADDRESS);
+        mmd_address.insert(MmdAddress::DATA_NO_POSTINC);
+        self.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits())
+            .await?;
+        self.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_value).await
+    }
+}
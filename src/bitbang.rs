@@ -0,0 +1,374 @@
+//! A [`Miim`] implementation that bit-bangs the MDIO and MDC
+//! signals on [`embedded_hal`] GPIO pins.
+
+use embedded_hal::{
+    delay::DelayNs,
+    digital::{Error as _, ErrorKind, InputPin, OutputPin},
+};
+
+use crate::{Clause45Miim, Miim};
+
+/// An error that occured while bit-banging a management frame.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitBangError {
+    /// Setting or reading one of the pins failed
+    Pin(ErrorKind),
+}
+
+/// A [`Miim`] that generates management frames by toggling GPIO pins.
+///
+/// `MDIO` must be an open-drain pin that can be read back: setting it high
+/// releases the line so that the PHY can drive it.
+///
+/// Like the MDIO controllers of most MACs, reads from an address at which no
+/// PHY responds return `0xFFFF`, the value of the pulled-up MDIO line.
+///
+/// This type also implements [`Clause45Miim`].
+#[derive(Debug)]
+pub struct BitBangMiim<MDC, MDIO, D> {
+    mdc: MDC,
+    mdio: MDIO,
+    delay: D,
+    half_period_ns: u32,
+    preamble_suppression: bool,
+}
+
+impl<MDC, MDIO, D> BitBangMiim<MDC, MDIO, D>
+where
+    MDC: OutputPin,
+    MDIO: OutputPin + InputPin,
+    D: DelayNs,
+{
+    /// The default half period of MDC, resulting in a clock of 2.5 MHz,
+    /// the maximum allowed by IEEE 802.3.
+    pub const DEFAULT_HALF_PERIOD_NS: u32 = 200;
+
+    const OP_C45_ADDRESS: u8 = 0b00;
+    const OP_WRITE: u8 = 0b01;
+    const OP_READ: u8 = 0b10;
+    const OP_C45_READ: u8 = 0b11;
+
+    const ST_C22: u8 = 0b01;
+    const ST_C45: u8 = 0b00;
+
+    /// Create a new bit-banged MIIM.
+    ///
+    /// This drives MDC low and releases MDIO.
+    pub fn new(mut mdc: MDC, mut mdio: MDIO, delay: D) -> Result<Self, BitBangError> {
+        mdc.set_low().map_err(|e| BitBangError::Pin(e.kind()))?;
+        mdio.set_high().map_err(|e| BitBangError::Pin(e.kind()))?;
+
+        Ok(Self {
+            mdc,
+            mdio,
+            delay,
+            half_period_ns: Self::DEFAULT_HALF_PERIOD_NS,
+            preamble_suppression: false,
+        })
+    }
+
+    /// Set the half period of MDC, in nanoseconds.
+    pub fn set_half_period_ns(&mut self, half_period_ns: u32) {
+        self.half_period_ns = half_period_ns;
+    }
+
+    /// Enable or disable preamble suppression.
+    ///
+    /// This may only be enabled if all PHYs on the bus report
+    /// [`PhyStatus::preamble_suppression`](crate::PhyStatus::preamble_suppression).
+    pub fn set_preamble_suppression(&mut self, preamble_suppression: bool) {
+        self.preamble_suppression = preamble_suppression;
+    }
+
+    /// Release the pins and delay used by this MIIM
+    pub fn release(self) -> (MDC, MDIO, D) {
+        (self.mdc, self.mdio, self.delay)
+    }
+
+    fn mdc(&mut self, high: bool) -> Result<(), BitBangError> {
+        let result = if high {
+            self.mdc.set_high()
+        } else {
+            self.mdc.set_low()
+        };
+        result.map_err(|e| BitBangError::Pin(e.kind()))
+    }
+
+    fn send_bit(&mut self, bit: bool) -> Result<(), BitBangError> {
+        let result = if bit {
+            self.mdio.set_high()
+        } else {
+            self.mdio.set_low()
+        };
+        result.map_err(|e| BitBangError::Pin(e.kind()))?;
+
+        self.delay.delay_ns(self.half_period_ns);
+        self.mdc(true)?;
+        self.delay.delay_ns(self.half_period_ns);
+        self.mdc(false)
+    }
+
+    fn get_bit(&mut self) -> Result<bool, BitBangError> {
+        self.delay.delay_ns(self.half_period_ns);
+        self.mdc(true)?;
+        self.delay.delay_ns(self.half_period_ns);
+        self.mdc(false)?;
+        self.mdio.is_high().map_err(|e| BitBangError::Pin(e.kind()))
+    }
+
+    fn send_bits(&mut self, value: u16, bits: u8) -> Result<(), BitBangError> {
+        for bit in (0..bits).rev() {
+            self.send_bit(value & (1 << bit) != 0)?;
+        }
+        Ok(())
+    }
+
+    fn release_mdio(&mut self) -> Result<(), BitBangError> {
+        self.mdio
+            .set_high()
+            .map_err(|e| BitBangError::Pin(e.kind()))
+    }
+
+    /// Send the preamble, start, opcode and both 5-bit address fields of a frame
+    fn send_header(&mut self, st: u8, op: u8, phy: u8, reg: u8) -> Result<(), BitBangError> {
+        if !self.preamble_suppression {
+            self.send_bits(0xFFFF, 16)?;
+            self.send_bits(0xFFFF, 16)?;
+        }
+
+        self.send_bits(st as u16, 2)?;
+        self.send_bits(op as u16, 2)?;
+        self.send_bits(phy as u16, 5)?;
+        self.send_bits(reg as u16, 5)
+    }
+
+    /// Receive the turnaround and data of a read frame
+    fn receive_data(&mut self) -> Result<u16, BitBangError> {
+        self.release_mdio()?;
+
+        // The PHY drives the second turnaround bit low. If it doesn't, no
+        // PHY is present and MDIO stays pulled high.
+        let responded = !self.get_bit()?;
+
+        let mut data = 0;
+        for _ in 0..16 {
+            data = (data << 1) | self.get_bit()? as u16;
+        }
+
+        // Idle
+        self.get_bit()?;
+
+        if responded {
+            Ok(data)
+        } else {
+            Ok(0xFFFF)
+        }
+    }
+
+    /// Send the turnaround and data of a write frame
+    fn send_data(&mut self, data: u16) -> Result<(), BitBangError> {
+        self.send_bits(0b10, 2)?;
+        self.send_bits(data, 16)?;
+
+        self.release_mdio()?;
+        // Idle
+        self.get_bit()?;
+        Ok(())
+    }
+}
+
+impl<MDC, MDIO, D> Miim for BitBangMiim<MDC, MDIO, D>
+where
+    MDC: OutputPin,
+    MDIO: OutputPin + InputPin,
+    D: DelayNs,
+{
+    type Error = BitBangError;
+
+    fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error> {
+        self.send_header(Self::ST_C22, Self::OP_READ, phy, reg)?;
+        self.receive_data()
+    }
+
+    fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error> {
+        self.send_header(Self::ST_C22, Self::OP_WRITE, phy, reg)?;
+        self.send_data(data)
+    }
+
+    fn clause45(&mut self) -> Option<&mut dyn Clause45Miim<Error = Self::Error>> {
+        Some(self)
+    }
+}
+
+impl<MDC, MDIO, D> Clause45Miim for BitBangMiim<MDC, MDIO, D>
+where
+    MDC: OutputPin,
+    MDIO: OutputPin + InputPin,
+    D: DelayNs,
+{
+    type Error = BitBangError;
+
+    fn read_c45(&mut self, port: u8, devad: u8, reg: u16) -> Result<u16, Self::Error> {
+        self.send_header(Self::ST_C45, Self::OP_C45_ADDRESS, port, devad)?;
+        self.send_data(reg)?;
+
+        self.send_header(Self::ST_C45, Self::OP_C45_READ, port, devad)?;
+        self.receive_data()
+    }
+
+    fn write_c45(&mut self, port: u8, devad: u8, reg: u16, data: u16) -> Result<(), Self::Error> {
+        self.send_header(Self::ST_C45, Self::OP_C45_ADDRESS, port, devad)?;
+        self.send_data(reg)?;
+
+        self.send_header(Self::ST_C45, Self::OP_WRITE, port, devad)?;
+        self.send_data(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use core::convert::Infallible;
+
+    use embedded_hal::digital::ErrorType;
+
+    use super::*;
+    use crate::{scan_bus, PhyIdent};
+
+    /// An MDIO bus with a single PHY, whose identifier registers can be read
+    #[derive(Default)]
+    struct Bus {
+        /// The level that the MAC drives
+        mac: Cell<bool>,
+        /// The PHY drives the line low
+        phy_low: Cell<bool>,
+        /// The amount of consecutive ones sent by the MAC
+        ones: Cell<u32>,
+        /// The amount and value of the header bits received after the preamble
+        header: Cell<Option<(u8, u16)>>,
+        /// The amount of bits of a read frame that the PHY has sent, and the data
+        reply: Cell<Option<(u8, u16)>>,
+    }
+
+    impl Bus {
+        const PHY: u8 = 5;
+
+        fn register(reg: u8) -> u16 {
+            match reg {
+                2 => 0x0007,
+                3 => 0xC0F1,
+                _ => 0,
+            }
+        }
+
+        /// A rising edge of MDC
+        fn clock(&self) {
+            let bit = self.mac.get();
+            self.phy_low.set(false);
+
+            if let Some((sent, data)) = self.reply.get() {
+                // The turnaround bit is low, followed by the data
+                let low = sent == 0 || data & (1 << (16 - sent)) == 0;
+                self.phy_low.set(low);
+                self.reply.set((sent < 16).then_some((sent + 1, data)));
+                return;
+            }
+
+            if let Some((len, value)) = self.header.get() {
+                let (len, value) = (len + 1, (value << 1) | bit as u16);
+                self.header.set((len < 14).then_some((len, value)));
+
+                // Start, opcode, PHY address and register address
+                let read = value >> 10 == 0b0110;
+                let phy = (value >> 5) as u8 & 0x1F;
+                if len == 14 && read && phy == Self::PHY {
+                    let reg = value as u8 & 0x1F;
+                    self.reply.set(Some((0, Self::register(reg))));
+                }
+                return;
+            }
+
+            if bit {
+                self.ones.set(self.ones.get() + 1);
+            } else {
+                // The first bit of the start of frame
+                if self.ones.get() >= 32 {
+                    self.header.set(Some((1, 0)));
+                }
+                self.ones.set(0);
+            }
+        }
+    }
+
+    struct Mdc<'a>(&'a Bus);
+    struct Mdio<'a>(&'a Bus);
+    struct NoDelay;
+
+    impl ErrorType for Mdc<'_> {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Mdc<'_> {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.0.clock();
+            Ok(())
+        }
+    }
+
+    impl ErrorType for Mdio<'_> {
+        type Error = Infallible;
+    }
+
+    impl OutputPin for Mdio<'_> {
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            self.0.mac.set(false);
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            self.0.mac.set(true);
+            Ok(())
+        }
+    }
+
+    impl InputPin for Mdio<'_> {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(self.0.mac.get() && !self.0.phy_low.get())
+        }
+
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    impl DelayNs for NoDelay {
+        fn delay_ns(&mut self, _: u32) {}
+    }
+
+    #[test]
+    fn absent_phys_read_as_all_ones() {
+        let bus = Bus::default();
+        let mut miim = BitBangMiim::new(Mdc(&bus), Mdio(&bus), NoDelay).unwrap();
+
+        assert_eq!(miim.read(Bus::PHY, 2), Ok(0x0007));
+        assert_eq!(miim.read(Bus::PHY + 1, 2), Ok(0xFFFF));
+        assert_eq!(miim.read(Bus::PHY, 3), Ok(0xC0F1));
+    }
+
+    #[test]
+    fn scan_skips_addresses_that_do_not_respond() {
+        let bus = Bus::default();
+        let mut miim = BitBangMiim::new(Mdc(&bus), Mdio(&bus), NoDelay).unwrap();
+
+        let result = scan_bus(&mut miim).unwrap();
+        assert_eq!(result.len(), 1);
+        let phy = result.get(0).unwrap();
+        assert_eq!(phy.address, Bus::PHY);
+        assert_eq!(phy.ident, PhyIdent::new(0x0007, 0xC0F1));
+    }
+}
@@ -0,0 +1,230 @@
+//! Reset and bring-up of a PHY with bounded waiting times.
+//!
+//! All functions in this module take a `delay_us` callback, which must wait
+//! for (at least) the given amount of microseconds. With `embedded-hal`, this
+//! can be `|us| delay.delay_us(us)`. Without a timer, a callback that counts
+//! ticks or spins for an approximate amount of time can be used instead.
+
+use crate::{AutoNegotiationAdvertisement, Miim, Phy};
+
+/// The waiting times used while bringing up a PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeouts {
+    /// The time to wait after initiating a reset before the PHY is
+    /// accessed again. Some PHYs do not respond to management frames
+    /// while they are resetting.
+    pub reset_settle_us: u32,
+    /// The maximum time that a reset may take.
+    pub reset_us: u32,
+    /// The maximum time that autonegotiation may take.
+    pub autoneg_us: u32,
+    /// The maximum time to wait for the link to come up.
+    pub link_us: u32,
+    /// The interval at which the PHY is polled.
+    pub poll_interval_us: u32,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            // Most PHYs ignore management frames for up to a few hundred
+            // microseconds after a reset is initiated
+            reset_settle_us: 500,
+            // IEEE 802.3 22.2.4.1.1: a reset completes within 0.5 s
+            reset_us: 500_000,
+            autoneg_us: 5_000_000,
+            link_us: 1_000_000,
+            poll_interval_us: 10_000,
+        }
+    }
+}
+
+/// The step of the bring-up sequence that timed out
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timeout {
+    /// The PHY did not clear the reset bit
+    ResetStuck,
+    /// A link partner was detected, but autonegotiation did not complete
+    AutonegNotCompleted,
+    /// No link partner was detected
+    NoLinkPartner,
+}
+
+/// An error that can occur while bringing up a PHY
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BringUpError<E> {
+    /// A step of the bring-up sequence timed out
+    Timeout(Timeout),
+    /// An error occured while accessing the PHY
+    Miim(E),
+}
+
+impl<E> From<Timeout> for BringUpError<E> {
+    fn from(timeout: Timeout) -> Self {
+        Self::Timeout(timeout)
+    }
+}
+
+/// Poll `done` every `poll_interval_us` until it returns `true`, or until
+/// `timeout_us` has elapsed.
+///
+/// Returns whether `done` returned `true`.
+fn poll_until<E>(
+    timeout_us: u32,
+    poll_interval_us: u32,
+    delay_us: &mut impl FnMut(u32),
+    mut done: impl FnMut() -> Result<bool, E>,
+) -> Result<bool, BringUpError<E>> {
+    let mut elapsed = 0u32;
+    loop {
+        if done().map_err(BringUpError::Miim)? {
+            return Ok(true);
+        }
+
+        if elapsed >= timeout_us {
+            return Ok(false);
+        }
+
+        let interval = poll_interval_us.clamp(1, timeout_us - elapsed);
+        delay_us(interval);
+        elapsed += interval;
+    }
+}
+
+/// Reset `phy`, and wait for the reset to complete.
+pub fn reset<M: Miim, P: Phy<M>>(
+    phy: &mut P,
+    delay_us: &mut impl FnMut(u32),
+    timeouts: &Timeouts,
+) -> Result<(), BringUpError<M::Error>> {
+    phy.reset().map_err(BringUpError::Miim)?;
+
+    if timeouts.reset_settle_us > 0 {
+        delay_us(timeouts.reset_settle_us);
+    }
+
+    let done = poll_until(
+        timeouts.reset_us,
+        timeouts.poll_interval_us,
+        delay_us,
+        || phy.is_resetting().map(|resetting| !resetting),
+    )?;
+
+    if done {
+        Ok(())
+    } else {
+        Err(Timeout::ResetStuck.into())
+    }
+}
+
+/// Wait for autonegotiation of `phy` to complete.
+///
+/// On timeout, this returns [`Timeout::AutonegNotCompleted`] if the link
+/// partner was detected, and [`Timeout::NoLinkPartner`] otherwise.
+pub fn wait_for_autoneg<M: Miim, P: Phy<M>>(
+    phy: &mut P,
+    delay_us: &mut impl FnMut(u32),
+    timeouts: &Timeouts,
+) -> Result<(), BringUpError<M::Error>> {
+    let done = poll_until(
+        timeouts.autoneg_us,
+        timeouts.poll_interval_us,
+        delay_us,
+        || phy.autoneg_completed(),
+    )?;
+
+    if done {
+        return Ok(());
+    }
+
+    let partner_detected = phy
+        .ane()
+        .map_err(BringUpError::Miim)?
+        .map(|ane| ane.partner_autoneg_capable() || ane.page_received())
+        .unwrap_or(false);
+
+    if partner_detected {
+        Err(Timeout::AutonegNotCompleted.into())
+    } else {
+        Err(Timeout::NoLinkPartner.into())
+    }
+}
+
+/// Wait for the link of `phy` to come up.
+///
+/// On timeout, this returns [`Timeout::NoLinkPartner`].
+pub fn wait_for_link<M: Miim, P: Phy<M>>(
+    phy: &mut P,
+    delay_us: &mut impl FnMut(u32),
+    timeouts: &Timeouts,
+) -> Result<(), BringUpError<M::Error>> {
+    let done = poll_until(
+        timeouts.link_us,
+        timeouts.poll_interval_us,
+        delay_us,
+        || phy.phy_link_up(),
+    )?;
+
+    if done {
+        Ok(())
+    } else {
+        Err(Timeout::NoLinkPartner.into())
+    }
+}
+
+/// Reset `phy`, advertise `ad` (or [`Phy::best_supported_advertisement`] if it is
+/// `None`), and wait for autonegotiation to complete and the link to come up.
+pub fn bring_up<M: Miim, P: Phy<M>>(
+    phy: &mut P,
+    ad: Option<AutoNegotiationAdvertisement>,
+    delay_us: &mut impl FnMut(u32),
+    timeouts: &Timeouts,
+) -> Result<(), BringUpError<M::Error>> {
+    reset(phy, delay_us, timeouts)?;
+
+    let ad = ad.unwrap_or_else(|| phy.best_supported_advertisement());
+    phy.set_autonegotiation_advertisement(ad)
+        .map_err(BringUpError::Miim)?;
+
+    wait_for_autoneg(phy, delay_us, timeouts)?;
+    wait_for_link(phy, delay_us, timeouts)
+}
+
+#[cfg(all(test, feature = "phy"))]
+mod tests {
+    use super::*;
+    use crate::phy::BarePhy;
+    use crate::sim::{SimChip, SimMiim, SimulatedPhy};
+    use crate::Pause;
+
+    #[test]
+    fn reset_waits_before_polling() {
+        let mut sim = SimulatedPhy::for_chip(SimChip::Generic);
+        sim.set_reset_polls(Some(2));
+        let mut miim = SimMiim::<1>::new();
+        miim.add(0, sim);
+        let mut phy = BarePhy::new(miim, 0, Pause::NoPause).unwrap();
+
+        let timeouts = Timeouts::default();
+        let mut delays = [0; 4];
+        let mut len = 0;
+        let mut delay_us = |us| {
+            delays[len] = us;
+            len += 1;
+        };
+        reset(&mut phy, &mut delay_us, &timeouts).unwrap();
+
+        assert!(timeouts.reset_settle_us > 0);
+        assert_eq!(
+            delays[..len],
+            [
+                timeouts.reset_settle_us,
+                timeouts.poll_interval_us,
+                timeouts.poll_interval_us
+            ]
+        );
+    }
+}
@@ -0,0 +1,471 @@
+//! Caching of Clause 22 registers to reduce MDIO traffic.
+
+use crate::registers::{Bcr, Bsr};
+use crate::{Clause45Miim, Miim};
+
+/// The registers that a [`CachedMiim`] caches, in the order in which
+/// deferred writes are flushed.
+///
+/// Registers 2 and 3 (the PHY identifier) and 15 (the ESR) are static.
+/// Registers 4 and 9 (the advertisement registers) and 0 (the BCR) only
+/// change when they are written, or when the PHY is reset. The BCR is
+/// flushed last, so that a restart of autonegotiation uses the new
+/// advertisement.
+const CACHED_REGS: [u8; 6] = [2, 3, 15, 4, 9, 0];
+
+/// The bits of the BCR that the PHY clears by itself
+const SELF_CLEARING: Bcr = Bcr::from_bits_truncate(Bcr::RESET.bits() | Bcr::RESTART_AUTONEG.bits());
+
+fn slot(reg: u8) -> Option<usize> {
+    CACHED_REGS.iter().position(|r| *r == reg)
+}
+
+fn is_shadowed(reg: u8) -> bool {
+    matches!(reg, 0 | 4 | 9)
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct CacheEntry {
+    values: [Option<u16>; CACHED_REGS.len()],
+    /// A bit for each slot whose value has not been written to the PHY yet
+    dirty: u8,
+    /// The [`Bsr::ABILITIES`] bits of the BSR
+    abilities: Option<u16>,
+}
+
+impl CacheEntry {
+    fn invalidate_shadowed(&mut self) {
+        for (slot, reg) in CACHED_REGS.iter().enumerate() {
+            if is_shadowed(*reg) {
+                self.values[slot] = None;
+                self.dirty &= !(1 << slot);
+            }
+        }
+    }
+}
+
+/// A [`Miim`] that caches the Clause 22 registers of up to `N` PHYs.
+///
+/// Reads of the PHY identifier registers (2, 3) and the ESR (15) are served
+/// from the cache after the first read, as they never change. The BCR (0)
+/// and the advertisement registers (4, 9) are shadowed: the last value
+/// that was read or written is returned without accessing the PHY, which
+/// turns a read-modify-write into a single write. All other registers,
+/// including the BSR and the vendor-specific interrupt and status
+/// registers, are always read from the PHY. The static ability bits of the
+/// BSR are cached by every read of the BSR, and are returned by
+/// [`Miim::read_abilities`], which the default [`Phy::status`] uses.
+///
+/// Writing `RESET` to the BCR invalidates the shadowed registers of that
+/// PHY, and values of the BCR with a self-clearing bit set are never cached.
+/// If a PHY is reset in another way (e.g. using a reset pin), call
+/// [`CachedMiim::invalidate`].
+///
+/// With [`CachedMiim::set_deferred`], writes to the shadowed registers are
+/// held back until [`CachedMiim::flush`] is called, so that multiple
+/// modifications of a register result in a single write. To preserve
+/// ordering, pending writes are flushed before any other write, and before
+/// a write that sets a self-clearing bit in the BCR. Reads of registers that
+/// are not shadowed do not flush pending writes.
+///
+/// PHYs at addresses beyond the first `N` that are accessed are not cached.
+/// Clause 45 frames are forwarded to the wrapped [`Miim`] without caching.
+/// Pending writes are flushed before a Clause 45 write.
+///
+/// [`Phy::status`]: crate::Phy::status
+#[derive(Debug)]
+pub struct CachedMiim<M, const N: usize = 1> {
+    miim: M,
+    entries: [Option<(u8, CacheEntry)>; N],
+    deferred: bool,
+}
+
+impl<M, const N: usize> CachedMiim<M, N> {
+    /// Create a new cache in front of `miim`
+    pub fn new(miim: M) -> Self {
+        Self {
+            miim,
+            entries: [None; N],
+            deferred: false,
+        }
+    }
+
+    /// Defer writes to the shadowed registers until [`Self::flush`] is called.
+    ///
+    /// Disabling deferred writes does not flush pending writes.
+    pub fn set_deferred(&mut self, deferred: bool) {
+        self.deferred = deferred;
+    }
+
+    /// Check if writes to the shadowed registers are deferred
+    pub fn deferred(&self) -> bool {
+        self.deferred
+    }
+
+    /// Check if there are writes that have not been flushed yet
+    pub fn has_pending_writes(&self) -> bool {
+        self.entries
+            .iter()
+            .flatten()
+            .any(|(_, entry)| entry.dirty != 0)
+    }
+
+    /// Drop all cached values of the PHY at `phy`, including pending writes.
+    pub fn invalidate(&mut self, phy: u8) {
+        if let Some(entry) = self.entry(phy) {
+            *entry = CacheEntry::default();
+        }
+    }
+
+    /// Drop all cached values, including pending writes.
+    pub fn invalidate_all(&mut self) {
+        self.entries = [None; N];
+    }
+
+    /// Get a mutable reference to the wrapped [`Miim`].
+    ///
+    /// Accesses through the returned reference bypass the cache.
+    pub fn inner_mut(&mut self) -> &mut M {
+        &mut self.miim
+    }
+
+    /// Release the wrapped [`Miim`].
+    ///
+    /// Pending writes are discarded, call [`Self::flush`] first.
+    pub fn release(self) -> M {
+        self.miim
+    }
+
+    fn entry(&mut self, phy: u8) -> Option<&mut CacheEntry> {
+        self.entries
+            .iter_mut()
+            .flatten()
+            .find(|(addr, _)| *addr == phy)
+            .map(|(_, entry)| entry)
+    }
+
+    fn entry_or_insert(&mut self, phy: u8) -> Option<&mut CacheEntry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|e| matches!(e, Some((addr, _)) if *addr == phy))
+            .or_else(|| self.entries.iter().position(Option::is_none))?;
+
+        let (_, entry) = self.entries[index].get_or_insert((phy, CacheEntry::default()));
+        Some(entry)
+    }
+}
+
+impl<M: Miim, const N: usize> CachedMiim<M, N> {
+    /// Write all pending writes to the PHYs.
+    ///
+    /// The writes of each PHY are flushed in the order: advertisement
+    /// registers, then the BCR.
+    pub fn flush(&mut self) -> Result<(), M::Error> {
+        for (phy, entry) in self.entries.iter_mut().flatten() {
+            for (slot, reg) in CACHED_REGS.iter().enumerate() {
+                if entry.dirty & (1 << slot) == 0 {
+                    continue;
+                }
+
+                if let Some(value) = entry.values[slot] {
+                    self.miim.write(*phy, *reg, value)?;
+                }
+                entry.dirty &= !(1 << slot);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<M: Miim, const N: usize> Miim for CachedMiim<M, N> {
+    type Error = M::Error;
+
+    fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error> {
+        let slot = match slot(reg) {
+            Some(slot) => slot,
+            None if reg == Bsr::ADDRESS => {
+                let value = self.miim.read(phy, reg)?;
+                if let Some(entry) = self.entry_or_insert(phy) {
+                    entry.abilities = Some(value & Bsr::ABILITIES.bits());
+                }
+                return Ok(value);
+            }
+            None => return self.miim.read(phy, reg),
+        };
+
+        if let Some(value) = self.entry(phy).and_then(|e| e.values[slot]) {
+            return Ok(value);
+        }
+
+        let value = self.miim.read(phy, reg)?;
+
+        let cacheable =
+            reg != Bcr::ADDRESS || !Bcr::from_bits_truncate(value).intersects(SELF_CLEARING);
+        if cacheable {
+            if let Some(entry) = self.entry_or_insert(phy) {
+                entry.values[slot] = Some(value);
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error> {
+        let slot = match slot(reg) {
+            Some(slot) if is_shadowed(reg) => slot,
+            // Registers that are not cached, and the read-only identifier
+            // registers and ESR
+            _ => {
+                self.flush()?;
+                return self.miim.write(phy, reg, data);
+            }
+        };
+
+        let bcr = (reg == Bcr::ADDRESS).then(|| Bcr::from_bits_truncate(data));
+        let self_clearing = matches!(bcr, Some(bcr) if bcr.intersects(SELF_CLEARING));
+
+        if self.deferred && !self_clearing {
+            if let Some(entry) = self.entry_or_insert(phy) {
+                entry.values[slot] = Some(data);
+                entry.dirty |= 1 << slot;
+                return Ok(());
+            }
+        }
+
+        self.flush()?;
+        self.miim.write(phy, reg, data)?;
+
+        if let Some(entry) = self.entry_or_insert(phy) {
+            if matches!(bcr, Some(bcr) if bcr.is_resetting()) {
+                entry.invalidate_shadowed();
+            } else if self_clearing {
+                entry.values[slot] = None;
+            } else {
+                entry.values[slot] = Some(data);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_abilities(&mut self, phy: u8) -> Result<u16, Self::Error> {
+        match self.entry(phy).and_then(|e| e.abilities) {
+            Some(abilities) => Ok(abilities),
+            None => Ok(self.read(phy, Bsr::ADDRESS)? & Bsr::ABILITIES.bits()),
+        }
+    }
+
+    fn clause45(&mut self) -> Option<&mut dyn Clause45Miim<Error = Self::Error>> {
+        self.miim.clause45()?;
+        Some(self)
+    }
+}
+
+/// # Panics
+/// The methods of this trait panic if the wrapped [`Miim`] does not support
+/// Clause 45 frames. [`Miim::clause45`] only returns this implementation if
+/// it does.
+impl<M: Miim, const N: usize> Clause45Miim for CachedMiim<M, N> {
+    type Error = M::Error;
+
+    fn read_c45(&mut self, port: u8, devad: u8, reg: u16) -> Result<u16, Self::Error> {
+        self.miim
+            .clause45()
+            .expect("The wrapped Miim does not support Clause 45 frames")
+            .read_c45(port, devad, reg)
+    }
+
+    fn write_c45(&mut self, port: u8, devad: u8, reg: u16, data: u16) -> Result<(), Self::Error> {
+        self.flush()?;
+        self.miim
+            .clause45()
+            .expect("The wrapped Miim does not support Clause 45 frames")
+            .write_c45(port, devad, reg, data)
+    }
+}
+
+#[cfg(all(test, feature = "phy"))]
+mod tests {
+    use super::*;
+    use crate::phy::BarePhy;
+    use crate::sim::{SimMiim, SimulatedPhy};
+    use crate::trace::{Direction, RecordingMiim, TraceBuffer, Transaction};
+    use crate::{AutoNegotiationAdvertisement, ExtendedPhyStatus, Pause, Phy};
+
+    const ADDR: u8 = 1;
+
+    /// A PHY that supports 1000BASE-T, so that it has the GbCtrl register
+    fn sim() -> SimMiim {
+        let bsr = Bsr::_100BASEXFD
+            | Bsr::_100BASEXHD
+            | Bsr::_10MPBSFD
+            | Bsr::_10MBPSHD
+            | Bsr::EXTENDED_STATUS
+            | Bsr::AUTONEG_ABLE
+            | Bsr::EXTENDED_CAPABILITIES;
+        let ext = ExtendedPhyStatus {
+            fd_1000base_x: false,
+            hd_1000base_x: false,
+            fd_1000base_t: true,
+            hd_1000base_t: true,
+        };
+
+        let mut miim = SimMiim::<1>::new();
+        miim.add(ADDR, SimulatedPhy::new(0x0007_C0F0, bsr.into(), Some(ext)));
+        miim
+    }
+
+    fn new_trace() -> [Transaction; 32] {
+        [Transaction::read(0, 0, 0); 32]
+    }
+
+    /// Query the static registers of the PHY twice
+    fn query<P: Phy<M>, M: Miim>(phy: &mut P) {
+        for _ in 0..2 {
+            phy.status().unwrap();
+            phy.esr().unwrap();
+            phy.phy_ident().unwrap();
+            phy.ane().unwrap();
+        }
+    }
+
+    fn writes<'a>(log: &'a TraceBuffer<'_>) -> impl Iterator<Item = u16> + 'a {
+        log.iter()
+            .filter(|t| t.direction == Direction::Write && t.devad.is_none())
+            .map(|t| t.reg)
+    }
+
+    #[test]
+    fn static_registers_are_read_once() {
+        let mut trace = new_trace();
+        let miim = RecordingMiim::new(sim(), TraceBuffer::new(&mut trace));
+        let mut phy = BarePhy::new(miim, ADDR, Pause::NoPause).unwrap();
+        phy.get_miim().log_mut().clear();
+        query(&mut phy);
+        let uncached = phy.get_miim().log().len();
+
+        let mut trace = new_trace();
+        let miim = RecordingMiim::new(sim(), TraceBuffer::new(&mut trace));
+        let mut phy = BarePhy::new(CachedMiim::<_>::new(miim), ADDR, Pause::NoPause).unwrap();
+        phy.get_miim().inner_mut().log_mut().clear();
+        query(&mut phy);
+        let log = phy.get_miim().inner_mut().log();
+
+        // The BSR and ESR were cached by `BarePhy::new`, the identifier is
+        // read once, and the ANE every time
+        assert!(log.len() < uncached);
+        assert!(log.iter().map(|t| t.reg).eq([2, 3, 6, 6]));
+    }
+
+    #[test]
+    fn recording_miim_forwards_the_abilities() {
+        let mut trace = new_trace();
+        let bus = RecordingMiim::new(sim(), TraceBuffer::new(&mut trace));
+        let miim = RecordingMiim::new(CachedMiim::<_>::new(bus), |_| {});
+        let mut phy = BarePhy::new(miim, ADDR, Pause::NoPause).unwrap();
+
+        let bus = phy.get_miim().inner_mut().inner_mut();
+        bus.log_mut().clear();
+        query(&mut phy);
+
+        let bus = phy.get_miim().inner_mut().inner_mut();
+        assert!(bus.log().iter().map(|t| t.reg).eq([2, 3, 6, 6]));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn async_status_uses_the_cached_abilities() {
+        use crate::AsyncPhy;
+        use core::future::Future;
+        use core::task::{Context, Poll, Waker};
+
+        fn block_on<F: Future>(future: F) -> F::Output {
+            let mut future = core::pin::pin!(future);
+            let mut cx = Context::from_waker(Waker::noop());
+            loop {
+                if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                    return output;
+                }
+            }
+        }
+
+        let mut trace = new_trace();
+        let bus = RecordingMiim::new(sim(), TraceBuffer::new(&mut trace));
+        let mut phy = block_on(BarePhy::new_async(
+            CachedMiim::<_>::new(bus),
+            ADDR,
+            Pause::NoPause,
+        ))
+        .unwrap();
+        AsyncPhy::get_miim(&mut phy).inner_mut().log_mut().clear();
+
+        for _ in 0..2 {
+            block_on(AsyncPhy::status(&mut phy)).unwrap();
+        }
+        assert!(AsyncPhy::get_miim(&mut phy).inner_mut().log().is_empty());
+    }
+
+    #[test]
+    fn deferred_writes_are_flushed_in_order() {
+        let mut trace = new_trace();
+        let mut miim =
+            CachedMiim::<_>::new(RecordingMiim::new(sim(), TraceBuffer::new(&mut trace)));
+        miim.set_deferred(true);
+
+        miim.write(ADDR, 0, Bcr::AUTONEG_ENABLE.bits()).unwrap();
+        miim.write(ADDR, 9, 0x0300).unwrap();
+        miim.write(ADDR, 4, 0x01E1).unwrap();
+        assert!(miim.has_pending_writes());
+        assert_eq!(miim.inner_mut().log().len(), 0);
+
+        miim.flush().unwrap();
+        assert!(!miim.has_pending_writes());
+        assert!(writes(miim.inner_mut().log()).eq([4, 9, 0]));
+    }
+
+    #[test]
+    fn advertisement_is_written_before_the_bcr() {
+        let mut trace = new_trace();
+        let miim = RecordingMiim::new(sim(), TraceBuffer::new(&mut trace));
+        let mut phy = BarePhy::new(CachedMiim::<_>::new(miim), ADDR, Pause::NoPause).unwrap();
+        phy.get_miim().set_deferred(true);
+
+        let ad = AutoNegotiationAdvertisement {
+            fd_100base_tx: true,
+            fd_1000base_t: true,
+            ..Default::default()
+        };
+        phy.set_autonegotiation_advertisement(ad).unwrap();
+
+        // Restarting autonegotiation flushes the advertisement first
+        let miim = phy.get_miim();
+        assert!(!miim.has_pending_writes());
+        assert!(writes(miim.inner_mut().log()).eq([4, 9, 0]));
+    }
+
+    #[test]
+    fn clause45_writes_flush_pending_writes() {
+        let mut trace = new_trace();
+        let mut sim = sim();
+        sim.set_clause45(true);
+        let mut miim = CachedMiim::<_>::new(RecordingMiim::new(sim, TraceBuffer::new(&mut trace)));
+        miim.set_deferred(true);
+
+        miim.write(ADDR, 4, 0x01E1).unwrap();
+        miim.clause45().unwrap().read_c45(ADDR, 3, 0x8010).unwrap();
+        assert!(miim.has_pending_writes());
+
+        miim.clause45()
+            .unwrap()
+            .write_c45(ADDR, 3, 0x8010, 0x1234)
+            .unwrap();
+        assert!(!miim.has_pending_writes());
+
+        let log = miim.inner_mut().log();
+        let writes = log.iter().filter(|t| t.direction == Direction::Write);
+        assert!(writes.copied().eq([
+            Transaction::write(ADDR, 4, 0x01E1),
+            Transaction::write_c45(ADDR, 3, 0x8010, 0x1234),
+        ]));
+    }
+}
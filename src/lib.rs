@@ -6,21 +6,56 @@
 
 mod miim;
 
-pub use miim::Miim;
+pub use miim::{Clause45Miim, Miim};
+
+mod cache;
+pub use cache::CachedMiim;
+
+mod scan;
+pub use scan::{scan_bus, FoundPhy, ScanResult, MAX_PHY_ADDRESS};
+
+mod trace;
+#[cfg(feature = "defmt")]
+pub use trace::DefmtLog;
+pub use trace::{
+    Direction, RecordingMiim, ReplayError, ReplayMiim, TraceBuffer, Transaction, TransactionLog,
+};
+
+mod next_page;
+pub use next_page::{NextPageError, NextPageExchange, NextPageStatus};
 
 #[cfg(feature = "mmd")]
 mod mmd;
 #[cfg(feature = "mmd")]
 use mmd::Mmd;
 
+#[cfg(feature = "async")]
+mod asynch;
+#[cfg(feature = "async")]
+pub use asynch::{AsyncMiim, AsyncPhy};
+
+#[cfg(feature = "bitbang")]
+mod bitbang;
+#[cfg(feature = "bitbang")]
+pub use bitbang::{BitBangError, BitBangMiim};
+
+#[cfg(any(feature = "sim", test))]
+mod sim;
+#[cfg(any(feature = "sim", test))]
+pub use sim::{
+    LinkPartner, SimChip, SimMiim, SimulatedPhy, SIM_MMD_CAPACITY, SIM_NEXT_PAGE_CAPACITY,
+};
+
 #[cfg(feature = "ptp")]
 mod ptp;
 #[cfg(feature = "ptp")]
-pub use ptp::PTP;
+pub use ptp::{PiServo, PiServoConfig, PtpTime, ServoAction, NANOS_PER_SECOND, PTP};
 
 pub mod registers;
 use registers::*;
 
+pub mod bringup;
+
 #[cfg(feature = "phy")]
 pub mod phy;
 
@@ -52,17 +87,35 @@ impl From<Bcr> for LinkSpeed {
     }
 }
 
-impl From<LinkSpeed> for Bcr {
-    fn from(link_speed: LinkSpeed) -> Self {
+/// The error returned when converting [`LinkSpeed::Illegal`] into a [`Bcr`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IllegalLinkSpeed;
+
+impl TryFrom<LinkSpeed> for Bcr {
+    type Error = IllegalLinkSpeed;
+
+    fn try_from(link_speed: LinkSpeed) -> Result<Self, Self::Error> {
         match link_speed {
-            LinkSpeed::Mpbs1000 => Bcr::SPEED_SEL_MSB,
-            LinkSpeed::Mbps100 => Bcr::SPEED_SEL_LSB,
-            LinkSpeed::Mpbs10 => Bcr::empty(),
-            LinkSpeed::Illegal => panic!("Cannot convert illegal link speed into Bcr"),
+            LinkSpeed::Mpbs1000 => Ok(Bcr::SPEED_SEL_MSB),
+            LinkSpeed::Mbps100 => Ok(Bcr::SPEED_SEL_LSB),
+            LinkSpeed::Mpbs10 => Ok(Bcr::empty()),
+            LinkSpeed::Illegal => Err(IllegalLinkSpeed),
         }
     }
 }
 
+/// An error that can occur while forcing the link mode of a PHY
+#[cfg(feature = "phy")]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForceLinkError<E> {
+    /// The PHY does not support the requested link mode
+    Unsupported,
+    /// An error occured while accessing the PHY
+    Miim(E),
+}
+
 /// The status register of a PHY.
 ///
 /// This struct describes what functions the PHY is capable of.
@@ -165,9 +218,10 @@ pub struct ExtendedPhyStatus {
 ///
 /// In practice, [`SelectorField::Std802_3`] is used almost exclusively.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum SelectorField {
     /// The message is an IEEE Std 802.3 message
+    #[default]
     Std802_3,
     /// The message is an IEEE Std 802.9 ISLAN-16T message
     Std802_9Islan16t,
@@ -177,12 +231,6 @@ pub enum SelectorField {
     Std1394,
 }
 
-impl Default for SelectorField {
-    fn default() -> Self {
-        Self::Std802_3
-    }
-}
-
 impl From<AutoNegCap> for Option<SelectorField> {
     fn from(ana: AutoNegCap) -> Self {
         // We use bitwise XOR (`^`) here to ensure that all bits
@@ -238,8 +286,30 @@ impl PhyIdent {
     }
 
     /// The OUI of this PhyIdent
+    ///
+    /// The returned value is the OUI in its usual written form, i.e.
+    /// `0x00800F` for `00-80-0F`. Bits 1 and 2 of the OUI are not stored
+    /// by the PHY, and are always zero.
     pub fn oui(&self) -> u32 {
-        (self.0 as u32) << 6 & (self.1 as u32) >> 10
+        // Register 2 holds OUI bits 3 through 18, and bits 15 to 10 of
+        // register 3 hold OUI bits 19 through 24 (IEEE 802.3 22.2.4.3.1).
+        let bits = (self.0 as u32) << 6 | (self.1 as u32) >> 10;
+
+        // OUI bit 1 is the least significant bit of the first octet.
+        let mut oui = 0;
+        for bit in 3..=24 {
+            if bits & (1 << (24 - bit)) != 0 {
+                let octet = (bit - 1) / 8;
+                let position = (bit - 1) % 8;
+                oui |= 1 << ((2 - octet) * 8 + position);
+            }
+        }
+        oui
+    }
+
+    /// The vendor of the PHY, as determined from its OUI
+    pub fn vendor(&self) -> Option<Vendor> {
+        Vendor::from_oui(self.oui())
     }
 
     /// The model number of this PhyIdent
@@ -251,13 +321,129 @@ impl PhyIdent {
     pub fn revision(&self) -> u8 {
         (self.1) as u8 & 0x0F
     }
+
+    /// The name of the model of this PHY, if it is known
+    pub fn model_name(&self) -> Option<&'static str> {
+        let name = match (self.vendor()?, self.model_number()) {
+            (Vendor::Microchip, 0x0F) => "LAN8720A",
+            (Vendor::Microchip, 0x13) => "LAN8742A",
+            (Vendor::Micrel, 0x16) => "KSZ8081",
+            (Vendor::Micrel, 0x22) => "KSZ9031",
+            (Vendor::TexasInstruments, 0x09) => "DP83848",
+            (Vendor::TexasInstruments, 0x0E) => "DP83640",
+            (Vendor::TexasInstruments, 0x23) => "DP83867",
+            (Vendor::Realtek, 0x01) => "RTL8201",
+            (Vendor::Realtek, 0x11) => "RTL8211",
+            (Vendor::Marvell, 0x0C) => "88E1111",
+            _ => return None,
+        };
+        Some(name)
+    }
 }
 
-/// The pause mode supported by this PHY
+impl core::fmt::Display for PhyIdent {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.vendor() {
+            Some(vendor) => write!(f, "{vendor} ")?,
+            None => {
+                let oui = self.oui();
+                write!(
+                    f,
+                    "OUI {:02X}-{:02X}-{:02X} ",
+                    oui >> 16,
+                    (oui >> 8) & 0xFF,
+                    oui & 0xFF
+                )?;
+            }
+        }
+
+        match self.model_name() {
+            Some(model) => write!(f, "{model}")?,
+            None => write!(f, "model 0x{:02X}", self.model_number())?,
+        }
+
+        write!(f, " rev {}", self.revision())
+    }
+}
+
+/// Vendors of commonly used PHYs
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Vendor {
+    /// Microchip, including PHYs originally made by SMSC
+    Microchip,
+    /// Texas Instruments, including PHYs originally made by National Semiconductor
+    TexasInstruments,
+    /// Realtek
+    Realtek,
+    /// Micrel, now part of Microchip
+    Micrel,
+    /// Marvell
+    Marvell,
+    /// Broadcom
+    Broadcom,
+    /// Analog Devices
+    AnalogDevices,
+    /// NXP
+    Nxp,
+}
+
+impl Vendor {
+    /// Look up the vendor that an OUI, as returned by [`PhyIdent::oui`], belongs to.
+    ///
+    /// Several vendors store their OUI in the PHY identifier registers with
+    /// the bit order of each octet reversed. These are matched by the value
+    /// that is actually read from the PHY.
+    ///
+    /// An all-zero OUI does not identify a vendor, and returns `None`.
+    pub fn from_oui(oui: u32) -> Option<Self> {
+        let vendor = match oui {
+            // SMSC, 00-80-0F
+            0x00800F => Self::Microchip,
+            // Texas Instruments 08-00-28 and National Semiconductor 08-00-17,
+            // both stored bit-reversed
+            0x100014 | 0x1000E8 => Self::TexasInstruments,
+            0x00E04C => Self::Realtek,
+            0x0010A1 => Self::Micrel,
+            // 00-50-43, stored bit-reversed
+            0x000AC2 => Self::Marvell,
+            0x001018 | 0x001BE9 | 0x18C086 => Self::Broadcom,
+            // 00-A0-EF, stored bit-reversed
+            0x0005F7 => Self::AnalogDevices,
+            // 00-60-37, stored bit-reversed
+            0x0006EC => Self::Nxp,
+            _ => return None,
+        };
+        Some(vendor)
+    }
+
+    /// A short, human-readable name for this vendor
+    pub fn name(&self) -> &'static str {
+        match self {
+            Vendor::Microchip => "Microchip",
+            Vendor::TexasInstruments => "TI",
+            Vendor::Realtek => "Realtek",
+            Vendor::Micrel => "Micrel",
+            Vendor::Marvell => "Marvell",
+            Vendor::Broadcom => "Broadcom",
+            Vendor::AnalogDevices => "ADI",
+            Vendor::Nxp => "NXP",
+        }
+    }
+}
+
+impl core::fmt::Display for Vendor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The pause mode supported by this PHY
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub enum Pause {
     /// The PHY supports no PAUSE modes
+    #[default]
     NoPause,
     /// The PHY supports asymmetric PAUSE mode toward its link partner
     AsymmetricPartner,
@@ -268,12 +454,6 @@ pub enum Pause {
     SymmetricAndAsymmetricLocal,
 }
 
-impl Default for Pause {
-    fn default() -> Self {
-        Pause::NoPause
-    }
-}
-
 impl From<AutoNegCap> for Pause {
     fn from(ana: AutoNegCap) -> Self {
         match (
@@ -288,6 +468,68 @@ impl From<AutoNegCap> for Pause {
     }
 }
 
+impl Pause {
+    /// The values of the PAUSE and ASM_DIR bits for this pause mode
+    fn bits(self) -> (bool, bool) {
+        match self {
+            Pause::NoPause => (false, false),
+            Pause::AsymmetricPartner => (false, true),
+            Pause::Symmetric => (true, false),
+            Pause::SymmetricAndAsymmetricLocal => (true, true),
+        }
+    }
+
+    /// Resolve the directions in which PAUSE frames may be used on a full
+    /// duplex link, if the local device advertises `self` and the link
+    /// partner advertises `partner`.
+    ///
+    /// This follows Table 28B-3 of IEEE 802.3.
+    pub fn resolve(self, partner: Pause) -> PauseResolution {
+        let (tx, rx) = match (self.bits(), partner.bits()) {
+            ((true, _), (true, _)) => (true, true),
+            ((false, true), (true, true)) => (true, false),
+            ((true, true), (false, true)) => (false, true),
+            _ => (false, false),
+        };
+        PauseResolution { tx, rx }
+    }
+}
+
+/// The directions in which PAUSE frames may be used, as resolved
+/// by autonegotiation.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PauseResolution {
+    /// The local device may transmit PAUSE frames
+    pub tx: bool,
+    /// The local device should act on received PAUSE frames
+    pub rx: bool,
+}
+
+/// The duplex mode of a link
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Duplex {
+    /// Half duplex
+    Half,
+    /// Full duplex
+    Full,
+}
+
+/// The outcome of autonegotiation.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AutoNegotiationResult {
+    /// The speed of the link
+    pub speed: LinkSpeed,
+    /// The duplex mode of the link
+    pub duplex: Duplex,
+    /// The resolved use of PAUSE frames.
+    ///
+    /// PAUSE is never used on half duplex links.
+    pub pause: PauseResolution,
+}
+
 impl From<Pause> for AutoNegCap {
     fn from(pause: Pause) -> Self {
         match pause {
@@ -315,6 +557,10 @@ pub struct AutoNegotiationAdvertisement {
     pub fd_100base_tx: bool,
     /// The PHY supports 100BASE-T4
     pub base100_t4: bool,
+    /// The PHY supports 1000BASE-T
+    pub hd_1000base_t: bool,
+    /// The PHY supports 1000BASE-T Full Duplex
+    pub fd_1000base_t: bool,
     /// The pause mode supported by the PHY
     pub pause: Pause,
 }
@@ -328,6 +574,8 @@ impl Default for AutoNegotiationAdvertisement {
             hd_100base_tx: false,
             fd_100base_tx: false,
             base100_t4: false,
+            hd_1000base_t: false,
+            fd_1000base_t: false,
             pause: Default::default(),
         }
     }
@@ -342,12 +590,98 @@ impl From<AutoNegCap> for AutoNegotiationAdvertisement {
             hd_100base_tx: ana.contains(AutoNegCap::_100BASETX),
             fd_100base_tx: ana.contains(AutoNegCap::_100BASETXFD),
             base100_t4: ana.contains(AutoNegCap::_100BASET4),
+            hd_1000base_t: false,
+            fd_1000base_t: false,
             pause: ana.into(),
         }
     }
 }
 
+impl AutoNegotiationAdvertisement {
+    /// The value of register 4 that advertises the modes of `self`
+    /// that are supported according to `status`.
+    pub(crate) fn local_cap(&self, status: &PhyStatus) -> AutoNegCap {
+        let mut ana = AutoNegCap::empty();
+
+        if self.hd_10base_t && status.hd_10mbps {
+            ana.insert(AutoNegCap::_10BASET);
+        }
+
+        if self.fd_10base_t && status.fd_10mbps {
+            ana.insert(AutoNegCap::_10BASETFD);
+        }
+
+        if self.hd_100base_tx && status.hd_100base_x {
+            ana.insert(AutoNegCap::_100BASETX);
+        }
+
+        if self.fd_100base_tx && status.fd_100base_x {
+            ana.insert(AutoNegCap::_100BASETXFD);
+        }
+
+        if self.base100_t4 {
+            ana.insert(AutoNegCap::_100BASET4);
+        }
+
+        if let Some(selector) = self.selector_field {
+            ana.insert(selector.into());
+        }
+
+        ana.insert(self.pause.into());
+        ana
+    }
+
+    /// Resolve the highest common denominator of the local advertisement `self`
+    /// and the advertisement of the link `partner`.
+    ///
+    /// This follows the priority resolution of Annex 28B.3 of IEEE 802.3, and
+    /// resolves PAUSE according to [`Pause::resolve`]. Returns `None` if there
+    /// is no mode that both devices advertise.
+    pub fn resolve(&self, partner: &Self) -> Option<AutoNegotiationResult> {
+        let (speed, duplex) = if self.fd_1000base_t && partner.fd_1000base_t {
+            (LinkSpeed::Mpbs1000, Duplex::Full)
+        } else if self.hd_1000base_t && partner.hd_1000base_t {
+            (LinkSpeed::Mpbs1000, Duplex::Half)
+        } else if self.fd_100base_tx && partner.fd_100base_tx {
+            (LinkSpeed::Mbps100, Duplex::Full)
+        } else if (self.base100_t4 && partner.base100_t4)
+            || (self.hd_100base_tx && partner.hd_100base_tx)
+        {
+            (LinkSpeed::Mbps100, Duplex::Half)
+        } else if self.fd_10base_t && partner.fd_10base_t {
+            (LinkSpeed::Mpbs10, Duplex::Full)
+        } else if self.hd_10base_t && partner.hd_10base_t {
+            (LinkSpeed::Mpbs10, Duplex::Half)
+        } else {
+            return None;
+        };
+
+        let pause = match duplex {
+            Duplex::Full => self.pause.resolve(partner.pause),
+            Duplex::Half => PauseResolution::default(),
+        };
+
+        Some(AutoNegotiationResult {
+            speed,
+            duplex,
+            pause,
+        })
+    }
+
+    /// Update `gb_ctrl` to advertise the 1000BASE-T modes of `self`
+    /// that are supported according to `ext`.
+    pub(crate) fn update_gb_ctrl(&self, ext: &ExtendedPhyStatus, gb_ctrl: &mut GbCtrl) {
+        gb_ctrl
+            .set_advertise_1000base_t_fd(self.fd_1000base_t && ext.fd_1000base_t)
+            .set_advertise_1000base_t_hd(self.hd_1000base_t && ext.hd_1000base_t);
+    }
+}
+
 /// An IEEE 802.3 compatible PHY
+///
+/// All register accesses are fallible. Errors reported by the underlying
+/// [`Miim`] are returned unmodified, so callers can tell a PHY that is not
+/// answering apart from one that reports its link as down.
 pub trait Phy<M: Miim> {
     /// The best advertisement this PHY can send out.
     ///
@@ -360,109 +694,124 @@ pub trait Phy<M: Miim> {
     /// Get the address of this PHY
     fn get_phy_addr(&self) -> u8;
 
+    /// Whether this PHY responds to Clause 45 management frames.
+    ///
+    /// If this is `true` and the [`Miim`] supports Clause 45 frames,
+    /// MMD registers are accessed directly instead of through the
+    /// indirection registers 13 and 14.
+    fn supports_clause45(&self) -> bool {
+        false
+    }
+
     /// Read a PHY register over MIIM
-    fn read(&mut self, address: u8) -> u16 {
+    fn read(&mut self, address: u8) -> Result<u16, M::Error> {
         let phy = self.get_phy_addr();
         let miim = self.get_miim();
         miim.read(phy, address)
     }
 
     /// Write a PHY register over MIIM
-    fn write(&mut self, address: u8, value: u16) {
+    fn write(&mut self, address: u8, value: u16) -> Result<(), M::Error> {
         let phy = self.get_phy_addr();
         let miim = self.get_miim();
         miim.write(phy, address, value)
     }
 
     /// Get the raw value of the Base Control Register of this PHY
-    fn bcr(&mut self) -> Bcr {
-        Bcr::from_bits_truncate(self.read(Bcr::ADDRESS))
+    fn bcr(&mut self) -> Result<Bcr, M::Error> {
+        Ok(Bcr::from_bits_truncate(self.read(Bcr::ADDRESS)?))
     }
 
     /// Modify the Base Control Register of this PHY
-    fn modify_bcr<F>(&mut self, f: F)
+    fn modify_bcr<F>(&mut self, f: F) -> Result<(), M::Error>
     where
         F: FnOnce(&mut Bcr),
     {
-        let bcr = &mut self.bcr();
+        let bcr = &mut self.bcr()?;
         f(bcr);
-        self.write(Bcr::ADDRESS, bcr.bits());
+        self.write(Bcr::ADDRESS, bcr.bits())
     }
 
     /// Check if the PHY is currently resetting
-    fn is_resetting(&mut self) -> bool {
-        self.bcr().is_resetting()
+    fn is_resetting(&mut self) -> Result<bool, M::Error> {
+        Ok(self.bcr()?.is_resetting())
     }
 
     /// Reset the PHY. Verify that the reset by checking
     /// [`Self::is_resetting`] == false before continuing usage
-    fn reset(&mut self) {
+    fn reset(&mut self) -> Result<(), M::Error> {
         self.modify_bcr(|bcr| {
             bcr.reset(true);
-        });
+        })
     }
 
-    /// Perform a reset, blocking until the reset is completed
-    fn blocking_reset(&mut self) {
-        self.reset();
-        while self.is_resetting() {}
+    /// Perform a reset, blocking until the reset is completed.
+    ///
+    /// This waits forever if the reset does not complete. Use
+    /// [`bringup::reset`] to wait with a timeout instead.
+    fn blocking_reset(&mut self) -> Result<(), M::Error> {
+        self.reset()?;
+        while self.is_resetting()? {}
+        Ok(())
     }
 
     /// Get the raw value of the Base Status Register of this PHY
-    fn bsr(&mut self) -> Bsr {
-        Bsr::from_bits_truncate(self.read(Bsr::ADDRESS))
+    fn bsr(&mut self) -> Result<Bsr, M::Error> {
+        Ok(Bsr::from_bits_truncate(self.read(Bsr::ADDRESS)?))
     }
 
     /// Check if the PHY reports its link as being up
-    fn phy_link_up(&mut self) -> bool {
-        self.bsr().phy_link_up()
+    fn phy_link_up(&mut self) -> Result<bool, M::Error> {
+        Ok(self.bsr()?.phy_link_up())
     }
 
     /// Check if the PHY reports its autonegotiation process
     /// as having completed
-    fn autoneg_completed(&mut self) -> bool {
-        self.bsr().autoneg_completed()
+    fn autoneg_completed(&mut self) -> Result<bool, M::Error> {
+        Ok(self.bsr()?.autoneg_completed())
     }
 
     /// Read the status register for this PHY
-    fn status(&mut self) -> PhyStatus {
-        self.bsr().into()
+    fn status(&mut self) -> Result<PhyStatus, M::Error> {
+        let phy = self.get_phy_addr();
+        let abilities = self.get_miim().read_abilities(phy)?;
+        Ok(Bsr::from_bits_truncate(abilities).into())
     }
 
     /// Read the ESR for this PHY. Will return `None` if
     /// `extended_status` in [`Self::status`] is false.
-    fn esr(&mut self) -> Option<Esr> {
-        if self.status().extended_status {
+    fn esr(&mut self) -> Result<Option<Esr>, M::Error> {
+        if self.status()?.extended_status {
             let phy = self.get_phy_addr();
             let miim = self.get_miim();
-            Some(Esr::from_bits_truncate(miim.read(phy, Esr::ADDRESS)))
+            Ok(Some(Esr::from_bits_truncate(miim.read(phy, Esr::ADDRESS)?)))
         } else {
-            None
+            Ok(None)
         }
     }
 
     /// Read the Extended Status Register for this PHY.
     ///
     /// Returns `None` if `extended_status` in [`Self::status`] is false.
-    fn extended_status(&mut self) -> Option<ExtendedPhyStatus> {
-        self.esr().map(|esr| ExtendedPhyStatus {
+    fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, M::Error> {
+        Ok(self.esr()?.map(|esr| ExtendedPhyStatus {
             fd_1000base_x: esr.contains(Esr::_1000BASEXFD),
             hd_1000base_x: esr.contains(Esr::_1000BASEXHD),
             fd_1000base_t: esr.contains(Esr::_1000BASETFD),
             hd_1000base_t: esr.contains(Esr::_1000BASETHD),
-        })
+        }))
     }
 
     /// Read the PHY identifier for this PHY.
     ///
     /// Returns `None` if `extended_capabilities` in [`Self::status`] is false
-    fn phy_ident(&mut self) -> Option<PhyIdent> {
-        if self.status().extended_caps {
-            let msb = self.read(2);
-            let lsb = self.read(3);
-            Some(PhyIdent::new(msb, lsb))
+    fn phy_ident(&mut self) -> Result<Option<PhyIdent>, M::Error> {
+        if self.status()?.extended_caps {
+            let msb = self.read(2)?;
+            let lsb = self.read(3)?;
+            Ok(Some(PhyIdent::new(msb, lsb)))
         } else {
-            None
+            Ok(None)
         }
     }
 
@@ -470,83 +819,171 @@ pub trait Phy<M: Miim> {
     /// process
     ///
     /// This is a no-op if `extended_caps` in [`Self::status`] is false
-    fn set_autonegotiation_advertisement(&mut self, ad: AutoNegotiationAdvertisement) {
-        let status = self.status();
+    fn set_autonegotiation_advertisement(
+        &mut self,
+        ad: AutoNegotiationAdvertisement,
+    ) -> Result<(), M::Error> {
+        let status = self.status()?;
         if !status.extended_caps {
-            return;
+            return Ok(());
         }
 
-        let mut ana = AutoNegCap::empty();
+        let ana = ad.local_cap(&status);
+        self.write(AutoNegCap::LOCAL_CAP_ADDRESS, ana.bits())?;
 
-        if ad.hd_10base_t && status.hd_10mbps {
-            ana.insert(AutoNegCap::_10BASET);
+        if let Some(ext) = self.extended_status()? {
+            self.modify_gb_ctrl(|gb_ctrl| ad.update_gb_ctrl(&ext, gb_ctrl))?;
         }
 
-        if ad.fd_10base_t && status.fd_10mbps {
-            ana.insert(AutoNegCap::_10BASETFD);
+        self.modify_bcr(|bcr| {
+            bcr.set_autonegotiation(true).restart_autonegotiation();
+        })
+    }
+
+    /// Get the advertised capabilities of this PHY
+    ///
+    /// This is a no-op if `extended_caps` in [`Self::status`] is false
+    fn get_autonegotiation_caps(
+        &mut self,
+    ) -> Result<Option<AutoNegotiationAdvertisement>, M::Error> {
+        let status = self.status()?;
+        if !status.extended_caps {
+            return Ok(None);
         }
+        let ana = AutoNegCap::from_bits_truncate(self.read(AutoNegCap::LOCAL_CAP_ADDRESS)?);
+        let mut ad: AutoNegotiationAdvertisement = ana.into();
 
-        if ad.hd_100base_tx && status.hd_100base_x {
-            ana.insert(AutoNegCap::_100BASETX);
+        if let Some(gb_ctrl) = self.gb_ctrl()? {
+            ad.fd_1000base_t = gb_ctrl.advertise_1000base_t_fd();
+            ad.hd_1000base_t = gb_ctrl.advertise_1000base_t_hd();
         }
 
-        if ad.fd_100base_tx && status.fd_100base_x {
-            ana.insert(AutoNegCap::_100BASETXFD);
+        Ok(Some(ad))
+    }
+
+    /// Get the capabilites of the autonegotiation partner of this PHY
+    ///
+    /// This is a no-op if `extended_caps` in [`Self::status`] is false
+    fn get_autonegotiation_partner_caps(
+        &mut self,
+    ) -> Result<Option<AutoNegotiationAdvertisement>, M::Error> {
+        let status = self.status()?;
+        if !status.extended_caps {
+            return Ok(None);
         }
+        let ana = AutoNegCap::from_bits_truncate(self.read(AutoNegCap::PARTNER_CAP_ADDRESS)?);
+        let mut ad: AutoNegotiationAdvertisement = ana.into();
 
-        if ad.base100_t4 {
-            ana.insert(AutoNegCap::_100BASET4);
+        if let Some(gb_status) = self.gb_status()? {
+            ad.fd_1000base_t = gb_status.partner_1000base_t_fd();
+            ad.hd_1000base_t = gb_status.partner_1000base_t_hd();
         }
 
-        if let Some(selector) = ad.selector_field {
-            ana.insert(selector.into());
+        Ok(Some(ad))
+    }
+
+    /// Disable autonegotiation, and force the PHY to operate at `speed`.
+    ///
+    /// Returns [`ForceLinkError::Unsupported`] if [`Self::status`] and
+    /// [`Self::extended_status`] report that the PHY does not support `speed`,
+    /// or if `speed` can not be forced (see
+    /// [`AdvancedPhySpeed::can_be_forced`](phy::AdvancedPhySpeed::can_be_forced)).
+    #[cfg(feature = "phy")]
+    fn force_link(&mut self, speed: phy::AdvancedPhySpeed) -> Result<(), ForceLinkError<M::Error>> {
+        if !speed.can_be_forced() {
+            return Err(ForceLinkError::Unsupported);
         }
 
-        ana.insert(ad.pause.into());
+        let status = self.status().map_err(ForceLinkError::Miim)?;
+        let ext = self.extended_status().map_err(ForceLinkError::Miim)?;
+        if !speed.is_supported(&status, ext.as_ref()) {
+            return Err(ForceLinkError::Unsupported);
+        }
 
-        self.write(AutoNegCap::LOCAL_CAP_ADDRESS, ana.bits());
+        let speed_sel =
+            Bcr::try_from(speed.speed()).map_err(|IllegalLinkSpeed| ForceLinkError::Unsupported)?;
 
         self.modify_bcr(|bcr| {
-            bcr.set_autonegotiation(true).restart_autonegotiation();
+            bcr.remove(Bcr::SPEED_SEL_MSB | Bcr::SPEED_SEL_LSB);
+            bcr.insert(speed_sel);
+            bcr.set_autonegotiation(false)
+                .set_full_duplex(speed.duplex() == Duplex::Full);
         })
+        .map_err(ForceLinkError::Miim)
     }
 
-    /// Get the advertised capabilities of this PHY
+    /// Resolve the outcome of autonegotiation from the local and link
+    /// partner advertisements, using [`AutoNegotiationAdvertisement::resolve`].
     ///
-    /// This is a no-op if `extended_caps` in [`Self::status`] is false
-    fn get_autonegotiation_caps(&mut self) -> Option<AutoNegotiationAdvertisement> {
-        let status = self.status();
-        if !status.extended_caps {
-            return None;
+    /// Returns `None` if autonegotiation has not completed, or if there is
+    /// no common mode. The latter is also the case if the link partner does not
+    /// autonegotiate, and the link was established using parallel detection.
+    fn resolve_autonegotiation(&mut self) -> Result<Option<AutoNegotiationResult>, M::Error> {
+        if !self.autoneg_completed()? {
+            return Ok(None);
+        }
+
+        let local = self.get_autonegotiation_caps()?;
+        let partner = self.get_autonegotiation_partner_caps()?;
+
+        match (local, partner) {
+            (Some(local), Some(partner)) => Ok(local.resolve(&partner)),
+            _ => Ok(None),
         }
-        let ana = AutoNegCap::from_bits_truncate(self.read(AutoNegCap::LOCAL_CAP_ADDRESS));
-        Some(ana.into())
     }
 
-    /// Get the capabilites of the autonegotiation partner of this PHY
+    /// Read the 1000BASE-T Control Register of this PHY.
     ///
-    /// This is a no-op if `extended_caps` in [`Self::status`] is false
-    fn get_autonegotiation_partner_caps(&mut self) -> Option<AutoNegotiationAdvertisement> {
-        let status = self.status();
-        if !status.extended_caps {
-            return None;
+    /// Returns `None` if [`Self::extended_status`] reports that the PHY
+    /// does not support 1000BASE-T.
+    fn gb_ctrl(&mut self) -> Result<Option<GbCtrl>, M::Error> {
+        match self.extended_status()? {
+            Some(ext) if ext.fd_1000base_t || ext.hd_1000base_t => Ok(Some(
+                GbCtrl::from_bits_truncate(self.read(GbCtrl::ADDRESS)?),
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    /// Modify the 1000BASE-T Control Register of this PHY.
+    ///
+    /// This is a no-op if the PHY does not support 1000BASE-T.
+    fn modify_gb_ctrl<F>(&mut self, f: F) -> Result<(), M::Error>
+    where
+        F: FnOnce(&mut GbCtrl),
+    {
+        if let Some(mut gb_ctrl) = self.gb_ctrl()? {
+            f(&mut gb_ctrl);
+            self.write(GbCtrl::ADDRESS, gb_ctrl.bits())?;
+        }
+        Ok(())
+    }
+
+    /// Read the 1000BASE-T Status Register of this PHY.
+    ///
+    /// Returns `None` if [`Self::extended_status`] reports that the PHY
+    /// does not support 1000BASE-T.
+    fn gb_status(&mut self) -> Result<Option<GbStatus>, M::Error> {
+        match self.extended_status()? {
+            Some(ext) if ext.fd_1000base_t || ext.hd_1000base_t => Ok(Some(
+                GbStatus::from_bits_truncate(self.read(GbStatus::ADDRESS)?),
+            )),
+            _ => Ok(None),
         }
-        let ana = AutoNegCap::from_bits_truncate(self.read(AutoNegCap::PARTNER_CAP_ADDRESS));
-        Some(ana.into())
     }
 
     /// This returns `None` if `extended_caps` in `Self::status` is `false`
-    fn ane(&mut self) -> Option<Ane> {
-        if self.status().extended_caps {
-            Some(Ane::from_bits_truncate(self.read(Ane::ADDRESS)))
+    fn ane(&mut self) -> Result<Option<Ane>, M::Error> {
+        if self.status()?.extended_caps {
+            Ok(Some(Ane::from_bits_truncate(self.read(Ane::ADDRESS)?)))
         } else {
-            None
+            Ok(None)
         }
     }
 
     /// Read an MMD register
     #[cfg(feature = "mmd")]
-    fn mmd_read(&mut self, mmd_address: u8, reg_address: u16) -> u16
+    fn mmd_read(&mut self, mmd_address: u8, reg_address: u16) -> Result<u16, M::Error>
     where
         Self: Sized,
     {
@@ -555,10 +992,69 @@ pub trait Phy<M: Miim> {
 
     /// Write an MMD register
     #[cfg(feature = "mmd")]
-    fn mmd_write(&mut self, device_address: u8, reg_address: u16, reg_value: u16)
+    fn mmd_write(
+        &mut self,
+        device_address: u8,
+        reg_address: u16,
+        reg_value: u16,
+    ) -> Result<(), M::Error>
     where
         Self: Sized,
     {
         Mmd::write(self, device_address, reg_address, reg_value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{PhyIdent, Vendor};
+    use std::string::ToString;
+
+    #[test]
+    fn oui_of_known_phys() {
+        // LAN8720A
+        let ident = PhyIdent::new(0x0007, 0xC0F1);
+        assert_eq!(ident.oui(), 0x00800F);
+        assert_eq!(ident.vendor(), Some(Vendor::Microchip));
+        assert_eq!((ident.model_number(), ident.revision()), (0x0F, 1));
+
+        // DP83848
+        let ident = PhyIdent::new(0x2000, 0x5C90);
+        assert_eq!(ident.vendor(), Some(Vendor::TexasInstruments));
+        assert_eq!((ident.model_number(), ident.revision()), (0x09, 0));
+    }
+
+    #[test]
+    fn zero_oui_has_no_vendor() {
+        let ident = PhyIdent::new(0x0000, 0x011A);
+        assert_eq!(ident.oui(), 0);
+        assert_eq!(ident.vendor(), None);
+        assert_eq!(Vendor::from_oui(0), None);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            PhyIdent::new(0x0007, 0xC0F1).to_string(),
+            "Microchip LAN8720A rev 1"
+        );
+        assert_eq!(
+            PhyIdent::new(0x2000, 0x5C90).to_string(),
+            "TI DP83848 rev 0"
+        );
+
+        // A known vendor with an unknown model
+        assert_eq!(
+            PhyIdent::new(0x0007, 0xC3F2).to_string(),
+            "Microchip model 0x3F rev 2"
+        );
+
+        // An unknown vendor
+        assert_eq!(
+            PhyIdent::new(0x0000, 0x011A).to_string(),
+            "OUI 00-00-00 model 0x11 rev 10"
+        );
+    }
+}
@@ -1,16 +1,61 @@
 //! This module defines traits and structs used for access to
 //! Media Independent Interface
 
+use crate::registers::Bsr;
+
 /// A trait used for implementing access to the Media Indepedent
 /// Interface of an IEEE 802.3 compatible PHY.
 pub trait Miim {
+    /// The error that can occur while accessing the bus.
+    ///
+    /// Implementations should use this to report conditions such as
+    /// a stuck MDIO bus, a MAC busy-timeout, or a PHY that does not
+    /// answer. Implementations that can not fail may use
+    /// [`core::convert::Infallible`].
+    type Error: core::fmt::Debug;
+
     /// Read an MII register
     ///
     /// This function receives `&mut self` because it is likely
     /// for implementations to expect to have unique access to underlying
     /// hardware elements (such as pins, or the MAC itself).
-    fn read(&mut self, phy: u8, reg: u8) -> u16;
+    fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error>;
 
     /// Write to an MII register
-    fn write(&mut self, phy: u8, reg: u8, data: u16);
+    fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error>;
+
+    /// Read the [`Bsr::ABILITIES`] bits of the BSR of the PHY at `phy`. The
+    /// other bits are returned as 0.
+    ///
+    /// As these bits never change, implementations that cache registers may
+    /// return them without accessing the bus.
+    fn read_abilities(&mut self, phy: u8) -> Result<u16, Self::Error> {
+        Ok(self.read(phy, Bsr::ADDRESS)? & Bsr::ABILITIES.bits())
+    }
+
+    /// Get access to the Clause 45 frames of this bus, if it supports them.
+    ///
+    /// Implementations that also implement [`Clause45Miim`] should return
+    /// `Some(self)`. MMD accesses use direct Clause 45 frames if this returns
+    /// `Some` and the PHY is Clause 45 capable, and fall back to the
+    /// indirect access through registers 13 and 14 otherwise.
+    fn clause45(&mut self) -> Option<&mut dyn Clause45Miim<Error = Self::Error>> {
+        None
+    }
+}
+
+/// A trait used for implementing access to PHYs using IEEE 802.3
+/// Clause 45 management frames.
+///
+/// Clause 45 frames address a register with a 5-bit port address, a 5-bit
+/// MMD device address and a full 16-bit register address.
+pub trait Clause45Miim {
+    /// The error that can occur while accessing the bus.
+    type Error: core::fmt::Debug;
+
+    /// Read a register of MMD `devad` of the PHY at `port`
+    fn read_c45(&mut self, port: u8, devad: u8, reg: u16) -> Result<u16, Self::Error>;
+
+    /// Write a register of MMD `devad` of the PHY at `port`
+    fn write_c45(&mut self, port: u8, devad: u8, reg: u16, data: u16) -> Result<(), Self::Error>;
 }
@@ -38,14 +38,25 @@ impl MmdAddress {
 pub struct Mmd;
 
 impl Mmd {
-    pub fn read<M: Miim, P: Phy<M>>(phy: &mut P, device_address: u8, reg_address: u16) -> u16 {
+    pub fn read<M: Miim, P: Phy<M>>(
+        phy: &mut P,
+        device_address: u8,
+        reg_address: u16,
+    ) -> Result<u16, M::Error> {
+        if phy.supports_clause45() {
+            let port = phy.get_phy_addr();
+            if let Some(c45) = phy.get_miim().clause45() {
+                return c45.read_c45(port, device_address, reg_address);
+            }
+        }
+
         let mut mmd_address = MmdAddress::device_address(device_address);
-        phy.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits());
-        phy.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_address);
+        phy.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits())?;
+        phy.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_address)?;
 
         mmd_address.remove(MmdAddress::ADDRESS);
         mmd_address.insert(MmdAddress::DATA_NO_POSTINC);
-        phy.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits());
+        phy.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits())?;
         phy.read(MmdAddress::DATA_ADRESS_ADDRESS)
     }
 
@@ -54,14 +65,21 @@ impl Mmd {
         device_address: u8,
         reg_address: u16,
         reg_data: u16,
-    ) {
+    ) -> Result<(), M::Error> {
+        if phy.supports_clause45() {
+            let port = phy.get_phy_addr();
+            if let Some(c45) = phy.get_miim().clause45() {
+                return c45.write_c45(port, device_address, reg_address, reg_data);
+            }
+        }
+
         let mut mmd_address = MmdAddress::device_address(device_address);
-        phy.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits());
-        phy.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_address);
+        phy.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits())?;
+        phy.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_address)?;
 
         mmd_address.remove(MmdAddress::ADDRESS);
         mmd_address.insert(MmdAddress::DATA_NO_POSTINC);
-        phy.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits());
-        phy.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_data);
+        phy.write(MmdAddress::CONTROL_ADDRESS, mmd_address.bits())?;
+        phy.write(MmdAddress::DATA_ADRESS_ADDRESS, reg_data)
     }
 }
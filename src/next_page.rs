@@ -0,0 +1,328 @@
+//! Exchange of autonegotiation next pages, as described in
+//! clause 28.2.3.4 of IEEE 802.3.
+
+use crate::{
+    registers::{Ane, AutoNegCap, NextPage},
+    Miim, Phy,
+};
+
+/// An error that can occur while starting a next page exchange
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NextPageError<E> {
+    /// The PHY does not report that it is next page able
+    Unsupported,
+    /// An error occured while accessing the PHY
+    Miim(E),
+}
+
+/// The status of a [`NextPageExchange`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NextPageStatus {
+    /// The exchange is still in progress
+    Pending,
+    /// Both devices have sent their last page
+    Done,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    BasePage,
+    NextPages,
+    Done,
+}
+
+/// A state machine that sends a sequence of next pages to the link partner,
+/// and collects the next pages that the link partner sends in return.
+///
+/// Up to `N` received pages are stored, further pages are acknowledged
+/// but dropped.
+///
+/// Pages are exchanged in pairs: once the local device has sent all of its
+/// pages, it sends Null Message pages for as long as the link partner has
+/// more pages to send.
+#[derive(Debug)]
+pub struct NextPageExchange<'a, const N: usize> {
+    pages: &'a [NextPage],
+    default_rx_location: u8,
+    state: State,
+    sent: usize,
+    last_sent_next_page: bool,
+    expected_toggle: bool,
+    received: [NextPage; N],
+    received_len: usize,
+    overflowed: bool,
+}
+
+impl<'a, const N: usize> NextPageExchange<'a, N> {
+    /// Start a next page exchange in which `pages` are sent to the link partner.
+    ///
+    /// This sets the next page flag in the local base page, and restarts
+    /// autonegotiation. The flag is also set if `pages` is empty, so that pages
+    /// sent by the link partner can be received.
+    ///
+    /// `default_rx_location` is the register in which received next pages are
+    /// read if [`Ane::next_page_location`] does not report it.
+    ///
+    /// The next page, ACK and toggle flags of `pages` are ignored, as they are
+    /// managed by the exchange and the PHY.
+    pub fn start<M: Miim, P: Phy<M>>(
+        phy: &mut P,
+        pages: &'a [NextPage],
+        default_rx_location: u8,
+    ) -> Result<Self, NextPageError<M::Error>> {
+        let ane = phy.ane().map_err(NextPageError::Miim)?;
+        if !ane
+            .map(|ane| ane.contains(Ane::NEXT_PAGE_ABLE))
+            .unwrap_or(false)
+        {
+            return Err(NextPageError::Unsupported);
+        }
+
+        let local_cap = phy
+            .read(AutoNegCap::LOCAL_CAP_ADDRESS)
+            .map_err(NextPageError::Miim)?;
+        let mut local_cap = AutoNegCap::from_bits_truncate(local_cap);
+        local_cap.insert(AutoNegCap::NEXT_PAGE);
+        phy.write(AutoNegCap::LOCAL_CAP_ADDRESS, local_cap.bits())
+            .map_err(NextPageError::Miim)?;
+
+        phy.modify_bcr(|bcr| {
+            bcr.set_autonegotiation(true).restart_autonegotiation();
+        })
+        .map_err(NextPageError::Miim)?;
+
+        Ok(Self {
+            pages,
+            default_rx_location,
+            state: State::BasePage,
+            sent: 0,
+            last_sent_next_page: false,
+            expected_toggle: false,
+            received: [NextPage::empty(); N],
+            received_len: 0,
+            overflowed: false,
+        })
+    }
+
+    /// Advance the exchange.
+    ///
+    /// This checks whether the PHY has received a page, and if so, stores it and
+    /// loads the next page to transmit. This function should be called repeatedly
+    /// until it returns [`NextPageStatus::Done`].
+    ///
+    /// When the exchange is done, the next page flag that [`Self::start`] set in
+    /// the local base page is cleared again, so that autonegotiation does not
+    /// exchange next pages when it is restarted later.
+    ///
+    /// Note that reading the Auto-Negotiation Expansion register clears its
+    /// page received flag, so it should not be read elsewhere while an exchange
+    /// is in progress.
+    pub fn poll<M: Miim, P: Phy<M>>(&mut self, phy: &mut P) -> Result<NextPageStatus, M::Error> {
+        if self.state == State::Done {
+            return Ok(NextPageStatus::Done);
+        }
+
+        let ane = Ane::from_bits_truncate(phy.read(Ane::ADDRESS)?);
+        if !ane.page_received() {
+            return Ok(NextPageStatus::Pending);
+        }
+
+        match self.state {
+            State::BasePage => {
+                let base =
+                    AutoNegCap::from_bits_truncate(phy.read(AutoNegCap::PARTNER_CAP_ADDRESS)?);
+
+                // Next pages are only exchanged if both devices set the
+                // next page flag in their base page.
+                if !base.contains(AutoNegCap::NEXT_PAGE) {
+                    self.finish(phy)?;
+                    return Ok(NextPageStatus::Done);
+                }
+
+                // The toggle bit of the first next page is the inverse
+                // of bit 11 of the base page.
+                self.expected_toggle = !base.contains(AutoNegCap::ASSYMETRIC_PAUSE);
+                self.state = State::NextPages;
+                self.transmit_next(phy)?;
+            }
+            State::NextPages => {
+                let page = NextPage::new(ane, self.default_rx_location, phy)?;
+
+                // A page with an unexpected toggle bit is a page that we have
+                // already seen.
+                if page.toggle_bit() != self.expected_toggle {
+                    return Ok(NextPageStatus::Pending);
+                }
+                self.expected_toggle = !self.expected_toggle;
+                self.store(page);
+
+                if !self.last_sent_next_page && !page.next_page() {
+                    self.finish(phy)?;
+                    return Ok(NextPageStatus::Done);
+                }
+
+                self.transmit_next(phy)?;
+            }
+            State::Done => {}
+        }
+
+        Ok(NextPageStatus::Pending)
+    }
+
+    /// Check if the exchange has completed
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+
+    /// The amount of pages passed to [`Self::start`] that have been sent
+    pub fn sent(&self) -> usize {
+        self.sent
+    }
+
+    /// The pages received from the link partner so far, in order.
+    ///
+    /// This includes Null Message pages.
+    pub fn received(&self) -> &[NextPage] {
+        &self.received[..self.received_len]
+    }
+
+    /// Check if more than `N` pages were received, and some were dropped
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+
+    /// Complete the exchange, and clear the next page flag in the local base page.
+    fn finish<M: Miim, P: Phy<M>>(&mut self, phy: &mut P) -> Result<(), M::Error> {
+        self.state = State::Done;
+
+        let local_cap = AutoNegCap::from_bits_truncate(phy.read(AutoNegCap::LOCAL_CAP_ADDRESS)?);
+        phy.write(
+            AutoNegCap::LOCAL_CAP_ADDRESS,
+            (local_cap - AutoNegCap::NEXT_PAGE).bits(),
+        )
+    }
+
+    fn store(&mut self, page: NextPage) {
+        if let Some(slot) = self.received.get_mut(self.received_len) {
+            *slot = page;
+            self.received_len += 1;
+        } else {
+            self.overflowed = true;
+        }
+    }
+
+    fn transmit_next<M: Miim, P: Phy<M>>(&mut self, phy: &mut P) -> Result<(), M::Error> {
+        let mut page = match self.pages.get(self.sent) {
+            Some(page) => {
+                self.sent += 1;
+                *page
+            }
+            None => NextPage::null_message(),
+        };
+
+        let more = self.sent < self.pages.len();
+        page.remove(NextPage::ACK | NextPage::TOGGLE);
+        page.set_next_page(more);
+        self.last_sent_next_page = more;
+
+        phy.write(NextPage::TRANSMIT_ADDR, page.bits())
+    }
+}
+
+#[cfg(all(test, feature = "phy"))]
+mod tests {
+    use super::*;
+    use crate::phy::BarePhy;
+    use crate::sim::{LinkPartner, SimChip, SimMiim, SimulatedPhy};
+    use crate::{AutoNegotiationAdvertisement, LinkSpeed, Pause};
+
+    const ADDR: u8 = 2;
+
+    fn phy(partner_pages: &[NextPage]) -> BarePhy<SimMiim> {
+        let mut sim = SimulatedPhy::for_chip(SimChip::Generic);
+        sim.set_partner_next_pages(partner_pages);
+        sim.connect(LinkPartner {
+            advertisement: AutoNegotiationAdvertisement {
+                fd_100base_tx: true,
+                ..Default::default()
+            },
+            autonegotiation: true,
+        });
+
+        let mut miim = SimMiim::new();
+        miim.add(ADDR, sim);
+        BarePhy::new(miim, ADDR, Pause::NoPause).unwrap()
+    }
+
+    fn local_next_page_flag(phy: &mut BarePhy<SimMiim>) -> bool {
+        let local_cap = phy.read(AutoNegCap::LOCAL_CAP_ADDRESS).unwrap();
+        AutoNegCap::from_bits_truncate(local_cap).contains(AutoNegCap::NEXT_PAGE)
+    }
+
+    fn exchange<'a, const N: usize>(
+        phy: &mut BarePhy<SimMiim>,
+        pages: &'a [NextPage],
+    ) -> NextPageExchange<'a, N> {
+        let mut exchange = NextPageExchange::start(phy, pages, NextPage::RECEIVE_ADDR).unwrap();
+        assert!(local_next_page_flag(phy));
+
+        for _ in 0..16 {
+            if exchange.poll(phy).unwrap() == NextPageStatus::Done {
+                return exchange;
+            }
+        }
+        panic!("The next page exchange did not complete");
+    }
+
+    #[test]
+    fn full_exchange() {
+        let local = [NextPage::message(5), NextPage::unformatted(0x123)];
+        let partner = [
+            NextPage::message(7),
+            NextPage::unformatted(0x456),
+            NextPage::unformatted(0x789),
+        ];
+        let mut phy = phy(&partner);
+
+        let exchange = exchange::<4>(&mut phy, &local);
+        assert!(exchange.is_done());
+        assert_eq!(exchange.sent(), local.len());
+        assert!(!exchange.overflowed());
+
+        // The received pages have their next page, ACK and toggle bits set
+        // by the link partner.
+        let flags = NextPage::NEXT_PAGE | NextPage::ACK | NextPage::TOGGLE;
+        let received = exchange.received();
+        assert_eq!(received.len(), partner.len());
+        for (received, sent) in received.iter().zip(&partner) {
+            assert_eq!(*received - flags, *sent);
+        }
+        assert!(received[0].next_page() && received[1].next_page());
+        assert!(!received[2].next_page());
+
+        // The local device sent a Null Message page after its own pages
+        let last = phy.read(NextPage::TRANSMIT_ADDR).unwrap();
+        assert_eq!(last, NextPage::null_message().bits());
+
+        assert!(!local_next_page_flag(&mut phy));
+        assert!(phy.autoneg_completed().unwrap());
+        assert_eq!(
+            phy.get_miim().phy(ADDR).unwrap().link().map(|link| link.0),
+            Some(LinkSpeed::Mbps100)
+        );
+    }
+
+    #[test]
+    fn partner_without_next_pages() {
+        let local = [NextPage::message(5)];
+        let mut phy = phy(&[]);
+
+        let exchange = exchange::<4>(&mut phy, &local);
+        assert_eq!(exchange.sent(), 0);
+        assert!(exchange.received().is_empty());
+        assert!(!local_next_page_flag(&mut phy));
+        assert!(phy.autoneg_completed().unwrap());
+    }
+}
@@ -0,0 +1,221 @@
+//! A PHY whose driver is selected at runtime from its identifier.
+
+#[cfg(feature = "dp83640")]
+use super::bare::DP83640_IDENT;
+#[cfg(feature = "dp83848")]
+use super::bare::DP83848_IDENT;
+#[cfg(feature = "ksz8081r")]
+use super::bare::KSZ8081R_IDENT;
+#[cfg(feature = "lan8720a")]
+use super::bare::LAN8720A_IDENT;
+#[cfg(feature = "lan8742a")]
+use super::bare::LAN8742A_IDENT;
+use super::{
+    bare::IDENT_MASK, AdvancedPhySpeed, BarePhy, InterruptEvents, PhyWithInterrupts, PhyWithSpeed,
+};
+use crate::{
+    registers::{Ane, Bcr, Bsr, Esr},
+    AutoNegotiationAdvertisement, ExtendedPhyStatus, Miim, Pause, Phy, PhyIdent, PhyStatus,
+};
+
+/// Any of the PHYs supported by this crate.
+///
+/// Use [`AnyPhy::detect`] to select the correct driver based on the
+/// identifier reported by the PHY.
+#[derive(Debug)]
+#[allow(missing_docs)]
+pub enum AnyPhy<M> {
+    #[cfg(feature = "lan8720a")]
+    LAN8720A(super::LAN8720A<M>),
+    #[cfg(feature = "lan8742a")]
+    LAN8742A(super::LAN8742A<M>),
+    #[cfg(feature = "ksz8081r")]
+    KSZ8081R(super::KSZ8081R<M>),
+    #[cfg(feature = "dp83640")]
+    DP83640(super::DP83640<M>),
+    #[cfg(feature = "dp83848")]
+    DP83848(super::DP83848<M>),
+    /// A PHY that is not supported by any of the enabled drivers
+    Bare(BarePhy<M>),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $phy:ident => $e:expr) => {
+        match $self {
+            #[cfg(feature = "lan8720a")]
+            AnyPhy::LAN8720A($phy) => $e,
+            #[cfg(feature = "lan8742a")]
+            AnyPhy::LAN8742A($phy) => $e,
+            #[cfg(feature = "ksz8081r")]
+            AnyPhy::KSZ8081R($phy) => $e,
+            #[cfg(feature = "dp83640")]
+            AnyPhy::DP83640($phy) => $e,
+            #[cfg(feature = "dp83848")]
+            AnyPhy::DP83848($phy) => $e,
+            AnyPhy::Bare($phy) => $e,
+        }
+    };
+}
+
+impl<M: Miim> AnyPhy<M> {
+    /// Read the identifier of the PHY at `phy_addr`, and create the driver
+    /// that matches it.
+    ///
+    /// If no enabled driver matches the identifier, or the PHY does not report
+    /// an identifier, a [`BarePhy`] advertising [`Pause::NoPause`] is returned.
+    pub fn detect(miim: M, phy_addr: u8) -> Result<Self, M::Error> {
+        let mut bare = BarePhy::new(miim, phy_addr, Pause::NoPause)?;
+
+        let ident = match bare.phy_ident()? {
+            Some(ident) => ident.raw_u32() & IDENT_MASK,
+            None => return Ok(Self::Bare(bare)),
+        };
+
+        let me = match ident {
+            #[cfg(feature = "lan8720a")]
+            LAN8720A_IDENT => Self::LAN8720A(super::LAN8720A::new(bare.release(), phy_addr)),
+            #[cfg(feature = "lan8742a")]
+            LAN8742A_IDENT => Self::LAN8742A(super::LAN8742A::new(bare.release(), phy_addr)),
+            #[cfg(feature = "ksz8081r")]
+            KSZ8081R_IDENT => Self::KSZ8081R(super::KSZ8081R::new(bare.release(), phy_addr)),
+            #[cfg(feature = "dp83640")]
+            DP83640_IDENT => Self::DP83640(super::DP83640::new(bare.release(), phy_addr)),
+            #[cfg(feature = "dp83848")]
+            DP83848_IDENT => Self::DP83848(super::DP83848::new(bare.release(), phy_addr)),
+            _ => Self::Bare(bare),
+        };
+
+        Ok(me)
+    }
+}
+
+impl<M> AnyPhy<M> {
+    /// Release the underlying [`Miim`]
+    pub fn release(self) -> M {
+        dispatch!(self, phy => phy.release())
+    }
+}
+
+impl<M: Miim> Phy<M> for AnyPhy<M> {
+    fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+        dispatch!(self, phy => phy.best_supported_advertisement())
+    }
+
+    fn get_miim(&mut self) -> &mut M {
+        dispatch!(self, phy => phy.get_miim())
+    }
+
+    fn get_phy_addr(&self) -> u8 {
+        dispatch!(self, phy => phy.get_phy_addr())
+    }
+
+    fn supports_clause45(&self) -> bool {
+        dispatch!(self, phy => phy.supports_clause45())
+    }
+
+    fn read(&mut self, address: u8) -> Result<u16, M::Error> {
+        dispatch!(self, phy => phy.read(address))
+    }
+
+    fn write(&mut self, address: u8, value: u16) -> Result<(), M::Error> {
+        dispatch!(self, phy => phy.write(address, value))
+    }
+
+    fn bcr(&mut self) -> Result<Bcr, M::Error> {
+        dispatch!(self, phy => phy.bcr())
+    }
+
+    fn modify_bcr<F>(&mut self, f: F) -> Result<(), M::Error>
+    where
+        F: FnOnce(&mut Bcr),
+    {
+        dispatch!(self, phy => phy.modify_bcr(f))
+    }
+
+    fn is_resetting(&mut self) -> Result<bool, M::Error> {
+        dispatch!(self, phy => phy.is_resetting())
+    }
+
+    fn reset(&mut self) -> Result<(), M::Error> {
+        dispatch!(self, phy => phy.reset())
+    }
+
+    fn blocking_reset(&mut self) -> Result<(), M::Error> {
+        dispatch!(self, phy => phy.blocking_reset())
+    }
+
+    fn bsr(&mut self) -> Result<Bsr, M::Error> {
+        dispatch!(self, phy => phy.bsr())
+    }
+
+    fn phy_link_up(&mut self) -> Result<bool, M::Error> {
+        dispatch!(self, phy => phy.phy_link_up())
+    }
+
+    fn autoneg_completed(&mut self) -> Result<bool, M::Error> {
+        dispatch!(self, phy => phy.autoneg_completed())
+    }
+
+    fn status(&mut self) -> Result<PhyStatus, M::Error> {
+        dispatch!(self, phy => phy.status())
+    }
+
+    fn esr(&mut self) -> Result<Option<Esr>, M::Error> {
+        dispatch!(self, phy => phy.esr())
+    }
+
+    fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, M::Error> {
+        dispatch!(self, phy => phy.extended_status())
+    }
+
+    fn phy_ident(&mut self) -> Result<Option<PhyIdent>, M::Error> {
+        dispatch!(self, phy => phy.phy_ident())
+    }
+
+    fn set_autonegotiation_advertisement(
+        &mut self,
+        ad: AutoNegotiationAdvertisement,
+    ) -> Result<(), M::Error> {
+        dispatch!(self, phy => phy.set_autonegotiation_advertisement(ad))
+    }
+
+    fn get_autonegotiation_caps(
+        &mut self,
+    ) -> Result<Option<AutoNegotiationAdvertisement>, M::Error> {
+        dispatch!(self, phy => phy.get_autonegotiation_caps())
+    }
+
+    fn get_autonegotiation_partner_caps(
+        &mut self,
+    ) -> Result<Option<AutoNegotiationAdvertisement>, M::Error> {
+        dispatch!(self, phy => phy.get_autonegotiation_partner_caps())
+    }
+
+    fn ane(&mut self) -> Result<Option<Ane>, M::Error> {
+        dispatch!(self, phy => phy.ane())
+    }
+}
+
+impl<M: Miim> PhyWithSpeed<M> for AnyPhy<M> {
+    fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, M::Error> {
+        dispatch!(self, phy => phy.get_link_speed())
+    }
+}
+
+impl<M: Miim> PhyWithInterrupts<M> for AnyPhy<M> {
+    fn supported_interrupts(&self) -> InterruptEvents {
+        dispatch!(self, phy => phy.supported_interrupts())
+    }
+
+    fn enable_interrupts(&mut self, events: InterruptEvents) -> Result<(), M::Error> {
+        dispatch!(self, phy => phy.enable_interrupts(events))
+    }
+
+    fn disable_interrupts(&mut self, events: InterruptEvents) -> Result<(), M::Error> {
+        dispatch!(self, phy => phy.disable_interrupts(events))
+    }
+
+    fn read_and_clear_interrupts(&mut self) -> Result<InterruptEvents, M::Error> {
+        dispatch!(self, phy => phy.read_and_clear_interrupts())
+    }
+}
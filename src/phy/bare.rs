@@ -1,17 +1,16 @@
 //! A bare phy that does not have any compile-time configurations
 //! assigned to it.
 
-use crate::{AutoNegotiationAdvertisement, Miim, Pause, Phy};
+use super::{AdvancedPhySpeed, InterruptEvents, PhyWithInterrupts, PhyWithSpeed};
+use crate::{AutoNegotiationAdvertisement, Duplex, LinkSpeed, Miim, Pause, Phy};
 
 /// A base phy
 #[derive(Debug)]
-pub struct BarePhy<MIIM>
-where
-    MIIM: Miim,
-{
+pub struct BarePhy<MIIM> {
     phy_address: u8,
     miim: MIIM,
     best_supported_advertisement: AutoNegotiationAdvertisement,
+    clause45: bool,
 }
 
 impl<MIIM> BarePhy<MIIM>
@@ -23,20 +22,28 @@ where
     ///
     /// The PHY will calculate it's best supported advertisement on the fly from
     /// details acquired through `miim`.
-    pub fn new(miim: MIIM, phy_address: u8, pause: Pause) -> Self {
+    pub fn new(miim: MIIM, phy_address: u8, pause: Pause) -> Result<Self, MIIM::Error> {
         let mut me = Self {
             phy_address,
             miim,
             best_supported_advertisement: Default::default(),
+            clause45: false,
         };
 
-        let mut ana = me.status().best_autoneg_ad();
+        let mut ana = me.status()?.best_autoneg_ad();
         ana.pause = pause;
 
+        if let Some(ext) = me.extended_status()? {
+            ana.fd_1000base_t = ext.fd_1000base_t;
+            ana.hd_1000base_t = ext.hd_1000base_t;
+        }
+
         me.best_supported_advertisement = ana;
-        me
+        Ok(me)
     }
+}
 
+impl<MIIM> BarePhy<MIIM> {
     /// Release the underlying MIIM
     pub fn release(self) -> MIIM {
         self.miim
@@ -46,6 +53,13 @@ where
     pub fn set_phy_addr(&mut self, phy_address: u8) {
         self.phy_address = phy_address;
     }
+
+    /// Configure whether this PHY responds to Clause 45 management frames.
+    ///
+    /// This is `false` by default.
+    pub fn set_clause45(&mut self, clause45: bool) {
+        self.clause45 = clause45;
+    }
 }
 
 impl<MIIM> Phy<MIIM> for BarePhy<MIIM>
@@ -63,24 +77,182 @@ where
     fn get_phy_addr(&self) -> u8 {
         self.phy_address
     }
+
+    fn supports_clause45(&self) -> bool {
+        self.clause45
+    }
+}
+
+impl<MIIM> PhyWithSpeed<MIIM> for BarePhy<MIIM>
+where
+    MIIM: Miim,
+{
+    /// Get the link speed from the configuration in the BCR if autonegotiation
+    /// is disabled, or using [`Phy::resolve_autonegotiation`] otherwise.
+    fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, MIIM::Error> {
+        if !self.phy_link_up()? {
+            return Ok(None);
+        }
+
+        let bcr = self.bcr()?;
+        if !bcr.autonegotiation() {
+            let duplex = if bcr.full_duplex() {
+                Duplex::Full
+            } else {
+                Duplex::Half
+            };
+            return Ok(AdvancedPhySpeed::new(LinkSpeed::from(bcr), duplex));
+        }
+
+        Ok(self
+            .resolve_autonegotiation()?
+            .and_then(|result| AdvancedPhySpeed::new(result.speed, result.duplex)))
+    }
 }
 
-pub enum IdentPhyError {
+/// IEEE 802.3 does not define interrupts, so a bare PHY does not support any.
+impl<MIIM> PhyWithInterrupts<MIIM> for BarePhy<MIIM>
+where
+    MIIM: Miim,
+{
+    fn supported_interrupts(&self) -> InterruptEvents {
+        InterruptEvents::empty()
+    }
+
+    fn enable_interrupts(&mut self, _events: InterruptEvents) -> Result<(), MIIM::Error> {
+        Ok(())
+    }
+
+    fn disable_interrupts(&mut self, _events: InterruptEvents) -> Result<(), MIIM::Error> {
+        Ok(())
+    }
+
+    fn read_and_clear_interrupts(&mut self) -> Result<InterruptEvents, MIIM::Error> {
+        Ok(InterruptEvents::empty())
+    }
+}
+
+#[cfg(feature = "mmd")]
+impl<MIIM> super::PhyWithEee<MIIM> for BarePhy<MIIM> where MIIM: Miim {}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use super::BarePhy;
+    use crate::{
+        phy::{AdvancedPhySpeed, AsyncPhyWithSpeed},
+        AsyncMiim, AsyncPhy, AutoNegotiationAdvertisement, Duplex, LinkSpeed, Pause,
+    };
+
+    impl<MIIM> BarePhy<MIIM>
+    where
+        MIIM: AsyncMiim,
+    {
+        /// Create a new bare PHY backed by an [`AsyncMiim`].
+        ///
+        /// This is the asynchronous version of [`BarePhy::new`].
+        pub async fn new_async(
+            miim: MIIM,
+            phy_address: u8,
+            pause: Pause,
+        ) -> Result<Self, MIIM::Error> {
+            let mut me = Self {
+                phy_address,
+                miim,
+                best_supported_advertisement: Default::default(),
+                clause45: false,
+            };
+
+            let mut ana = me.status().await?.best_autoneg_ad();
+            ana.pause = pause;
+
+            if let Some(ext) = me.extended_status().await? {
+                ana.fd_1000base_t = ext.fd_1000base_t;
+                ana.hd_1000base_t = ext.hd_1000base_t;
+            }
+
+            me.best_supported_advertisement = ana;
+            Ok(me)
+        }
+    }
+
+    impl<MIIM> AsyncPhy<MIIM> for BarePhy<MIIM>
+    where
+        MIIM: AsyncMiim,
+    {
+        fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+            self.best_supported_advertisement
+        }
+
+        fn get_miim(&mut self) -> &mut MIIM {
+            &mut self.miim
+        }
+
+        fn get_phy_addr(&self) -> u8 {
+            self.phy_address
+        }
+    }
+
+    impl<MIIM> AsyncPhyWithSpeed<MIIM> for BarePhy<MIIM>
+    where
+        MIIM: AsyncMiim,
+    {
+        async fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, MIIM::Error> {
+            if !self.phy_link_up().await? {
+                return Ok(None);
+            }
+
+            let bcr = self.bcr().await?;
+            if !bcr.autonegotiation() {
+                let duplex = if bcr.full_duplex() {
+                    Duplex::Full
+                } else {
+                    Duplex::Half
+                };
+                return Ok(AdvancedPhySpeed::new(LinkSpeed::from(bcr), duplex));
+            }
+
+            Ok(self
+                .resolve_autonegotiation()
+                .await?
+                .and_then(|result| AdvancedPhySpeed::new(result.speed, result.duplex)))
+        }
+    }
+}
+
+pub enum IdentPhyError<E> {
     PhyIdentUnavailable,
     IncorrectPhyIdent,
+    Miim(E),
 }
 
+/// The mask applied to a raw PHY identifier to strip the revision number
+pub(crate) const IDENT_MASK: u32 = 0xFFFFFFF0;
+#[cfg(feature = "ksz8081r")]
+pub(crate) const KSZ8081R_IDENT: u32 = 0x00221560;
+#[cfg(feature = "lan8720a")]
+pub(crate) const LAN8720A_IDENT: u32 = 0x0007C0F0;
+#[cfg(feature = "lan8742a")]
+pub(crate) const LAN8742A_IDENT: u32 = 0x0007C130;
+#[cfg(feature = "dp83640")]
+pub(crate) const DP83640_IDENT: u32 = 0x20005CE0;
+#[cfg(feature = "dp83848")]
+pub(crate) const DP83848_IDENT: u32 = 0x20005C90;
+
 macro_rules! into_phy {
-    ($([$feat:literal, $phy:ident, $id:literal],)*) => {
+    ($([$feat:literal, $phy:ident, $id:expr],)*) => {
         $(
             #[cfg(feature = $feat)]
             impl<MIIM: Miim> TryFrom<BarePhy<MIIM>> for super::$phy<MIIM> {
-                type Error = IdentPhyError;
+                type Error = IdentPhyError<MIIM::Error>;
 
                 fn try_from(mut value: BarePhy<MIIM>) -> Result<Self, Self::Error> {
-                    let phy_ident = value.phy_ident().ok_or(IdentPhyError::PhyIdentUnavailable)?.raw_u32();
+                    let phy_ident = value
+                        .phy_ident()
+                        .map_err(IdentPhyError::Miim)?
+                        .ok_or(IdentPhyError::PhyIdentUnavailable)?
+                        .raw_u32();
 
-                    if phy_ident & 0xFFFFFFF0 == $id {
+                    if phy_ident & IDENT_MASK == $id {
                         Ok(super::$phy::new(value.miim, value.phy_address))
                     } else {
                         Err(IdentPhyError::IncorrectPhyIdent)
@@ -92,9 +264,9 @@ macro_rules! into_phy {
 }
 
 into_phy!(
-    ["ksz8081r", KSZ8081R, 0x00221560],
-    ["lan8720a", LAN8720A, 0x0007C0F0],
-    ["lan8742a", LAN8742A, 0x0007C130],
-    ["dp83xxx", DP83640, 0x20005CE1],
-    ["dp83xxx", DP83848, 0x20005C62],
+    ["ksz8081r", KSZ8081R, KSZ8081R_IDENT],
+    ["lan8720a", LAN8720A, LAN8720A_IDENT],
+    ["lan8742a", LAN8742A, LAN8742A_IDENT],
+    ["dp83640", DP83640, DP83640_IDENT],
+    ["dp83848", DP83848, DP83848_IDENT],
 );
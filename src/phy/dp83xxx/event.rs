@@ -0,0 +1,282 @@
+//! The event units of the DP83640, which timestamp edges on its GPIOs.
+
+use crate::{Miim, PtpTime};
+
+use super::registers::{PTPESTS, PTPEVNT, PTPSTS, PTP_EDATA};
+use super::DP83640;
+
+/// The edges of a signal that are timestamped by an event unit
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventEdge {
+    /// Rising edges
+    Rising,
+    /// Falling edges
+    Falling,
+    /// Both rising and falling edges
+    Both,
+}
+
+/// The configuration of an event unit
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventConfig {
+    /// The event unit, 0 to 7. Only the low 3 bits are used.
+    pub event: u8,
+    /// The GPIO that is monitored, 1 to 12
+    pub gpio: u8,
+    /// The edges that are timestamped
+    pub edge: EventEdge,
+    /// Only timestamp the first edge, and disable the event unit afterwards
+    pub single: bool,
+}
+
+/// A timestamped event, read from PTP_ESTS and PTP_EDATA
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EventTimestamp {
+    /// The time at which the event occurred
+    pub time: PtpTime,
+    /// A bit for each event unit that detected an event at `time`
+    pub events: u8,
+    /// The event was a rising edge. If multiple event units detected an
+    /// event, this applies to the lowest one.
+    pub rising: bool,
+    /// The amount of events that were dropped because the event queue was
+    /// full, saturating at 7.
+    pub missed: u8,
+}
+
+impl<MIIM: Miim> DP83640<MIIM> {
+    fn event_select(event: u8) -> PTPEVNT {
+        PTPEVNT::from_bits_truncate((event as u16) << PTPEVNT::SEL_SHIFT) & PTPEVNT::EVNT_SEL
+    }
+
+    /// Configure and enable an event unit
+    pub fn configure_event(&mut self, config: EventConfig) -> Result<(), MIIM::Error> {
+        let gpio = (config.gpio as u16) << PTPEVNT::GPIO_SHIFT;
+
+        let mut evnt = PTPEVNT::from_bits_truncate(gpio) & PTPEVNT::EVNT_GPIO;
+        evnt |= Self::event_select(config.event) | PTPEVNT::EVNT_WR;
+        evnt |= match config.edge {
+            EventEdge::Rising => PTPEVNT::EVNT_RISE,
+            EventEdge::Falling => PTPEVNT::EVNT_FALL,
+            EventEdge::Both => PTPEVNT::EVNT_RISE | PTPEVNT::EVNT_FALL,
+        };
+        evnt.set(PTPEVNT::EVNT_SINGLE, config.single);
+
+        self.write_paged(evnt)
+    }
+
+    /// Disable an event unit
+    pub fn disable_event(&mut self, event: u8) -> Result<(), MIIM::Error> {
+        self.write_paged(Self::event_select(event) | PTPEVNT::EVNT_WR)
+    }
+
+    /// Read the oldest event timestamp, if one is available.
+    ///
+    /// If events were dropped since the previous event was read,
+    /// [`EventTimestamp::missed`] is non-zero.
+    pub fn read_event(&mut self) -> Result<Option<EventTimestamp>, MIIM::Error> {
+        let mut data = self.event_data;
+
+        let event = self.paged(|regs| {
+            let status: PTPSTS = regs.read()?;
+            if !status.contains(PTPSTS::EVENT_RDY) {
+                return Ok(None);
+            }
+
+            let ests: PTPESTS = regs.read()?;
+            if !ests.contains(PTPESTS::EVENT_DET) {
+                return Ok(None);
+            }
+
+            let events = if ests.contains(PTPESTS::MULT_EVNT) {
+                regs.read_raw(PTP_EDATA)? as u8
+            } else {
+                1 << ((ests & PTPESTS::EVNT_NUM).bits() >> PTPESTS::NUM_SHIFT)
+            };
+
+            // The timestamp is shortened to the words that differ from the
+            // previous event, starting with the low word of the nanoseconds
+            let words = ((ests & PTPESTS::EVNT_TS_LEN).bits() >> PTPESTS::TS_LEN_SHIFT) + 1;
+            for word in data.iter_mut().take(words as usize) {
+                *word = regs.read_raw(PTP_EDATA)?;
+            }
+
+            Ok(Some((ests, events)))
+        })?;
+
+        let (ests, events) = match event {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+        self.event_data = data;
+
+        let [ns_low, ns_high, sec_low, sec_high] = data.map(u32::from);
+        Ok(Some(EventTimestamp {
+            time: PtpTime {
+                seconds: (sec_high << 16) | sec_low,
+                nanoseconds: ((ns_high & 0x3FFF) << 16) | ns_low,
+            },
+            events,
+            rising: ests.contains(PTPESTS::EVNT_RF),
+            missed: ((ests & PTPESTS::EVNTS_MISSED).bits() >> PTPESTS::MISSED_SHIFT) as u8,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phy::dp83xxx::registers::PAGESEL;
+    use crate::sim::{SimChip, SimMiim, SimulatedPhy};
+    use crate::trace::{Direction, RecordingMiim, TraceBuffer, Transaction};
+    use crate::Phy;
+
+    const ADDR: u8 = 1;
+
+    type TracedPhy<'a> = DP83640<RecordingMiim<SimMiim, TraceBuffer<'a>>>;
+
+    fn traced(trace: &mut [Transaction]) -> TracedPhy<'_> {
+        let mut sim = SimMiim::new();
+        sim.add(ADDR, SimulatedPhy::for_chip(SimChip::Dp83640));
+        DP83640::new(RecordingMiim::new(sim, TraceBuffer::new(trace)), ADDR)
+    }
+
+    /// Queue an event with status `ests`, whose words all read as `edata`
+    fn queue_event(phy: &mut TracedPhy, ests: PTPESTS, edata: u16) {
+        let sim = phy.get_miim().inner_mut().phy_mut(ADDR).unwrap();
+        let (page, reg) = PTPSTS::ADDRESS;
+        sim.set_page_register(page, reg, PTPSTS::EVENT_RDY.bits());
+        let (page, reg) = PTPESTS::ADDRESS;
+        sim.set_page_register(page, reg, ests.bits());
+        sim.set_page_register(PTP_EDATA.0, PTP_EDATA.1, edata);
+    }
+
+    /// Read an event, and count the reads of PTP_EDATA
+    fn read_event(phy: &mut TracedPhy) -> (Option<EventTimestamp>, usize) {
+        phy.get_miim().log_mut().clear();
+        let event = phy.read_event().unwrap();
+        let log = phy.get_miim().log();
+        let reads = log
+            .iter()
+            .filter(|t| t.direction == Direction::Read && t.reg == PTP_EDATA.1 as u16)
+            .count();
+        (event, reads)
+    }
+
+    fn ests(words: u16, num: u16) -> PTPESTS {
+        let len = PTPESTS::from_bits_truncate((words - 1) << PTPESTS::TS_LEN_SHIFT);
+        let num = PTPESTS::from_bits_truncate(num << PTPESTS::NUM_SHIFT);
+        PTPESTS::EVENT_DET | (len & PTPESTS::EVNT_TS_LEN) | (num & PTPESTS::EVNT_NUM)
+    }
+
+    #[test]
+    fn no_event() {
+        let mut trace = [Transaction::read(0, 0, 0); 16];
+        let mut phy = traced(&mut trace);
+        assert_eq!(read_event(&mut phy), (None, 0));
+
+        // PTP_ESTS is only read if PTPSTS reports an event
+        {
+            let log = phy.get_miim().log();
+            let mut reads = log.iter().filter(|t| t.direction == Direction::Read);
+            assert_eq!(reads.next().unwrap().reg, PTPSTS::ADDRESS.1 as u16);
+            assert!(reads.next().is_none());
+        }
+
+        // An event that is ready, but not detected, is not read either
+        queue_event(&mut phy, PTPESTS::empty(), 0);
+        assert_eq!(read_event(&mut phy), (None, 0));
+    }
+
+    #[test]
+    fn full_timestamp() {
+        let mut trace = [Transaction::read(0, 0, 0); 16];
+        let mut phy = traced(&mut trace);
+        queue_event(&mut phy, ests(4, 3) | PTPESTS::EVNT_RF, 0x1234);
+
+        let (event, reads) = read_event(&mut phy);
+        assert_eq!(reads, 4);
+        assert_eq!(
+            event,
+            Some(EventTimestamp {
+                time: PtpTime {
+                    seconds: 0x1234_1234,
+                    nanoseconds: 0x1234_1234,
+                },
+                events: 1 << 3,
+                rising: true,
+                missed: 0,
+            })
+        );
+
+        // The event data is read on page 4, after which page 0 is selected
+        let log = phy.get_miim().log();
+        let pages = log
+            .iter()
+            .filter(|t| t.direction == Direction::Write && t.reg == PAGESEL as u16)
+            .map(|t| t.value);
+        assert!(pages.eq([4, 0]));
+    }
+
+    #[test]
+    fn shortened_timestamps() {
+        let mut trace = [Transaction::read(0, 0, 0); 16];
+        let mut phy = traced(&mut trace);
+        queue_event(&mut phy, ests(4, 0), 0x0001);
+        assert_eq!(read_event(&mut phy).1, 4);
+
+        // Only the low word of the nanoseconds changed
+        queue_event(&mut phy, ests(1, 0), 0x0002);
+        let (event, reads) = read_event(&mut phy);
+        assert_eq!(reads, 1);
+        let time = event.unwrap().time;
+        assert_eq!((time.seconds, time.nanoseconds), (0x0001_0001, 0x0001_0002));
+
+        // Both words of the nanoseconds changed
+        queue_event(&mut phy, ests(2, 0), 0x0003);
+        let (event, reads) = read_event(&mut phy);
+        assert_eq!(reads, 2);
+        let time = event.unwrap().time;
+        assert_eq!((time.seconds, time.nanoseconds), (0x0001_0001, 0x0003_0003));
+
+        // The nanoseconds and the low word of the seconds changed
+        queue_event(&mut phy, ests(3, 0), 0x0004);
+        let (event, reads) = read_event(&mut phy);
+        assert_eq!(reads, 3);
+        let time = event.unwrap().time;
+        assert_eq!((time.seconds, time.nanoseconds), (0x0001_0004, 0x0004_0004));
+    }
+
+    #[test]
+    fn multiple_events() {
+        let mut trace = [Transaction::read(0, 0, 0); 16];
+        let mut phy = traced(&mut trace);
+
+        // The events are read from PTP_EDATA before the timestamp, and
+        // EVNT_NUM is ignored
+        queue_event(&mut phy, ests(1, 7) | PTPESTS::MULT_EVNT, 0x0025);
+        let (event, reads) = read_event(&mut phy);
+        assert_eq!(reads, 2);
+        let event = event.unwrap();
+        assert_eq!(event.events, 0x25);
+        assert!(!event.rising);
+        assert_eq!(event.time.nanoseconds, 0x0025);
+    }
+
+    #[test]
+    fn missed_events() {
+        let mut trace = [Transaction::read(0, 0, 0); 16];
+        let mut phy = traced(&mut trace);
+
+        for missed in 0..=7 {
+            let count = PTPESTS::from_bits_truncate(missed << PTPESTS::MISSED_SHIFT);
+            queue_event(&mut phy, ests(4, 0) | count, 0);
+            let event = read_event(&mut phy).0.unwrap();
+            assert_eq!(event.missed, missed as u8);
+            assert_eq!(event.events, 1);
+        }
+    }
+}
@@ -0,0 +1,1265 @@
+//! Phy implementation for the TI DP83xxx Series
+
+use crate::registers::{Bcr, Esr};
+use crate::{AutoNegotiationAdvertisement, ExtendedPhyStatus, Miim, Phy};
+#[cfg(feature = "dp83640")]
+use crate::{PtpTime, PTP};
+
+use self::registers::{MICR, MISR, PAGESEL, PHYSTS};
+#[cfg(feature = "dp83640")]
+use self::registers::{PTPCTL, PTP_RATEH, PTP_RATEL, PTP_TDR, PTP_TRDH, PTP_TRDL};
+
+use super::{AdvancedPhySpeed, InterruptEvents, PhySpeed, PhyWithInterrupts, PhyWithSpeed};
+
+#[cfg(feature = "dp83640")]
+mod event;
+mod paged;
+#[cfg(feature = "dp83640")]
+mod timestamp;
+#[cfg(feature = "dp83640")]
+mod trigger;
+#[cfg(feature = "dp83640")]
+pub use event::{EventConfig, EventEdge, EventTimestamp};
+pub use paged::PagedAccess;
+#[cfg(feature = "dp83640")]
+pub use timestamp::{
+    PtpTransports, RxTimestamp, RxTimestampConfig, TxTimestamp, TxTimestampConfig,
+};
+#[cfg(feature = "dp83640")]
+pub use trigger::{TriggerConfig, TriggerStatus};
+
+/// A DP83xxx series PHY
+#[derive(Debug)]
+pub struct DP83XXX<MIIM, const PTP: bool> {
+    phy_addr: u8,
+    miim: MIIM,
+    /// The page that is currently selected, if it is known
+    page: Option<u16>,
+    /// The timestamp words of the last event read from PTP_EDATA, which
+    /// provide the upper words of shortened event timestamps
+    #[cfg(feature = "dp83640")]
+    event_data: [u16; 4],
+}
+
+/// DP83640 with hardware PTP stamping
+pub type DP83640<MIIM> = DP83XXX<MIIM, true>;
+
+/// DP83848
+pub type DP83848<MIIM> = DP83XXX<MIIM, false>;
+
+impl<MIIM, const PTP_EN: bool> DP83XXX<MIIM, PTP_EN> {
+    /// Create a new DP83xxx at `phy_addr`, backed by the given `miim`,
+    pub fn new(miim: MIIM, phy_addr: u8) -> Self {
+        Self {
+            phy_addr,
+            miim,
+            page: None,
+            #[cfg(feature = "dp83640")]
+            event_data: [0; 4],
+        }
+    }
+
+    /// Release the underlying [`Miim`]
+    pub fn release(self) -> MIIM {
+        self.miim
+    }
+}
+
+impl<MIIM: Miim, const PTP_EN: bool> DP83XXX<MIIM, PTP_EN> {
+    const INTERRUPT_REG: u8 = MISR::ADDRESS;
+    const INTERRUPT_REG_EN_LINK_CHANGE: u16 = MISR::LINK_INT_EN.bits();
+    /// A mask for determining if the Link Status Change Interrupt occurred
+    pub const INTERRUPT_REG_INT_LINK_CHANGE: u16 = MISR::LINK_INT.bits();
+
+    /// Enable the link status change interrupt
+    pub fn interrupt_enable(&mut self) -> Result<(), MIIM::Error> {
+        self.write(Self::INTERRUPT_REG, Self::INTERRUPT_REG_EN_LINK_CHANGE)?;
+        self.write(MICR::ADDRESS, (MICR::INTEN | MICR::INT_OE).bits())
+    }
+
+    /// Get the link speed at which the PHY is currently operating
+    pub fn link_speed(&mut self) -> Result<Option<PhySpeed>, MIIM::Error> {
+        let phy_ctrl1 = PHYSTS::from_bits_truncate(self.read(PHYSTS::ADDRESS)?);
+        Ok(phy_ctrl1.into())
+    }
+
+    /// Get the value of the interrupt register.
+    pub fn get_interrupt_reg_val(&mut self) -> Result<u16, MIIM::Error> {
+        self.read(Self::INTERRUPT_REG)
+    }
+
+    /// Check whether a link is established or not
+    pub fn link_established(&mut self) -> Result<bool, MIIM::Error> {
+        Ok(self.autoneg_completed()? && self.phy_link_up()?)
+    }
+
+    /// Write `value` to the register at `address_ext`, a page and a register.
+    ///
+    /// Use [`Self::paged`] to access multiple registers on the same page.
+    pub fn write_ext(&mut self, address_ext: (u16, u8), value: u16) -> Result<(), MIIM::Error> {
+        self.paged(|regs| regs.write_raw(address_ext, value))
+    }
+
+    /// Read the register at `address_ext`, a page and a register.
+    ///
+    /// Use [`Self::paged`] to access multiple registers on the same page.
+    pub fn read_ext(&mut self, address_ext: (u16, u8)) -> Result<u16, MIIM::Error> {
+        self.paged(|regs| regs.read_raw(address_ext))
+    }
+}
+
+impl<MIIM: Miim, const PTP_EN: bool> Phy<MIIM> for DP83XXX<MIIM, PTP_EN> {
+    fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+        AutoNegotiationAdvertisement {
+            hd_10base_t: true,
+            fd_10base_t: true,
+            hd_100base_tx: true,
+            fd_100base_tx: true,
+            base100_t4: true,
+            ..Default::default()
+        }
+    }
+
+    fn get_miim(&mut self) -> &mut MIIM {
+        &mut self.miim
+    }
+
+    fn get_phy_addr(&self) -> u8 {
+        self.phy_addr
+    }
+
+    fn write(&mut self, address: u8, value: u16) -> Result<(), MIIM::Error> {
+        // Writing the page select register or resetting the PHY changes the
+        // selected page
+        let resetting = address == Bcr::ADDRESS && Bcr::from_bits_truncate(value).is_resetting();
+        if address == PAGESEL || resetting {
+            self.page = None;
+        }
+        self.miim.write(self.phy_addr, address, value)
+    }
+
+    fn esr(&mut self) -> Result<Option<Esr>, MIIM::Error> {
+        Ok(None)
+    }
+
+    fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, MIIM::Error> {
+        Ok(None)
+    }
+}
+
+impl<MIIM: Miim, const PTP_EN: bool> PhyWithSpeed<MIIM> for DP83XXX<MIIM, PTP_EN> {
+    fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, MIIM::Error> {
+        Ok(self.link_speed()?.map(Into::into))
+    }
+}
+
+/// The events that the DP83xxx can signal, and their enable and status
+/// flags in the MISR. A link status change is reported as either
+/// [`InterruptEvents::LINK_UP`] or [`InterruptEvents::LINK_DOWN`].
+const DP83XXX_INTERRUPTS: &[(InterruptEvents, MISR, MISR)] = &[
+    (
+        InterruptEvents::ENERGY_DETECT,
+        MISR::ED_INT_EN,
+        MISR::ED_INT,
+    ),
+    (InterruptEvents::LINK_UP, MISR::LINK_INT_EN, MISR::LINK_INT),
+    (
+        InterruptEvents::LINK_DOWN,
+        MISR::LINK_INT_EN,
+        MISR::LINK_INT,
+    ),
+    (
+        InterruptEvents::SPEED_CHANGE,
+        MISR::SPD_INT_EN,
+        MISR::SPD_INT,
+    ),
+    (
+        InterruptEvents::DUPLEX_CHANGE,
+        MISR::DUP_INT_EN,
+        MISR::DUP_INT,
+    ),
+    (
+        InterruptEvents::AUTONEG_COMPLETE,
+        MISR::ANC_INT_EN,
+        MISR::ANC_INT,
+    ),
+    (
+        InterruptEvents::FALSE_CARRIER,
+        MISR::FHF_INT_EN,
+        MISR::FHF_INT,
+    ),
+    (
+        InterruptEvents::RECEIVE_ERROR,
+        MISR::RHF_INT_EN,
+        MISR::RHF_INT,
+    ),
+];
+
+impl<MIIM: Miim, const PTP_EN: bool> DP83XXX<MIIM, PTP_EN> {
+    fn modify_interrupt_enables(&mut self, f: impl FnOnce(&mut MISR)) -> Result<(), MIIM::Error> {
+        // Only keep the enable bits, the status bits are read-only
+        let mut misr = MISR::from_bits_truncate(self.read(MISR::ADDRESS)?) & MISR::ENABLE_MASK;
+        f(&mut misr);
+        self.write(MISR::ADDRESS, misr.bits())?;
+
+        let mut micr = MICR::from_bits_truncate(self.read(MICR::ADDRESS)?);
+        micr.set(MICR::INTEN | MICR::INT_OE, !misr.is_empty());
+        self.write(MICR::ADDRESS, micr.bits())
+    }
+
+    fn interrupt_enables(events: InterruptEvents) -> MISR {
+        DP83XXX_INTERRUPTS
+            .iter()
+            .filter(|(event, _, _)| events.contains(*event))
+            .fold(MISR::empty(), |misr, (_, enable, _)| misr | *enable)
+    }
+}
+
+/// Note that the interrupt status bits of the DP83xxx are cleared when the
+/// MISR is read, so enabling or disabling interrupts also clears any pending
+/// events.
+impl<MIIM: Miim, const PTP_EN: bool> PhyWithInterrupts<MIIM> for DP83XXX<MIIM, PTP_EN> {
+    fn supported_interrupts(&self) -> InterruptEvents {
+        DP83XXX_INTERRUPTS
+            .iter()
+            .fold(InterruptEvents::empty(), |all, (event, _, _)| all | *event)
+    }
+
+    fn enable_interrupts(&mut self, events: InterruptEvents) -> Result<(), MIIM::Error> {
+        let enables = Self::interrupt_enables(events);
+        self.modify_interrupt_enables(|misr| misr.insert(enables))
+    }
+
+    fn disable_interrupts(&mut self, mut events: InterruptEvents) -> Result<(), MIIM::Error> {
+        // Link up and link down share a single interrupt, which
+        // is only disabled if both are.
+        let link = InterruptEvents::LINK_UP | InterruptEvents::LINK_DOWN;
+        if !events.contains(link) {
+            events.remove(link);
+        }
+
+        let enables = Self::interrupt_enables(events);
+        self.modify_interrupt_enables(|misr| misr.remove(enables))
+    }
+
+    fn read_and_clear_interrupts(&mut self) -> Result<InterruptEvents, MIIM::Error> {
+        let misr = MISR::from_bits_truncate(self.read(MISR::ADDRESS)?);
+
+        let mut events = DP83XXX_INTERRUPTS
+            .iter()
+            .filter(|(_, _, status)| misr.contains(*status))
+            .fold(InterruptEvents::empty(), |events, (event, _, _)| {
+                events | *event
+            });
+
+        if misr.contains(MISR::LINK_INT) {
+            if self.phy_link_up()? {
+                events.remove(InterruptEvents::LINK_DOWN);
+            } else {
+                events.remove(InterruptEvents::LINK_UP);
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use super::{registers::PHYSTS, DP83XXX};
+    use crate::{
+        phy::{AdvancedPhySpeed, AsyncPhyWithSpeed, PhySpeed},
+        registers::Esr,
+        AsyncMiim, AsyncPhy, AutoNegotiationAdvertisement, ExtendedPhyStatus,
+    };
+
+    impl<MIIM: AsyncMiim, const PTP_EN: bool> AsyncPhy<MIIM> for DP83XXX<MIIM, PTP_EN> {
+        fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+            AutoNegotiationAdvertisement {
+                hd_10base_t: true,
+                fd_10base_t: true,
+                hd_100base_tx: true,
+                fd_100base_tx: true,
+                base100_t4: true,
+                ..Default::default()
+            }
+        }
+
+        fn get_miim(&mut self) -> &mut MIIM {
+            &mut self.miim
+        }
+
+        fn get_phy_addr(&self) -> u8 {
+            self.phy_addr
+        }
+
+        async fn esr(&mut self) -> Result<Option<Esr>, MIIM::Error> {
+            Ok(None)
+        }
+
+        async fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, MIIM::Error> {
+            Ok(None)
+        }
+    }
+
+    impl<MIIM: AsyncMiim, const PTP_EN: bool> AsyncPhyWithSpeed<MIIM> for DP83XXX<MIIM, PTP_EN> {
+        async fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, MIIM::Error> {
+            let physts = PHYSTS::from_bits_truncate(self.read(PHYSTS::ADDRESS).await?);
+            let speed: Option<PhySpeed> = physts.into();
+            Ok(speed.map(Into::into))
+        }
+    }
+}
+
+#[allow(missing_docs)]
+pub mod registers {
+    use bitflags::bitflags;
+
+    use crate::phy::PhySpeed;
+
+    bitflags! {
+        // PHYSTS contains device status
+        pub struct PHYSTS: u16 {
+            const MII_INTERRUPT = (1<<7);
+            const AUTONEG_COMPLETE = (1<<4);
+            const FULL_DUPLEX = (1<<2);
+            const MBIT10=(1<<1);
+            const LINK_STATUS=(1<<0);
+        }
+    }
+
+    bitflags! {
+        /// MII Interrupt Control Register
+        pub struct MICR: u16 {
+            const TINT = (1 << 2);
+            const INTEN = (1 << 1);
+            const INT_OE = (1 << 0);
+        }
+
+        /// MII Interrupt Status and Event Control Register
+        pub struct MISR: u16 {
+            const LQ_INT = (1 << 15);
+            const ED_INT = (1 << 14);
+            const LINK_INT = (1 << 13);
+            const SPD_INT = (1 << 12);
+            const DUP_INT = (1 << 11);
+            const ANC_INT = (1 << 10);
+            const FHF_INT = (1 << 9);
+            const RHF_INT = (1 << 8);
+            const LQ_INT_EN = (1 << 7);
+            const ED_INT_EN = (1 << 6);
+            const LINK_INT_EN = (1 << 5);
+            const SPD_INT_EN = (1 << 4);
+            const DUP_INT_EN = (1 << 3);
+            const ANC_INT_EN = (1 << 2);
+            const FHF_INT_EN = (1 << 1);
+            const RHF_INT_EN = (1 << 0);
+        }
+    }
+
+    impl MICR {
+        pub const ADDRESS: u8 = 0x11;
+    }
+
+    impl MISR {
+        pub const ADDRESS: u8 = 0x12;
+        pub const ENABLE_MASK: Self = Self::from_bits_truncate(0x00FF);
+    }
+
+    impl PHYSTS {
+        pub const ADDRESS: u8 = 0x10;
+    }
+
+    impl From<PHYSTS> for Option<PhySpeed> {
+        fn from(ctrl: PHYSTS) -> Self {
+            let full_duplex = ctrl.contains(PHYSTS::FULL_DUPLEX);
+            let mbit_10 = ctrl.contains(PHYSTS::MBIT10);
+            let link = ctrl.contains(PHYSTS::LINK_STATUS);
+
+            if !link {
+                return None;
+            }
+
+            let speed = match (full_duplex, mbit_10) {
+                (true, true) => PhySpeed::FullDuplexBase10T,
+                (true, false) => PhySpeed::FullDuplexBase100Tx,
+                (false, true) => PhySpeed::HalfDuplexBase10T,
+                (false, false) => PhySpeed::HalfDuplexBase100Tx,
+            };
+            Some(speed)
+        }
+    }
+
+    /// The page select register. Registers 0x14 to 0x1F are paged, all
+    /// other registers are the same on every page.
+    pub const PAGESEL: u8 = 0x13;
+
+    /// A register in one of the pages of a DP83xxx
+    pub trait PagedRegister: Copy {
+        /// The page and address of the register
+        const ADDRESS: (u16, u8);
+
+        /// Create the register from the value that was read
+        fn from_raw(value: u16) -> Self;
+
+        /// The value to write to the register
+        fn raw(self) -> u16;
+    }
+
+    macro_rules! paged_registers {
+        ($($reg:ty),*) => {
+            $(
+                impl PagedRegister for $reg {
+                    const ADDRESS: (u16, u8) = <$reg>::ADDRESS;
+
+                    fn from_raw(value: u16) -> Self {
+                        Self::from_bits_truncate(value)
+                    }
+
+                    fn raw(self) -> u16 {
+                        self.bits()
+                    }
+                }
+            )*
+        };
+    }
+
+    bitflags! {
+        /// PHY Control Register
+        pub struct PHYCR: u16 {
+            const MDIX_EN = (1 << 15);
+            const FORCE_MDIX = (1 << 14);
+            const PAUSE_RX = (1 << 13);
+            const PAUSE_TX = (1 << 12);
+            const BIST_FE = (1 << 11);
+            const PSR_15 = (1 << 10);
+            const BIST_STATUS = (1 << 9);
+            const BIST_START = (1 << 8);
+            const BP_STRETCH = (1 << 7);
+            const LED_CNFG = (0b11 << 5);
+            const PHYADDR = 0x1F;
+        }
+
+        /// 10Base-T Status/Control Register
+        pub struct TENBTSCR: u16 {
+            const SQUELCH = (0b111 << 9);
+            const LOOPBACK_10_DIS = (1 << 8);
+            const LP_DIS = (1 << 7);
+            const FORCE_LINK_10 = (1 << 6);
+            const POLARITY = (1 << 4);
+            const HEARTBEAT_DIS = (1 << 1);
+            const JABBER_DIS = (1 << 0);
+        }
+
+        /// CD Test and BIST Extensions Register
+        pub struct CDCTRL1: u16 {
+            const BIST_ERROR_COUNT = (0xFF << 8);
+            const BIST_CONT_MODE = (1 << 5);
+            const CDPATTEN_10 = (1 << 4);
+            const PATT_GAP_10M = (1 << 2);
+            const CDPATTSEL = 0b11;
+        }
+
+        /// Energy Detect Control Register
+        pub struct EDCR: u16 {
+            const ED_EN = (1 << 15);
+            const ED_AUTO_UP = (1 << 14);
+            const ED_AUTO_DOWN = (1 << 13);
+            const ED_MAN = (1 << 12);
+            const ED_BURST_DIS = (1 << 11);
+            const ED_PWR_STATE = (1 << 10);
+            const ED_ERR_MET = (1 << 9);
+            const ED_DATA_MET = (1 << 8);
+            const ED_ERR_COUNT = (0xF << 4);
+            const ED_DATA_COUNT = 0xF;
+        }
+    }
+
+    impl PHYCR {
+        pub const ADDRESS: (u16, u8) = (0, 0x19);
+    }
+
+    impl TENBTSCR {
+        pub const ADDRESS: (u16, u8) = (0, 0x1A);
+        pub const SQUELCH_SHIFT: u16 = 9;
+    }
+
+    impl CDCTRL1 {
+        pub const ADDRESS: (u16, u8) = (0, 0x1B);
+    }
+
+    impl EDCR {
+        pub const ADDRESS: (u16, u8) = (0, 0x1D);
+    }
+
+    paged_registers!(PHYCR, TENBTSCR, CDCTRL1, EDCR);
+
+    #[cfg(feature = "dp83640")]
+    bitflags! {
+        /// PHY Control Register 2
+        pub struct PHYCR2: u16 {
+            const SYNC_ENET_EN = (1 << 13);
+            const CLK_OUT_RXCLK = (1 << 12);
+            const BC_WRITE = (1 << 11);
+            const PHYTER_COMP = (1 << 10);
+            const SOFT_RESET = (1 << 9);
+            const CLK_OUT_DIS = (1 << 1);
+        }
+
+        /// PHY Control Frames Configuration Register
+        pub struct PCFCR: u16 {
+            const PCF_STS_ERR = (1 << 15);
+            const PCF_STS_OK = (1 << 14);
+            const PCF_DA_SEL = (1 << 8);
+            const PCF_INT_CTL = (0b11 << 6);
+            const PCF_BC_DIS = (1 << 5);
+            const PCF_BUF = (0xF << 1);
+            const PCF_EN = (1 << 0);
+        }
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PHYCR2 {
+        pub const ADDRESS: (u16, u8) = (0, 0x1C);
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PCFCR {
+        pub const ADDRESS: (u16, u8) = (0, 0x1F);
+    }
+
+    #[cfg(feature = "dp83640")]
+    paged_registers!(PHYCR2, PCFCR);
+
+    #[cfg(feature = "dp83640")]
+    bitflags! {
+        pub struct PTPCTL:u16{
+            const TRIG_SEL = (0b111<<10);
+            const TRIG_DIS = (1<<9);
+            const TRIG_EN = (1<<8);
+            const TRIG_READ = (1<<7);
+            const TRIG_LOAD = (1<<6);
+            const PTP_RESET = (1<<0);
+            const PTP_DISABLE = (1<<1);
+            const PTP_ENABLE = (1<<2);
+            const PTP_STEP_CLK = (1<<3);
+            const PTP_LOAD_CLK = (1<<4);
+            const PTP_RD_CLK = (1<<5);
+        }
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PTPCTL {
+        pub const ADDRESS: (u16, u8) = (0b100, 0x14);
+        pub const TRIG_SEL_SHIFT: u16 = 10;
+    }
+
+    #[cfg(feature = "dp83640")]
+    bitflags! {
+        /// PTP Status Register
+        pub struct PTPSTS: u16 {
+            const TXTS_RDY = (1 << 11);
+            const RXTS_RDY = (1 << 10);
+            const TRIG_DONE = (1 << 9);
+            const EVENT_RDY = (1 << 8);
+            const TXTS_IE = (1 << 3);
+            const RXTS_IE = (1 << 2);
+            const TRIG_IE = (1 << 1);
+            const EVENT_IE = (1 << 0);
+        }
+
+        /// PTP Transmit Configuration Register 0
+        pub struct PTPTXCFG0: u16 {
+            const SYNC_1STEP = (1 << 15);
+            const DR_INSERT = (1 << 13);
+            const NTP_TS_EN = (1 << 12);
+            const IGNORE_2STEP = (1 << 11);
+            const CRC_1STEP = (1 << 10);
+            const CHK_1STEP = (1 << 9);
+            const IP1588_EN = (1 << 8);
+            const TX_L2_EN = (1 << 7);
+            const TX_IPV6_EN = (1 << 6);
+            const TX_IPV4_EN = (1 << 5);
+            const TX_PTP_VER = (0xF << 1);
+            const TX_TS_EN = (1 << 0);
+        }
+
+        /// PTP Receive Configuration Register 0
+        pub struct PTPRXCFG0: u16 {
+            const DOMAIN_EN = (1 << 15);
+            const ALT_MAST_DIS = (1 << 14);
+            const USER_IP_SEL = (1 << 13);
+            const USER_IP_EN = (1 << 12);
+            const RX_SLAVE = (1 << 11);
+            const IP1588_EN = (0b111 << 8);
+            const RX_L2_EN = (1 << 7);
+            const RX_IPV6_EN = (1 << 6);
+            const RX_IPV4_EN = (1 << 5);
+            const RX_PTP_VER = (0xF << 1);
+            const RX_TS_EN = (1 << 0);
+        }
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PTPSTS {
+        pub const ADDRESS: (u16, u8) = (0b100, 0x16);
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PTPTXCFG0 {
+        pub const ADDRESS: (u16, u8) = (0b101, 0x16);
+        pub const PTP_VER_SHIFT: u16 = 1;
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PTPRXCFG0 {
+        pub const ADDRESS: (u16, u8) = (0b101, 0x19);
+        pub const PTP_VER_SHIFT: u16 = 1;
+    }
+
+    #[cfg(feature = "dp83640")]
+    bitflags! {
+        /// PTP Trigger Configuration Register
+        pub struct PTPTRIG: u16 {
+            const TRIG_PULSE = (1 << 15);
+            const TRIG_PER = (1 << 14);
+            const TRIG_IF_LATE = (1 << 13);
+            const TRIG_NOTIFY = (1 << 12);
+            const TRIG_GPIO = (0xF << 8);
+            const TRIG_TOGGLE = (1 << 7);
+            const TRIG_CSEL = (0b111 << 1);
+            const TRIG_WR = (1 << 0);
+        }
+
+        /// PTP Event Configuration Register
+        pub struct PTPEVNT: u16 {
+            const EVNT_RISE = (1 << 14);
+            const EVNT_FALL = (1 << 13);
+            const EVNT_SINGLE = (1 << 12);
+            const EVNT_GPIO = (0xF << 8);
+            const EVNT_SEL = (0b111 << 1);
+            const EVNT_WR = (1 << 0);
+        }
+
+        /// PTP Event Status Register
+        pub struct PTPESTS: u16 {
+            const EVNTS_MISSED = (0b111 << 8);
+            const EVNT_TS_LEN = (0b11 << 6);
+            const EVNT_RF = (1 << 5);
+            const EVNT_NUM = (0b111 << 2);
+            const MULT_EVNT = (1 << 1);
+            const EVENT_DET = (1 << 0);
+        }
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PTPTRIG {
+        pub const ADDRESS: (u16, u8) = (0b101, 0x14);
+        pub const GPIO_SHIFT: u16 = 8;
+        pub const CSEL_SHIFT: u16 = 1;
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PTPEVNT {
+        pub const ADDRESS: (u16, u8) = (0b101, 0x15);
+        pub const GPIO_SHIFT: u16 = 8;
+        pub const SEL_SHIFT: u16 = 1;
+    }
+
+    #[cfg(feature = "dp83640")]
+    impl PTPESTS {
+        pub const ADDRESS: (u16, u8) = (0b100, 0x1E);
+        pub const MISSED_SHIFT: u16 = 8;
+        pub const TS_LEN_SHIFT: u16 = 6;
+        pub const NUM_SHIFT: u16 = 2;
+    }
+
+    #[cfg(feature = "dp83640")]
+    paged_registers!(PTPCTL, PTPSTS, PTPTXCFG0, PTPRXCFG0, PTPTRIG, PTPEVNT, PTPESTS);
+
+    // The PTP registers that hold data instead of flags. Registers that hold
+    // wider values are read or written multiple times in a row.
+
+    /// PTP Time Data Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_TDR: (u16, u8) = (0b100, 0x15);
+    /// PTP Trigger Status Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_TSTS: (u16, u8) = (0b100, 0x17);
+    /// PTP Rate Low Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_RATEL: (u16, u8) = (0b100, 0x18);
+    /// PTP Rate High Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_RATEH: (u16, u8) = (0b100, 0x19);
+    /// PTP Read Checksum
+    #[cfg(feature = "dp83640")]
+    pub const PTP_RDCKSUM: (u16, u8) = (0b100, 0x1A);
+    /// PTP Write Checksum
+    #[cfg(feature = "dp83640")]
+    pub const PTP_WRCKSUM: (u16, u8) = (0b100, 0x1B);
+    /// PTP Transmit Timestamp Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_TXTS: (u16, u8) = (0b100, 0x1C);
+    /// PTP Receive Timestamp Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_RXTS: (u16, u8) = (0b100, 0x1D);
+    /// PTP Event Data Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_EDATA: (u16, u8) = (0b100, 0x1F);
+
+    /// PTP Transmit Configuration Register 1
+    #[cfg(feature = "dp83640")]
+    pub const PTP_TXCFG1: (u16, u8) = (0b101, 0x17);
+    /// PHY Status Frame Configuration Register 0
+    #[cfg(feature = "dp83640")]
+    pub const PSF_CFG0: (u16, u8) = (0b101, 0x18);
+    /// PTP Receive Configuration Registers 1 to 4
+    #[cfg(feature = "dp83640")]
+    pub const PTP_RXCFG: [(u16, u8); 4] =
+        [(0b101, 0x1A), (0b101, 0x1B), (0b101, 0x1C), (0b101, 0x1D)];
+    /// PTP Temporary Rate Duration Low Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_TRDL: (u16, u8) = (0b101, 0x1E);
+    /// PTP Temporary Rate Duration High Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_TRDH: (u16, u8) = (0b101, 0x1F);
+
+    /// PTP Oscillator Clock Output Configuration Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_COC: (u16, u8) = (0b110, 0x14);
+    /// PHY Status Frame Configuration Registers 1 to 4
+    #[cfg(feature = "dp83640")]
+    pub const PSF_CFG: [(u16, u8); 4] =
+        [(0b110, 0x15), (0b110, 0x16), (0b110, 0x17), (0b110, 0x18)];
+    /// PTP SFD Configuration Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_SFDCFG: (u16, u8) = (0b110, 0x19);
+    /// PTP Interrupt Control Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_INTCTL: (u16, u8) = (0b110, 0x1A);
+    /// PTP Clock Source Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_CLKSRC: (u16, u8) = (0b110, 0x1B);
+    /// PTP Ethernet Type Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_ETR: (u16, u8) = (0b110, 0x1C);
+    /// PTP Offset Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_OFF: (u16, u8) = (0b110, 0x1D);
+    /// PTP GPIO Monitor Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_GPIOMON: (u16, u8) = (0b110, 0x1E);
+    /// PTP Receive Hash Register
+    #[cfg(feature = "dp83640")]
+    pub const PTP_RXHASH: (u16, u8) = (0b110, 0x1F);
+}
+
+#[cfg(feature = "dp83640")]
+impl<MIIM: Miim> DP83640<MIIM> {
+    /// PTP_RATEH: the clock runs slower instead of faster
+    const PTP_RATE_DIR: u16 = 1 << 15;
+    /// PTP_RATEH: the rate only applies for the duration in PTP_TRDL/PTP_TRDH
+    const PTP_TMP_RATE: u16 = 1 << 14;
+    /// The largest value of the 26 bit rate
+    const PTP_RATE_MAX: u32 = 0x03FF_FFFF;
+    /// The period of the 125 MHz reference clock, in nanoseconds
+    const PTP_CLOCK_PERIOD_NS: u64 = 8;
+    /// The frequency adjustment, in ppb, of [`Self::PTP_RATE_MAX`]
+    const PTP_MAX_PPB: u32 = ((Self::PTP_RATE_MAX as u64 * crate::NANOS_PER_SECOND as u64)
+        / (Self::PTP_CLOCK_PERIOD_NS << 32)) as u32;
+
+    /// Convert `ppb` into the rate registers. The rate is the amount of
+    /// 2^-32 ns that are added to every 8 ns clock period.
+    fn rate_from_ppb(ppb: i32) -> (u16, u16) {
+        let abs_ppb = ppb.unsigned_abs().min(Self::PTP_MAX_PPB) as u64;
+        let rate = ((abs_ppb * Self::PTP_CLOCK_PERIOD_NS) << 32) / crate::NANOS_PER_SECOND as u64;
+        let rate = rate.min(Self::PTP_RATE_MAX as u64) as u32;
+
+        let mut high = (rate >> 16) as u16;
+        if ppb < 0 {
+            high |= Self::PTP_RATE_DIR;
+        }
+        (high, rate as u16)
+    }
+
+    /// Write `time` to PTP_TDR, in the order in which the PHY expects it
+    /// for a load or step command.
+    fn write_time_data(
+        regs: &mut PagedAccess<'_, MIIM, true>,
+        seconds: u32,
+        nanoseconds: u32,
+    ) -> Result<(), MIIM::Error> {
+        regs.write_raw(PTP_TDR, nanoseconds as u16)?;
+        regs.write_raw(PTP_TDR, (nanoseconds >> 16) as u16 & 0x3FFF)?;
+        regs.write_raw(PTP_TDR, seconds as u16)?;
+        regs.write_raw(PTP_TDR, (seconds >> 16) as u16)
+    }
+}
+
+#[cfg(feature = "dp83640")]
+impl<MIIM: Miim> PTP for DP83640<MIIM> {
+    type Error = MIIM::Error;
+
+    fn started(&mut self) -> Result<bool, Self::Error> {
+        let ptpctl: PTPCTL = self.read_paged()?;
+        Ok(ptpctl.contains(PTPCTL::PTP_ENABLE))
+    }
+
+    fn reset_clock(&mut self) -> Result<(), Self::Error> {
+        self.modify_paged(|ptpctl: &mut PTPCTL| ptpctl.insert(PTPCTL::PTP_RESET))
+    }
+
+    fn start_ptp(&mut self) -> Result<(), Self::Error> {
+        self.modify_paged(|ptpctl: &mut PTPCTL| ptpctl.insert(PTPCTL::PTP_ENABLE))
+    }
+
+    fn stop_ptp(&mut self) -> Result<(), Self::Error> {
+        self.modify_paged(|ptpctl: &mut PTPCTL| ptpctl.insert(PTPCTL::PTP_DISABLE))
+    }
+
+    fn set_clock(&mut self, time: PtpTime) -> Result<(), Self::Error> {
+        self.paged(|regs| {
+            Self::write_time_data(regs, time.seconds, time.nanoseconds)?;
+            regs.write(PTPCTL::PTP_LOAD_CLK)
+        })
+    }
+
+    fn read_clock(&mut self) -> Result<PtpTime, Self::Error> {
+        self.paged(|regs| {
+            // Latch the clock, and read it back from PTP_TDR
+            regs.write(PTPCTL::PTP_RD_CLK)?;
+
+            let ns_low = regs.read_raw(PTP_TDR)? as u32;
+            let ns_high = regs.read_raw(PTP_TDR)? as u32 & 0x3FFF;
+            let sec_low = regs.read_raw(PTP_TDR)? as u32;
+            let sec_high = regs.read_raw(PTP_TDR)? as u32;
+
+            Ok(PtpTime {
+                seconds: (sec_high << 16) | sec_low,
+                nanoseconds: (ns_high << 16) | ns_low,
+            })
+        })
+    }
+
+    fn step_clock(&mut self, offset: i64) -> Result<(), Self::Error> {
+        // A negative offset is written as negative (two's complement)
+        // seconds, and positive nanoseconds.
+        let nanos_per_second = crate::NANOS_PER_SECOND as i64;
+        let seconds = offset.div_euclid(nanos_per_second) as i32 as u32;
+        let nanoseconds = offset.rem_euclid(nanos_per_second) as u32;
+
+        self.paged(|regs| {
+            Self::write_time_data(regs, seconds, nanoseconds)?;
+            regs.write(PTPCTL::PTP_STEP_CLK)
+        })
+    }
+
+    fn set_rate_control(&mut self, rate: u32) -> Result<(), Self::Error> {
+        let high_bits = (rate >> 16) as u16;
+        let low_bits = rate as u16;
+
+        self.paged(|regs| {
+            regs.write_raw(PTP_RATEH, high_bits)?;
+            regs.write_raw(PTP_RATEL, low_bits)
+        })
+    }
+
+    fn max_frequency_adjustment(&self) -> u32 {
+        Self::PTP_MAX_PPB
+    }
+
+    fn adjust_frequency(&mut self, ppb: i32) -> Result<(), Self::Error> {
+        let (high, low) = Self::rate_from_ppb(ppb);
+
+        // The rate takes effect when PTP_RATEL is written
+        self.paged(|regs| {
+            regs.write_raw(PTP_RATEH, high)?;
+            regs.write_raw(PTP_RATEL, low)
+        })
+    }
+
+    fn adjust_frequency_temporarily(
+        &mut self,
+        ppb: i32,
+        duration_ns: u32,
+    ) -> Result<(), Self::Error> {
+        let (high, low) = Self::rate_from_ppb(ppb);
+
+        // The duration is a 30 bit amount of clock periods
+        let duration = (duration_ns as u64 / Self::PTP_CLOCK_PERIOD_NS) as u32;
+
+        self.paged(|regs| {
+            regs.write_raw(PTP_TRDL, duration as u16)?;
+            regs.write_raw(PTP_TRDH, (duration >> 16) as u16 & 0x3FFF)?;
+
+            regs.write_raw(PTP_RATEH, high | Self::PTP_TMP_RATE)?;
+            regs.write_raw(PTP_RATEL, low)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{LinkPartner, SimChip, SimMiim, SimulatedPhy};
+    use crate::trace::{Direction, RecordingMiim, TraceBuffer, Transaction};
+    use crate::{Duplex, LinkSpeed};
+
+    const ADDR: u8 = 1;
+
+    fn all_modes() -> AutoNegotiationAdvertisement {
+        AutoNegotiationAdvertisement {
+            hd_10base_t: true,
+            fd_10base_t: true,
+            hd_100base_tx: true,
+            fd_100base_tx: true,
+            ..Default::default()
+        }
+    }
+
+    fn partner(advertisement: AutoNegotiationAdvertisement) -> LinkPartner {
+        LinkPartner {
+            advertisement,
+            autonegotiation: true,
+        }
+    }
+
+    fn disconnected(chip: SimChip) -> SimMiim {
+        let mut miim = SimMiim::new();
+        miim.add(ADDR, SimulatedPhy::for_chip(chip));
+        miim
+    }
+
+    fn connected(chip: SimChip, advertisement: AutoNegotiationAdvertisement) -> SimMiim {
+        let mut miim = disconnected(chip);
+        miim.phy_mut(ADDR).unwrap().connect(partner(advertisement));
+        miim
+    }
+
+    fn check_link_speed<const PTP_EN: bool>(chip: SimChip) {
+        let modes = [
+            (
+                AutoNegotiationAdvertisement {
+                    hd_10base_t: true,
+                    ..Default::default()
+                },
+                PhySpeed::HalfDuplexBase10T,
+            ),
+            (
+                AutoNegotiationAdvertisement {
+                    fd_10base_t: true,
+                    ..Default::default()
+                },
+                PhySpeed::FullDuplexBase10T,
+            ),
+            (
+                AutoNegotiationAdvertisement {
+                    hd_100base_tx: true,
+                    ..Default::default()
+                },
+                PhySpeed::HalfDuplexBase100Tx,
+            ),
+            (
+                AutoNegotiationAdvertisement {
+                    fd_100base_tx: true,
+                    ..Default::default()
+                },
+                PhySpeed::FullDuplexBase100Tx,
+            ),
+        ];
+
+        for (advertisement, speed) in modes {
+            let mut phy = DP83XXX::<_, PTP_EN>::new(connected(chip, advertisement), ADDR);
+            assert_eq!(phy.link_speed().unwrap(), Some(speed));
+            assert_eq!(phy.get_link_speed().unwrap(), Some(speed.into()));
+        }
+
+        let mut phy = DP83XXX::<_, PTP_EN>::new(disconnected(chip), ADDR);
+        assert_eq!(phy.link_speed().unwrap(), None);
+    }
+
+    fn check_autonegotiation<const PTP_EN: bool>(chip: SimChip) {
+        let mut phy = DP83XXX::<_, PTP_EN>::new(connected(chip, all_modes()), ADDR);
+        assert!(phy.autoneg_completed().unwrap());
+        assert_eq!(
+            phy.link_speed().unwrap(),
+            Some(PhySpeed::FullDuplexBase100Tx)
+        );
+
+        phy.set_autonegotiation_advertisement(AutoNegotiationAdvertisement {
+            hd_100base_tx: true,
+            fd_10base_t: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let result = phy.resolve_autonegotiation().unwrap().unwrap();
+        assert_eq!(
+            (result.speed, result.duplex),
+            (LinkSpeed::Mbps100, Duplex::Half)
+        );
+        assert_eq!(
+            phy.link_speed().unwrap(),
+            Some(PhySpeed::HalfDuplexBase100Tx)
+        );
+    }
+
+    fn check_interrupts<const PTP_EN: bool>(chip: SimChip) {
+        let mut phy = DP83XXX::<_, PTP_EN>::new(disconnected(chip), ADDR);
+        phy.enable_interrupts(phy.supported_interrupts()).unwrap();
+        phy.write(MICR::ADDRESS, (MICR::INTEN | MICR::INT_OE).bits())
+            .unwrap();
+        let enables = phy.read(MISR::ADDRESS).unwrap() & MISR::ENABLE_MASK.bits();
+
+        phy.get_miim()
+            .phy_mut(ADDR)
+            .unwrap()
+            .connect(partner(all_modes()));
+        assert!(phy.get_miim().phy(ADDR).unwrap().interrupt_asserted());
+        assert_eq!(
+            phy.read_and_clear_interrupts().unwrap(),
+            InterruptEvents::LINK_UP
+                | InterruptEvents::AUTONEG_COMPLETE
+                | InterruptEvents::ENERGY_DETECT
+        );
+
+        // Reading the MISR clears the status bits, but not the enables
+        assert!(!phy.get_miim().phy(ADDR).unwrap().interrupt_asserted());
+        assert_eq!(phy.get_interrupt_reg_val().unwrap(), enables);
+        assert_eq!(
+            phy.read_and_clear_interrupts().unwrap(),
+            InterruptEvents::empty()
+        );
+
+        phy.get_miim().phy_mut(ADDR).unwrap().disconnect();
+        assert_eq!(
+            phy.read_and_clear_interrupts().unwrap(),
+            InterruptEvents::LINK_DOWN
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "dp83848")]
+    fn dp83848_link_speed_from_physts() {
+        check_link_speed::<false>(SimChip::Dp83848);
+    }
+
+    #[test]
+    #[cfg(feature = "dp83848")]
+    fn dp83848_autonegotiation() {
+        check_autonegotiation::<false>(SimChip::Dp83848);
+    }
+
+    #[test]
+    #[cfg(feature = "dp83848")]
+    fn dp83848_misr_is_cleared_on_read() {
+        check_interrupts::<false>(SimChip::Dp83848);
+    }
+
+    #[test]
+    #[cfg(feature = "dp83640")]
+    fn dp83640_link_speed_from_physts() {
+        check_link_speed::<true>(SimChip::Dp83640);
+    }
+
+    #[test]
+    #[cfg(feature = "dp83640")]
+    fn dp83640_autonegotiation() {
+        check_autonegotiation::<true>(SimChip::Dp83640);
+    }
+
+    #[test]
+    #[cfg(feature = "dp83640")]
+    fn dp83640_misr_is_cleared_on_read() {
+        check_interrupts::<true>(SimChip::Dp83640);
+    }
+
+    #[test]
+    #[cfg(feature = "dp83640")]
+    fn dp83640_paged_registers() {
+        let mut phy = DP83640::new(disconnected(SimChip::Dp83640), ADDR);
+        let page0_rate = phy.read(PTP_RATEL.1).unwrap();
+
+        phy.write_ext(PTP_RATEL, 0x1234).unwrap();
+        phy.write_ext(PTP_TRDL, 0x5678).unwrap();
+        phy.write_paged(PTPCTL::PTP_ENABLE).unwrap();
+
+        let sim = phy.get_miim().phy(ADDR).unwrap();
+        assert_eq!(sim.page_register(PTP_RATEL.0, PTP_RATEL.1), 0x1234);
+        assert_eq!(sim.page_register(PTP_TRDL.0, PTP_TRDL.1), 0x5678);
+        assert_eq!(
+            sim.page_register(PTPCTL::ADDRESS.0, PTPCTL::ADDRESS.1),
+            PTPCTL::PTP_ENABLE.bits()
+        );
+
+        // Page 0 is selected afterwards, and its registers are untouched
+        assert_eq!(sim.register(registers::PAGESEL), 0);
+        assert_eq!(phy.read(PTP_RATEL.1).unwrap(), page0_rate);
+
+        assert_eq!(phy.read_ext(PTP_RATEL).unwrap(), 0x1234);
+        let (rate, duration) = phy
+            .paged(|regs| Ok((regs.read_raw(PTP_RATEL)?, regs.read_raw(PTP_TRDL)?)))
+            .unwrap();
+        assert_eq!((rate, duration), (0x1234, 0x5678));
+        assert_eq!(phy.read_paged::<PTPCTL>().unwrap(), PTPCTL::PTP_ENABLE);
+    }
+
+    /// The words written to PTP_TDR by `step_clock(offset)`
+    #[cfg(feature = "dp83640")]
+    fn step_data(offset: i64) -> [u16; 4] {
+        let mut trace = [Transaction::read(0, 0, 0); 16];
+        let miim = RecordingMiim::new(disconnected(SimChip::Dp83640), TraceBuffer::new(&mut trace));
+        let mut phy = DP83640::new(miim, ADDR);
+        phy.step_clock(offset).unwrap();
+
+        let log = phy.get_miim().log();
+        let writes = || log.iter().filter(|t| t.direction == Direction::Write);
+        let mut data = writes().filter(|t| t.reg == PTP_TDR.1 as u16);
+        let data = core::array::from_fn(|_| data.next().unwrap().value);
+
+        // The step command follows the data, before page 0 is selected again
+        let mut tail = writes().skip_while(|t| t.reg != PTP_TDR.1 as u16).skip(4);
+        assert_eq!(tail.next().unwrap().value, PTPCTL::PTP_STEP_CLK.bits());
+        assert_eq!(tail.next().unwrap().reg, registers::PAGESEL as u16);
+        data
+    }
+
+    #[test]
+    #[cfg(feature = "dp83640")]
+    fn dp83640_step_clock_encoding() {
+        // Nanoseconds first, then seconds, low word first
+        assert_eq!(step_data(1_000_000_001), [1, 0, 1, 0]);
+        assert_eq!(step_data(1_500_000_000), [0x6500, 0x1DCD, 1, 0]);
+
+        // Negative offsets are negative seconds and positive nanoseconds
+        assert_eq!(step_data(-1), [0xC9FF, 0x3B9A, 0xFFFF, 0xFFFF]);
+        assert_eq!(step_data(-1_000_000_000), [0, 0, 0xFFFF, 0xFFFF]);
+        assert_eq!(step_data(-1_500_000_000), [0x6500, 0x1DCD, 0xFFFE, 0xFFFF]);
+    }
+
+    #[test]
+    #[cfg(feature = "dp83640")]
+    fn dp83640_rate_from_ppb() {
+        type Phy = DP83640<SimMiim>;
+        let dir = Phy::PTP_RATE_DIR;
+
+        assert_eq!(Phy::rate_from_ppb(0), (0, 0));
+        // 1000 ppb of 8 ns is 8000 * 2^-32 ns per period
+        assert_eq!(Phy::rate_from_ppb(1_000), (0, 34_359));
+        assert_eq!(Phy::rate_from_ppb(-1_000), (dir, 34_359));
+        assert_eq!(Phy::rate_from_ppb(1_000_000), (0x020C, 0x49BA));
+        assert_eq!(Phy::rate_from_ppb(-1_000_000), (dir | 0x020C, 0x49BA));
+
+        // Larger adjustments are clamped to PTP_MAX_PPB
+        let max = Phy::PTP_MAX_PPB as i32;
+        let (high, low) = Phy::rate_from_ppb(max);
+        assert!((((high as u32) << 16) | low as u32) <= Phy::PTP_RATE_MAX);
+        assert_eq!(Phy::rate_from_ppb(max + 1), (high, low));
+        assert_eq!(Phy::rate_from_ppb(i32::MAX), (high, low));
+        assert_eq!(Phy::rate_from_ppb(-max - 1), (dir | high, low));
+        assert_eq!(Phy::rate_from_ppb(i32::MIN), (dir | high, low));
+    }
+
+    #[test]
+    #[cfg(feature = "dp83640")]
+    fn dp83640_trigger_status() {
+        let mut phy = DP83640::new(disconnected(SimChip::Dp83640), ADDR);
+
+        // Trigger 1 is active, trigger 2 failed, and trigger 7 is both
+        let tsts = (0b01 << 2) | (0b10 << 4) | (0b11 << 14);
+        let (page, reg) = registers::PTP_TSTS;
+        phy.get_miim()
+            .phy_mut(ADDR)
+            .unwrap()
+            .set_page_register(page, reg, tsts);
+
+        let status = |active, error| TriggerStatus { active, error };
+        assert_eq!(phy.trigger_status(0).unwrap(), status(false, false));
+        assert_eq!(phy.trigger_status(1).unwrap(), status(true, false));
+        assert_eq!(phy.trigger_status(2).unwrap(), status(false, true));
+        assert_eq!(phy.trigger_status(7).unwrap(), status(true, true));
+    }
+
+    /// The pages written to PAGESEL, in order
+    #[cfg(feature = "dp83640")]
+    fn page_selects<'a>(log: &'a TraceBuffer<'_>) -> impl Iterator<Item = u16> + 'a {
+        log.iter()
+            .filter(|t| t.direction == Direction::Write && t.reg == PAGESEL as u16)
+            .map(|t| t.value)
+    }
+
+    #[test]
+    #[cfg(feature = "dp83640")]
+    fn dp83640_page_selection() {
+        let mut sim = disconnected(SimChip::Dp83640);
+        let (page, reg) = registers::PTPSTS::ADDRESS;
+        sim.phy_mut(ADDR)
+            .unwrap()
+            .set_page_register(page, reg, registers::PTPSTS::RXTS_RDY.bits());
+
+        let mut trace = [Transaction::read(0, 0, 0); 32];
+        let miim = RecordingMiim::new(sim, TraceBuffer::new(&mut trace));
+        let mut phy = DP83640::new(miim, ADDR);
+
+        // PTPSTS and PTP_RXTS are on the same page, which is selected once
+        phy.read_rx_timestamp().unwrap().unwrap();
+        {
+            let log = phy.get_miim().log();
+            let mut accesses = log.iter().map(|t| (t.direction, t.reg));
+            assert_eq!(accesses.next(), Some((Direction::Write, PAGESEL as u16)));
+            assert_eq!(accesses.next(), Some((Direction::Read, reg as u16)));
+            for _ in 0..6 {
+                assert_eq!(
+                    accesses.next(),
+                    Some((Direction::Read, registers::PTP_RXTS.1 as u16))
+                );
+            }
+            assert_eq!(accesses.next(), Some((Direction::Write, PAGESEL as u16)));
+            assert_eq!(accesses.next(), None);
+            assert!(page_selects(log).eq([4, 0]));
+        }
+
+        // Back-to-back calls select page 0 in between, but not again if it
+        // is already selected
+        phy.get_miim().log_mut().clear();
+        phy.paged(|regs| regs.read_raw(PTP_RATEL)).unwrap();
+        phy.paged(|regs| regs.read_raw((0, 0x14))).unwrap();
+        phy.paged(|regs| regs.read_raw(PTP_TRDL)).unwrap();
+        assert!(page_selects(phy.get_miim().log()).eq([4, 0, 5, 0]));
+
+        phy.get_miim().log_mut().clear();
+        phy.paged(|regs| {
+            regs.read_raw(PTP_RATEL)?;
+            regs.read_raw(PTP_RATEH)?;
+            regs.read_raw(PTP_TRDL)?;
+            regs.read_raw(PTP_RATEL)
+        })
+        .unwrap();
+        assert!(page_selects(phy.get_miim().log()).eq([4, 5, 4, 0]));
+
+        // Writing PAGESEL or resetting the PHY invalidates the selected page
+        phy.get_miim().log_mut().clear();
+        phy.write(PAGESEL, 4).unwrap();
+        phy.read_ext((0, 0x14)).unwrap();
+        phy.write(Bcr::ADDRESS, Bcr::RESET.bits()).unwrap();
+        phy.read_ext((0, 0x14)).unwrap();
+        assert!(page_selects(phy.get_miim().log()).eq([4, 0, 0]));
+    }
+
+    #[test]
+    #[cfg(feature = "dp83848")]
+    fn dp83848_has_no_pages() {
+        let mut trace = [Transaction::read(0, 0, 0); 8];
+        let miim = RecordingMiim::new(disconnected(SimChip::Dp83848), TraceBuffer::new(&mut trace));
+        let mut phy = DP83848::new(miim, ADDR);
+
+        phy.read_ext((4, 0x14)).unwrap();
+        phy.write_ext((4, 0x14), 0).unwrap();
+
+        // PAGESEL is never written
+        let log = phy.get_miim().log();
+        let accesses = log.iter().map(|t| (t.direction, t.reg));
+        assert!(accesses.eq([(Direction::Read, 0x14), (Direction::Write, 0x14)]));
+    }
+}
@@ -0,0 +1,108 @@
+//! Access to the paged registers of the DP83xxx.
+
+use crate::{Miim, Phy};
+
+use super::registers::{PagedRegister, PAGESEL};
+use super::DP83XXX;
+
+/// The first register that depends on the selected page
+const FIRST_PAGED_REG: u8 = 0x14;
+
+/// Access to the registers of a DP83xxx, that only writes the page select
+/// register if a register on a different page is accessed.
+///
+/// Created by [`DP83XXX::paged`].
+#[derive(Debug)]
+pub struct PagedAccess<'a, MIIM, const PTP: bool> {
+    phy: &'a mut DP83XXX<MIIM, PTP>,
+}
+
+impl<'a, MIIM: Miim, const PTP_EN: bool> PagedAccess<'a, MIIM, PTP_EN> {
+    /// Read the register at `address`, a page and a register
+    pub fn read_raw(&mut self, address: (u16, u8)) -> Result<u16, MIIM::Error> {
+        self.phy.select_page(address)?;
+        self.phy.read(address.1)
+    }
+
+    /// Write `value` to the register at `address`, a page and a register
+    pub fn write_raw(&mut self, address: (u16, u8), value: u16) -> Result<(), MIIM::Error> {
+        self.phy.select_page(address)?;
+        self.phy.write(address.1, value)
+    }
+
+    /// Read the register `R`
+    pub fn read<R: PagedRegister>(&mut self) -> Result<R, MIIM::Error> {
+        self.read_raw(R::ADDRESS).map(R::from_raw)
+    }
+
+    /// Write `value` to the register `R`
+    pub fn write<R: PagedRegister>(&mut self, value: R) -> Result<(), MIIM::Error> {
+        self.write_raw(R::ADDRESS, value.raw())
+    }
+
+    /// Read the register `R`, modify it using `f`, and write it back
+    pub fn modify<R: PagedRegister>(&mut self, f: impl FnOnce(&mut R)) -> Result<(), MIIM::Error> {
+        let mut value = self.read()?;
+        f(&mut value);
+        self.write(value)
+    }
+}
+
+impl<MIIM: Miim, const PTP_EN: bool> DP83XXX<MIIM, PTP_EN> {
+    /// Select the page of `address`, if it is a paged register on a page
+    /// other than the selected one.
+    ///
+    /// The DP83848 does not have pages, so this does nothing for it.
+    fn select_page(&mut self, address: (u16, u8)) -> Result<(), MIIM::Error> {
+        if !PTP_EN || address.1 < FIRST_PAGED_REG || self.page == Some(address.0) {
+            return Ok(());
+        }
+
+        // The selected page is unknown if the write fails
+        self.page = None;
+        self.write(PAGESEL, address.0)?;
+        self.page = Some(address.0);
+        Ok(())
+    }
+
+    /// Access the paged registers using `f`, and select page 0 afterwards.
+    ///
+    /// The page select register is only written when a register on a
+    /// different page is accessed. Page 0 is selected afterwards even if `f`
+    /// fails, so that the other methods of the PHY access the registers on
+    /// page 0.
+    ///
+    /// The selected page is tracked by the PHY, and forgotten when the page
+    /// select register is written or the PHY is reset using [`Phy::write`].
+    /// Writes through [`Phy::get_miim`] bypass the tracking, and must be
+    /// followed by a write of page 0 to the page select register.
+    pub fn paged<T>(
+        &mut self,
+        f: impl FnOnce(&mut PagedAccess<'_, MIIM, PTP_EN>) -> Result<T, MIIM::Error>,
+    ) -> Result<T, MIIM::Error> {
+        let result = f(&mut PagedAccess { phy: self });
+        let restored = self.select_page((0, FIRST_PAGED_REG));
+
+        let value = result?;
+        restored?;
+        Ok(value)
+    }
+
+    /// Read the register `R`
+    pub fn read_paged<R: PagedRegister>(&mut self) -> Result<R, MIIM::Error> {
+        self.paged(|regs| regs.read())
+    }
+
+    /// Write `value` to the register `R`
+    pub fn write_paged<R: PagedRegister>(&mut self, value: R) -> Result<(), MIIM::Error> {
+        self.paged(|regs| regs.write(value))
+    }
+
+    /// Read the register `R`, modify it using `f`, and write it back
+    pub fn modify_paged<R: PagedRegister>(
+        &mut self,
+        f: impl FnOnce(&mut R),
+    ) -> Result<(), MIIM::Error> {
+        self.paged(|regs| regs.modify(f))
+    }
+}
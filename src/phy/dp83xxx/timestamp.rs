@@ -0,0 +1,354 @@
+//! Timestamping of PTP event messages by the DP83640.
+
+use crate::{Miim, PtpTime};
+
+use super::registers::{PTPRXCFG0, PTPSTS, PTPTXCFG0, PTP_RXTS, PTP_TXTS};
+use super::{PagedAccess, DP83640};
+
+/// The transports on which PTP event messages are detected
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PtpTransports {
+    /// Layer 2 Ethernet frames, with EtherType 0x88F7
+    pub layer2: bool,
+    /// UDP over IPv4
+    pub ipv4: bool,
+    /// UDP over IPv6
+    pub ipv6: bool,
+}
+
+impl PtpTransports {
+    /// All transports
+    pub const ALL: Self = Self {
+        layer2: true,
+        ipv4: true,
+        ipv6: true,
+    };
+
+    fn is_udp(&self) -> bool {
+        self.ipv4 || self.ipv6
+    }
+}
+
+/// The configuration of transmit timestamping, written to PTP_TXCFG0.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxTimestampConfig {
+    /// Timestamp transmitted event messages
+    pub enabled: bool,
+    /// The PTP version of the messages to timestamp. Only the low 4 bits are used.
+    pub ptp_version: u8,
+    /// The transports on which event messages are timestamped. For UDP, only
+    /// messages sent to the IEEE 1588 multicast addresses are timestamped.
+    pub transports: PtpTransports,
+    /// Insert the transmit timestamp into Sync messages, instead of making it
+    /// available in PTP_TXTS. Over UDP, the checksum is corrected as well.
+    pub one_step_sync: bool,
+}
+
+impl Default for TxTimestampConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ptp_version: 2,
+            transports: PtpTransports::ALL,
+            one_step_sync: false,
+        }
+    }
+}
+
+impl From<TxTimestampConfig> for PTPTXCFG0 {
+    fn from(config: TxTimestampConfig) -> Self {
+        let transports = config.transports;
+        let version = (config.ptp_version as u16) << PTPTXCFG0::PTP_VER_SHIFT;
+
+        let mut cfg = PTPTXCFG0::from_bits_truncate(version) & PTPTXCFG0::TX_PTP_VER;
+        cfg.set(PTPTXCFG0::TX_TS_EN, config.enabled);
+        cfg.set(PTPTXCFG0::TX_L2_EN, transports.layer2);
+        cfg.set(PTPTXCFG0::TX_IPV4_EN, transports.ipv4);
+        cfg.set(PTPTXCFG0::TX_IPV6_EN, transports.ipv6);
+        // Only timestamp UDP messages sent to the IEEE 1588 addresses
+        cfg.set(PTPTXCFG0::IP1588_EN, transports.is_udp());
+        cfg.set(PTPTXCFG0::SYNC_1STEP, config.one_step_sync);
+        // Inserting the timestamp invalidates the UDP checksum, so it has to
+        // be corrected as well
+        cfg.set(
+            PTPTXCFG0::CHK_1STEP,
+            config.one_step_sync && transports.is_udp(),
+        );
+        cfg
+    }
+}
+
+/// The configuration of receive timestamping, written to PTP_RXCFG0.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RxTimestampConfig {
+    /// Timestamp received event messages
+    pub enabled: bool,
+    /// The PTP version of the messages to timestamp. Only the low 4 bits are used.
+    pub ptp_version: u8,
+    /// The transports on which event messages are timestamped. For UDP, only
+    /// messages sent to the IEEE 1588 multicast addresses are timestamped.
+    pub transports: PtpTransports,
+}
+
+impl Default for RxTimestampConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ptp_version: 2,
+            transports: PtpTransports::ALL,
+        }
+    }
+}
+
+impl From<RxTimestampConfig> for PTPRXCFG0 {
+    fn from(config: RxTimestampConfig) -> Self {
+        let transports = config.transports;
+        let version = (config.ptp_version as u16) << PTPRXCFG0::PTP_VER_SHIFT;
+
+        let mut cfg = PTPRXCFG0::from_bits_truncate(version) & PTPRXCFG0::RX_PTP_VER;
+        cfg.set(PTPRXCFG0::RX_TS_EN, config.enabled);
+        cfg.set(PTPRXCFG0::RX_L2_EN, transports.layer2);
+        cfg.set(PTPRXCFG0::RX_IPV4_EN, transports.ipv4);
+        cfg.set(PTPRXCFG0::RX_IPV6_EN, transports.ipv6);
+        // Only timestamp UDP messages sent to the IEEE 1588 addresses
+        cfg.set(PTPRXCFG0::IP1588_EN, transports.is_udp());
+        cfg
+    }
+}
+
+/// The timestamp of a transmitted event message, read from PTP_TXTS
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxTimestamp {
+    /// The time at which the message was transmitted
+    pub time: PtpTime,
+    /// The amount of timestamps that were dropped because this one was not
+    /// read in time, saturating at 3.
+    pub overflow: u8,
+}
+
+/// The timestamp of a received event message, read from PTP_RXTS
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RxTimestamp {
+    /// The time at which the message was received
+    pub time: PtpTime,
+    /// The amount of timestamps that were dropped because this one was not
+    /// read in time, saturating at 3.
+    pub overflow: u8,
+    /// The sequenceId of the message
+    pub sequence_id: u16,
+    /// The messageType of the message
+    pub message_type: u8,
+    /// A 12 bit CRC of the sourcePortIdentity of the message, which can be
+    /// used to match the timestamp to a received message.
+    pub source_hash: u16,
+}
+
+impl<MIIM: Miim> DP83640<MIIM> {
+    /// Configure the timestamping of transmitted event messages.
+    ///
+    /// This overwrites PTP_TXCFG0 completely.
+    pub fn configure_tx_timestamps(
+        &mut self,
+        config: TxTimestampConfig,
+    ) -> Result<(), MIIM::Error> {
+        self.write_paged(PTPTXCFG0::from(config))
+    }
+
+    /// Configure the timestamping of received event messages.
+    ///
+    /// This overwrites PTP_RXCFG0 completely.
+    pub fn configure_rx_timestamps(
+        &mut self,
+        config: RxTimestampConfig,
+    ) -> Result<(), MIIM::Error> {
+        self.write_paged(PTPRXCFG0::from(config))
+    }
+
+    /// Read the oldest transmit timestamp, if one is available.
+    pub fn read_tx_timestamp(&mut self) -> Result<Option<TxTimestamp>, MIIM::Error> {
+        self.paged(|regs| {
+            let status: PTPSTS = regs.read()?;
+            if !status.contains(PTPSTS::TXTS_RDY) {
+                return Ok(None);
+            }
+
+            let (time, overflow) = Self::read_timestamp(regs, PTP_TXTS)?;
+            Ok(Some(TxTimestamp { time, overflow }))
+        })
+    }
+
+    /// Read the oldest receive timestamp, if one is available.
+    pub fn read_rx_timestamp(&mut self) -> Result<Option<RxTimestamp>, MIIM::Error> {
+        self.paged(|regs| {
+            let status: PTPSTS = regs.read()?;
+            if !status.contains(PTPSTS::RXTS_RDY) {
+                return Ok(None);
+            }
+
+            let (time, overflow) = Self::read_timestamp(regs, PTP_RXTS)?;
+            let sequence_id = regs.read_raw(PTP_RXTS)?;
+            // The message type is in the top 4 bits, the hash in the low 12
+            let type_hash = regs.read_raw(PTP_RXTS)?;
+
+            Ok(Some(RxTimestamp {
+                time,
+                overflow,
+                sequence_id,
+                message_type: (type_hash >> 12) as u8,
+                source_hash: type_hash & 0x0FFF,
+            }))
+        })
+    }
+
+    /// Read a timestamp and its overflow count from the first four words of
+    /// `register`.
+    fn read_timestamp(
+        regs: &mut PagedAccess<'_, MIIM, true>,
+        register: (u16, u8),
+    ) -> Result<(PtpTime, u8), MIIM::Error> {
+        let ns_low = regs.read_raw(register)? as u32;
+        let ns_high = regs.read_raw(register)? as u32;
+        let sec_low = regs.read_raw(register)? as u32;
+        let sec_high = regs.read_raw(register)? as u32;
+
+        let time = PtpTime {
+            seconds: (sec_high << 16) | sec_low,
+            nanoseconds: ((ns_high & 0x3FFF) << 16) | ns_low,
+        };
+        Ok((time, (ns_high >> 14) as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phy::dp83xxx::registers::PAGESEL;
+    use crate::sim::{SimChip, SimMiim, SimulatedPhy};
+    use crate::trace::{Direction, RecordingMiim, TraceBuffer, Transaction};
+    use crate::Phy;
+
+    const ADDR: u8 = 1;
+
+    const UDP: PtpTransports = PtpTransports {
+        layer2: false,
+        ipv4: true,
+        ipv6: false,
+    };
+
+    const LAYER2: PtpTransports = PtpTransports {
+        layer2: true,
+        ipv4: false,
+        ipv6: false,
+    };
+
+    #[test]
+    fn tx_config() {
+        let version = PTPTXCFG0::from_bits_truncate(2 << PTPTXCFG0::PTP_VER_SHIFT);
+        assert_eq!(
+            PTPTXCFG0::from(TxTimestampConfig::default()),
+            version
+                | PTPTXCFG0::TX_TS_EN
+                | PTPTXCFG0::TX_L2_EN
+                | PTPTXCFG0::TX_IPV4_EN
+                | PTPTXCFG0::TX_IPV6_EN
+                | PTPTXCFG0::IP1588_EN
+        );
+
+        // Only the low 4 bits of the version are used
+        let config = TxTimestampConfig {
+            enabled: false,
+            ptp_version: 0x13,
+            transports: LAYER2,
+            one_step_sync: false,
+        };
+        let version = PTPTXCFG0::from_bits_truncate(3 << PTPTXCFG0::PTP_VER_SHIFT);
+        assert_eq!(PTPTXCFG0::from(config), version | PTPTXCFG0::TX_L2_EN);
+    }
+
+    #[test]
+    fn tx_config_one_step() {
+        // The UDP checksum is only corrected for one-step Sync messages over UDP
+        let config = |transports, one_step_sync| {
+            PTPTXCFG0::from(TxTimestampConfig {
+                transports,
+                one_step_sync,
+                ..Default::default()
+            })
+        };
+        let one_step = PTPTXCFG0::SYNC_1STEP | PTPTXCFG0::CHK_1STEP;
+
+        assert_eq!(config(UDP, true) & one_step, one_step);
+        assert_eq!(config(PtpTransports::ALL, true) & one_step, one_step);
+        assert_eq!(config(LAYER2, true) & one_step, PTPTXCFG0::SYNC_1STEP);
+        assert_eq!(config(UDP, false) & one_step, PTPTXCFG0::empty());
+        assert_eq!(config(LAYER2, false) & one_step, PTPTXCFG0::empty());
+    }
+
+    #[test]
+    fn rx_config() {
+        let version = PTPRXCFG0::from_bits_truncate(2 << PTPRXCFG0::PTP_VER_SHIFT);
+        assert_eq!(
+            PTPRXCFG0::from(RxTimestampConfig::default()),
+            version
+                | PTPRXCFG0::RX_TS_EN
+                | PTPRXCFG0::RX_L2_EN
+                | PTPRXCFG0::RX_IPV4_EN
+                | PTPRXCFG0::RX_IPV6_EN
+                | PTPRXCFG0::IP1588_EN
+        );
+
+        let config = RxTimestampConfig {
+            enabled: true,
+            ptp_version: 1,
+            transports: LAYER2,
+        };
+        let version = PTPRXCFG0::from_bits_truncate(1 << PTPRXCFG0::PTP_VER_SHIFT);
+        assert_eq!(
+            PTPRXCFG0::from(config),
+            version | PTPRXCFG0::RX_TS_EN | PTPRXCFG0::RX_L2_EN
+        );
+    }
+
+    #[test]
+    fn tx_timestamp() {
+        let mut sim: SimMiim = SimMiim::new();
+        sim.add(ADDR, SimulatedPhy::for_chip(SimChip::Dp83640));
+        let mut trace = [Transaction::read(0, 0, 0); 16];
+        let miim = RecordingMiim::new(sim, TraceBuffer::new(&mut trace));
+        let mut phy = DP83640::new(miim, ADDR);
+
+        assert_eq!(phy.read_tx_timestamp().unwrap(), None);
+
+        // Every word of PTP_TXTS reads as 0x5678, and the top 2 bits of the
+        // high word of the nanoseconds are the overflow count
+        let sim = phy.get_miim().inner_mut().phy_mut(ADDR).unwrap();
+        let (page, reg) = PTPSTS::ADDRESS;
+        sim.set_page_register(page, reg, PTPSTS::TXTS_RDY.bits());
+        sim.set_page_register(PTP_TXTS.0, PTP_TXTS.1, 0x5678);
+
+        phy.get_miim().log_mut().clear();
+        assert_eq!(
+            phy.read_tx_timestamp().unwrap(),
+            Some(TxTimestamp {
+                time: PtpTime {
+                    seconds: 0x5678_5678,
+                    nanoseconds: 0x1678_5678,
+                },
+                overflow: 1,
+            })
+        );
+
+        let log = phy.get_miim().log();
+        let accesses = log
+            .iter()
+            .filter(|t| t.reg != PAGESEL as u16)
+            .map(|t| (t.direction, t.reg as u8));
+        let txts = (Direction::Read, PTP_TXTS.1);
+        assert!(accesses.eq([(Direction::Read, reg), txts, txts, txts, txts]));
+    }
+}
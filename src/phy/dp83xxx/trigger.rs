@@ -0,0 +1,209 @@
+//! The trigger units of the DP83640, which generate signals on its GPIOs at
+//! a given time of the PTP clock.
+
+use crate::{Miim, PtpTime};
+
+use super::registers::{PTPCTL, PTPTRIG, PTP_TDR, PTP_TSTS};
+use super::DP83640;
+
+/// The configuration of a trigger unit.
+///
+/// The trigger generates a pulse that starts at `start`, and is high for
+/// `pulse_width_ns`. If it is periodic, the output is then low for
+/// `pulse_width2_ns` (triggers 0 and 1) or `pulse_width_ns` (triggers 2 to
+/// 7), after which the pulse repeats.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TriggerConfig {
+    /// The trigger unit, 0 to 7. Only the low 3 bits are used.
+    pub trigger: u8,
+    /// The GPIO on which the trigger is output, 1 to 12, or `None` if the
+    /// trigger is not output.
+    pub gpio: Option<u8>,
+    /// Repeat the pulse, instead of generating it once
+    pub periodic: bool,
+    /// The time of the rising edge of the first pulse
+    pub start: PtpTime,
+    /// The time that the output is high, in nanoseconds
+    pub pulse_width_ns: u32,
+    /// The time that the output is low between periodic pulses, in
+    /// nanoseconds. This is only used by triggers 0 and 1.
+    pub pulse_width2_ns: u32,
+}
+
+impl TriggerConfig {
+    /// A periodic pulse, every second at the start of the second, that is
+    /// high for half a second.
+    ///
+    /// The first pulse is generated at `start_seconds`.
+    pub fn pps(trigger: u8, gpio: u8, start_seconds: u32) -> Self {
+        let half_second = crate::NANOS_PER_SECOND / 2;
+        Self {
+            trigger,
+            gpio: Some(gpio),
+            periodic: true,
+            start: PtpTime {
+                seconds: start_seconds,
+                nanoseconds: 0,
+            },
+            pulse_width_ns: half_second,
+            pulse_width2_ns: half_second,
+        }
+    }
+}
+
+/// The status of a trigger unit, read from PTP_TSTS
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TriggerStatus {
+    /// The trigger is enabled, and has not completed yet
+    pub active: bool,
+    /// The trigger was enabled after its start time had already passed
+    pub error: bool,
+}
+
+impl<MIIM: Miim> DP83640<MIIM> {
+    fn trigger_select(trigger: u8) -> PTPCTL {
+        PTPCTL::from_bits_truncate((trigger as u16) << PTPCTL::TRIG_SEL_SHIFT) & PTPCTL::TRIG_SEL
+    }
+
+    /// Configure a trigger unit, load its start time and pulse widths, and
+    /// enable it.
+    pub fn configure_trigger(&mut self, config: TriggerConfig) -> Result<(), MIIM::Error> {
+        let gpio = (config.gpio.unwrap_or(0) as u16) << PTPTRIG::GPIO_SHIFT;
+        let csel = (config.trigger as u16) << PTPTRIG::CSEL_SHIFT;
+
+        let mut trig = PTPTRIG::from_bits_truncate(gpio) & PTPTRIG::TRIG_GPIO;
+        trig |= PTPTRIG::from_bits_truncate(csel) & PTPTRIG::TRIG_CSEL;
+        trig |= PTPTRIG::TRIG_PULSE | PTPTRIG::TRIG_WR;
+        trig.set(PTPTRIG::TRIG_PER, config.periodic);
+
+        let select = Self::trigger_select(config.trigger);
+        let start = config.start;
+
+        self.paged(|regs| {
+            regs.write(trig)?;
+
+            // The start time and pulse widths are loaded through PTP_TDR
+            regs.write(select | PTPCTL::TRIG_LOAD)?;
+            Self::write_time_data(regs, start.seconds, start.nanoseconds)?;
+            regs.write_raw(PTP_TDR, config.pulse_width_ns as u16)?;
+            regs.write_raw(PTP_TDR, (config.pulse_width_ns >> 16) as u16)?;
+            if config.trigger & 0b111 < 2 {
+                regs.write_raw(PTP_TDR, config.pulse_width2_ns as u16)?;
+                regs.write_raw(PTP_TDR, (config.pulse_width2_ns >> 16) as u16)?;
+            }
+
+            regs.write(select | PTPCTL::TRIG_EN)
+        })
+    }
+
+    /// Disable a trigger unit, and stop driving its GPIO.
+    pub fn disable_trigger(&mut self, trigger: u8) -> Result<(), MIIM::Error> {
+        let select = Self::trigger_select(trigger);
+        let csel = PTPTRIG::from_bits_truncate((trigger as u16) << PTPTRIG::CSEL_SHIFT);
+
+        self.paged(|regs| {
+            regs.write(select | PTPCTL::TRIG_DIS)?;
+            regs.write((csel & PTPTRIG::TRIG_CSEL) | PTPTRIG::TRIG_WR)
+        })
+    }
+
+    /// Read the status of a trigger unit
+    pub fn trigger_status(&mut self, trigger: u8) -> Result<TriggerStatus, MIIM::Error> {
+        // Every trigger has an active and an error bit, starting with
+        // trigger 0 in the low bits
+        let status = self.read_ext(PTP_TSTS)? >> ((trigger & 0b111) * 2);
+        Ok(TriggerStatus {
+            active: status & 0b01 != 0,
+            error: status & 0b10 != 0,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phy::dp83xxx::registers::PAGESEL;
+    use crate::sim::{SimChip, SimMiim, SimulatedPhy};
+    use crate::trace::{Direction, RecordingMiim, TraceBuffer, Transaction};
+    use crate::Phy;
+
+    const ADDR: u8 = 1;
+
+    /// The registers and values written by `configure_trigger(config)`,
+    /// without the page selects
+    fn trigger_writes(config: TriggerConfig) -> ([(u8, u16); 12], usize) {
+        let mut sim: SimMiim = SimMiim::new();
+        sim.add(ADDR, SimulatedPhy::for_chip(SimChip::Dp83640));
+        let mut trace = [Transaction::read(0, 0, 0); 32];
+        let miim = RecordingMiim::new(sim, TraceBuffer::new(&mut trace));
+        let mut phy = DP83640::new(miim, ADDR);
+        phy.configure_trigger(config).unwrap();
+
+        let log = phy.get_miim().log();
+        let writes = log
+            .iter()
+            .filter(|t| t.direction == Direction::Write && t.reg != PAGESEL as u16);
+        let mut data = [(0, 0); 12];
+        let mut len = 0;
+        for (slot, t) in data.iter_mut().zip(writes) {
+            *slot = (t.reg as u8, t.value);
+            len += 1;
+        }
+        (data, len)
+    }
+
+    fn config(trigger: u8) -> TriggerConfig {
+        TriggerConfig {
+            trigger,
+            gpio: Some(2),
+            periodic: true,
+            start: PtpTime {
+                seconds: 0x0005_0006,
+                nanoseconds: 0x0007_0008,
+            },
+            pulse_width_ns: 0x0001_0002,
+            pulse_width2_ns: 0x0003_0004,
+        }
+    }
+
+    fn expected(trigger: u8, widths: &[u16]) -> ([(u8, u16); 12], usize) {
+        let trig = (2 << PTPTRIG::GPIO_SHIFT) | ((trigger as u16) << PTPTRIG::CSEL_SHIFT);
+        let trig = trig | (PTPTRIG::TRIG_PULSE | PTPTRIG::TRIG_PER | PTPTRIG::TRIG_WR).bits();
+        let select = (trigger as u16) << PTPCTL::TRIG_SEL_SHIFT;
+        let (ctl, tdr) = (PTPCTL::ADDRESS.1, PTP_TDR.1);
+
+        let mut data = [(0, 0); 12];
+        data[0] = (PTPTRIG::ADDRESS.1, trig);
+        data[1] = (ctl, select | PTPCTL::TRIG_LOAD.bits());
+        // The start time, nanoseconds first and low words first
+        data[2..6].copy_from_slice(&[(tdr, 0x0008), (tdr, 0x0007), (tdr, 0x0006), (tdr, 0x0005)]);
+        for (slot, &width) in data[6..].iter_mut().zip(widths) {
+            *slot = (tdr, width);
+        }
+        let len = 6 + widths.len();
+        data[len] = (ctl, select | PTPCTL::TRIG_EN.bits());
+        (data, len + 1)
+    }
+
+    #[test]
+    fn triggers_0_and_1_load_two_pulse_widths() {
+        for trigger in 0..2 {
+            assert_eq!(
+                trigger_writes(config(trigger)),
+                expected(trigger, &[0x0002, 0x0001, 0x0004, 0x0003])
+            );
+        }
+    }
+
+    #[test]
+    fn triggers_2_to_7_load_one_pulse_width() {
+        for trigger in 2..8 {
+            assert_eq!(
+                trigger_writes(config(trigger)),
+                expected(trigger, &[0x0002, 0x0001])
+            );
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Energy Efficient Ethernet, as described in clause 78 of IEEE 802.3.
+
+use bitflags::bitflags;
+
+use crate::{Duplex, LinkSpeed, Miim, Phy};
+
+bitflags! {
+    /// The EEE modes in registers 3.20 (EEE capability), 7.60 (EEE
+    /// advertisement) and 7.61 (link partner EEE ability).
+    pub struct EeeModes: u16 {
+        /// EEE for 100BASE-TX
+        const _100BASETX = (1 << 1);
+        /// EEE for 1000BASE-T
+        const _1000BASET = (1 << 2);
+        /// EEE for 10GBASE-T
+        const _10GBASET = (1 << 3);
+        /// EEE for 1000BASE-KX
+        const _1000BASEKX = (1 << 4);
+        /// EEE for 10GBASE-KX4
+        const _10GBASEKX4 = (1 << 5);
+        /// EEE for 10GBASE-KR
+        const _10GBASEKR = (1 << 6);
+    }
+}
+
+/// The Low Power Idle status reported in register 3.1, the PCS status register.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LpiStatus {
+    /// LPI was received by the transmit path since this register was last read
+    pub tx_lpi_received: bool,
+    /// LPI was received by the receive path since this register was last read
+    pub rx_lpi_received: bool,
+    /// The transmit path is currently receiving LPI
+    pub tx_lpi_indication: bool,
+    /// The receive path is currently receiving LPI
+    pub rx_lpi_indication: bool,
+    /// The MAC may stop the transmit clock during LPI
+    pub tx_clock_stoppable: bool,
+}
+
+/// The MMD of the PCS
+const MMD_PCS: u8 = 3;
+/// The MMD of the autonegotiation registers
+pub(crate) const MMD_AN: u8 = 7;
+
+const PCS_CONTROL_1: u16 = 0;
+const PCS_STATUS_1: u16 = 1;
+const EEE_CAPABILITY: u16 = 20;
+const EEE_WAKE_ERROR_COUNTER: u16 = 22;
+pub(crate) const EEE_ADVERTISEMENT: u16 = 60;
+const EEE_PARTNER_ABILITY: u16 = 61;
+
+const PCS_CONTROL_1_CLOCK_STOP_ENABLE: u16 = 1 << 10;
+
+const PCS_STATUS_1_TX_LPI_RECEIVED: u16 = 1 << 11;
+const PCS_STATUS_1_RX_LPI_RECEIVED: u16 = 1 << 10;
+const PCS_STATUS_1_TX_LPI_INDICATION: u16 = 1 << 9;
+const PCS_STATUS_1_RX_LPI_INDICATION: u16 = 1 << 8;
+const PCS_STATUS_1_TX_CLOCK_STOPPABLE: u16 = 1 << 6;
+
+/// A PHY that supports Energy Efficient Ethernet.
+///
+/// All registers are accessed through [`Phy::mmd_read`] and [`Phy::mmd_write`].
+pub trait PhyWithEee<M: Miim>: Phy<M> + Sized {
+    /// Read the EEE modes that this PHY supports from register 3.20
+    fn eee_capabilities(&mut self) -> Result<EeeModes, M::Error> {
+        Ok(EeeModes::from_bits_truncate(
+            self.mmd_read(MMD_PCS, EEE_CAPABILITY)?,
+        ))
+    }
+
+    /// Read the EEE modes that this PHY advertises from register 7.60
+    fn eee_advertisement(&mut self) -> Result<EeeModes, M::Error> {
+        Ok(EeeModes::from_bits_truncate(
+            self.mmd_read(MMD_AN, EEE_ADVERTISEMENT)?,
+        ))
+    }
+
+    /// Advertise the EEE modes in `modes` that are also reported by
+    /// [`Self::eee_capabilities`], and restart autonegotiation so that the
+    /// new advertisement takes effect.
+    fn set_eee_advertisement(&mut self, modes: EeeModes) -> Result<(), M::Error> {
+        let modes = modes & self.eee_capabilities()?;
+        self.mmd_write(MMD_AN, EEE_ADVERTISEMENT, modes.bits())?;
+        self.modify_bcr(|bcr| {
+            bcr.restart_autonegotiation();
+        })
+    }
+
+    /// Read the EEE modes that the link partner advertises from register 7.61
+    fn eee_partner_advertisement(&mut self) -> Result<EeeModes, M::Error> {
+        Ok(EeeModes::from_bits_truncate(
+            self.mmd_read(MMD_AN, EEE_PARTNER_ABILITY)?,
+        ))
+    }
+
+    /// Check if EEE is active on the current link.
+    ///
+    /// EEE is active if the link was established using autonegotiation at a
+    /// full duplex speed for which both devices advertise EEE.
+    fn eee_active(&mut self) -> Result<bool, M::Error> {
+        if !self.phy_link_up()? {
+            return Ok(false);
+        }
+
+        let mode = match self.resolve_autonegotiation()? {
+            Some(result) if result.duplex == Duplex::Full => match result.speed {
+                LinkSpeed::Mbps100 => EeeModes::_100BASETX,
+                LinkSpeed::Mpbs1000 => EeeModes::_1000BASET,
+                _ => return Ok(false),
+            },
+            _ => return Ok(false),
+        };
+
+        let common = self.eee_advertisement()? & self.eee_partner_advertisement()?;
+        Ok(common.contains(mode))
+    }
+
+    /// Read the LPI status from register 3.1.
+    ///
+    /// Reading this register clears the `*_lpi_received` flags.
+    fn lpi_status(&mut self) -> Result<LpiStatus, M::Error> {
+        let status = self.mmd_read(MMD_PCS, PCS_STATUS_1)?;
+        Ok(LpiStatus {
+            tx_lpi_received: status & PCS_STATUS_1_TX_LPI_RECEIVED != 0,
+            rx_lpi_received: status & PCS_STATUS_1_RX_LPI_RECEIVED != 0,
+            tx_lpi_indication: status & PCS_STATUS_1_TX_LPI_INDICATION != 0,
+            rx_lpi_indication: status & PCS_STATUS_1_RX_LPI_INDICATION != 0,
+            tx_clock_stoppable: status & PCS_STATUS_1_TX_CLOCK_STOPPABLE != 0,
+        })
+    }
+
+    /// Allow or disallow the PHY to stop the receive clock during LPI,
+    /// through bit 3.0.10
+    fn set_clock_stop(&mut self, enable: bool) -> Result<(), M::Error> {
+        let mut control = self.mmd_read(MMD_PCS, PCS_CONTROL_1)?;
+        if enable {
+            control |= PCS_CONTROL_1_CLOCK_STOP_ENABLE;
+        } else {
+            control &= !PCS_CONTROL_1_CLOCK_STOP_ENABLE;
+        }
+        self.mmd_write(MMD_PCS, PCS_CONTROL_1, control)
+    }
+
+    /// Check if the PHY may stop the receive clock during LPI
+    fn clock_stop(&mut self) -> Result<bool, M::Error> {
+        Ok(self.mmd_read(MMD_PCS, PCS_CONTROL_1)? & PCS_CONTROL_1_CLOCK_STOP_ENABLE != 0)
+    }
+
+    /// Read the EEE wake error counter from register 3.22.
+    ///
+    /// This counter is cleared when read.
+    fn eee_wake_error_count(&mut self) -> Result<u16, M::Error> {
+        self.mmd_read(MMD_PCS, EEE_WAKE_ERROR_COUNTER)
+    }
+}
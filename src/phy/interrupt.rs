@@ -0,0 +1,61 @@
+//! A common interface to the interrupts of PHYs.
+
+use bitflags::bitflags;
+
+use crate::{Miim, Phy};
+
+bitflags! {
+    /// Events that a PHY can signal using an interrupt.
+    ///
+    /// Not all PHYs support all events, see [`PhyWithInterrupts::supported_interrupts`].
+    pub struct InterruptEvents: u16 {
+        /// The link went up
+        const LINK_UP = (1 << 0);
+        /// The link went down
+        const LINK_DOWN = (1 << 1);
+        /// Autonegotiation completed
+        const AUTONEG_COMPLETE = (1 << 2);
+        /// A remote fault was detected
+        const REMOTE_FAULT = (1 << 3);
+        /// Energy was detected on the line
+        const ENERGY_DETECT = (1 << 4);
+        /// Jabber was detected
+        const JABBER = (1 << 5);
+        /// An autonegotiation page was received
+        const PAGE_RECEIVED = (1 << 6);
+        /// A parallel detection fault occured
+        const PARALLEL_DETECT_FAULT = (1 << 7);
+        /// The link partner acknowledged the last autonegotiation page
+        const LINK_PARTNER_ACK = (1 << 8);
+        /// A receive error occured
+        const RECEIVE_ERROR = (1 << 9);
+        /// The link speed changed
+        const SPEED_CHANGE = (1 << 10);
+        /// The duplex mode changed
+        const DUPLEX_CHANGE = (1 << 11);
+        /// The false carrier counter is half full
+        const FALSE_CARRIER = (1 << 12);
+        /// A Wake-on-LAN event occured
+        const WAKE_ON_LAN = (1 << 13);
+    }
+}
+
+/// A PHY that can signal events on an interrupt pin.
+pub trait PhyWithInterrupts<MIIM: Miim>: Phy<MIIM> {
+    /// The events that this PHY can signal.
+    fn supported_interrupts(&self) -> InterruptEvents;
+
+    /// Enable the interrupts for `events`, leaving other interrupts unchanged.
+    ///
+    /// Events that are not in [`Self::supported_interrupts`] are ignored.
+    fn enable_interrupts(&mut self, events: InterruptEvents) -> Result<(), MIIM::Error>;
+
+    /// Disable the interrupts for `events`, leaving other interrupts unchanged.
+    fn disable_interrupts(&mut self, events: InterruptEvents) -> Result<(), MIIM::Error>;
+
+    /// Read and clear the events that have occured.
+    ///
+    /// Depending on the PHY, this may also report events whose interrupt is
+    /// not enabled.
+    fn read_and_clear_interrupts(&mut self) -> Result<InterruptEvents, MIIM::Error>;
+}
@@ -4,15 +4,27 @@ use crate::{registers::Esr, AutoNegotiationAdvertisement, ExtendedPhyStatus, Mii
 
 use self::registers::PhyControl1;
 
-use super::{AdvancedPhySpeed, PhySpeed, PhyWithSpeed};
+use super::{AdvancedPhySpeed, InterruptEvents, PhySpeed, PhyWithInterrupts, PhyWithSpeed};
 
 /// A KSZ8081R
 #[derive(Debug)]
-pub struct KSZ8081R<MIIM: Miim> {
+pub struct KSZ8081R<MIIM> {
     phy_addr: u8,
     miim: MIIM,
 }
 
+impl<MIIM> KSZ8081R<MIIM> {
+    /// Create a new Ksz8081r at `phy_addr`, backed by the given `miim`,
+    pub fn new(miim: MIIM, phy_addr: u8) -> Self {
+        Self { phy_addr, miim }
+    }
+
+    /// Release the underlying [`Miim`]
+    pub fn release(self) -> MIIM {
+        self.miim
+    }
+}
+
 impl<MIIM: Miim> KSZ8081R<MIIM> {
     const INTERRUPT_REG: u8 = 0x1B;
     const INTERRUPT_REG_EN_LINK_UP: u16 = 1 << 8;
@@ -23,41 +35,31 @@ impl<MIIM: Miim> KSZ8081R<MIIM> {
     /// A mask for determining if the Link Down Interrupt occurred
     pub const INTERRUPT_REG_INT_LINK_DOWN: u16 = 1 << 2;
 
-    /// Create a new Ksz8081r at `phy_addr`, backed by the given `miim`,
-    pub fn new(miim: MIIM, phy_addr: u8) -> Self {
-        Self { phy_addr, miim }
-    }
-
     /// Enable the link up and link down interrupts
-    pub fn interrupt_enable(&mut self) {
+    pub fn interrupt_enable(&mut self) -> Result<(), MIIM::Error> {
         self.write(
             Self::INTERRUPT_REG,
             Self::INTERRUPT_REG_EN_LINK_UP | Self::INTERRUPT_REG_EN_LINK_DOWN,
-        );
+        )
     }
 
     /// Get the link speed at which the PHY is currently operating
-    pub fn link_speed(&mut self) -> Option<PhySpeed> {
-        let phy_ctrl1 = PhyControl1::from_bits_truncate(self.read(PhyControl1::ADDRESS));
-        phy_ctrl1.into()
+    pub fn link_speed(&mut self) -> Result<Option<PhySpeed>, MIIM::Error> {
+        let phy_ctrl1 = PhyControl1::from_bits_truncate(self.read(PhyControl1::ADDRESS)?);
+        Ok(phy_ctrl1.into())
     }
 
     /// Get the value of the interrupt register.
     ///
     /// Use [`Self::INTERRUPT_REG_INT_LINK_UP`] and [`Self::INTERRUPT_REG_INT_LINK_DOWN`]
     /// to determine the type of interrupt that occurred
-    pub fn get_interrupt_reg_val(&mut self) -> u16 {
+    pub fn get_interrupt_reg_val(&mut self) -> Result<u16, MIIM::Error> {
         self.read(Self::INTERRUPT_REG)
     }
 
     /// Check whether a link is established or not
-    pub fn link_established(&mut self) -> bool {
-        self.autoneg_completed() && self.phy_link_up()
-    }
-
-    /// Release the underlying [`Miim`]
-    pub fn release(self) -> MIIM {
-        self.miim
+    pub fn link_established(&mut self) -> Result<bool, MIIM::Error> {
+        Ok(self.autoneg_completed()? && self.phy_link_up()?)
     }
 }
 
@@ -81,18 +83,125 @@ impl<MIIM: Miim> Phy<MIIM> for KSZ8081R<MIIM> {
         self.phy_addr
     }
 
-    fn esr(&mut self) -> Option<Esr> {
-        None
+    fn esr(&mut self) -> Result<Option<Esr>, MIIM::Error> {
+        Ok(None)
     }
 
-    fn extended_status(&mut self) -> Option<ExtendedPhyStatus> {
-        None
+    fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, MIIM::Error> {
+        Ok(None)
     }
 }
 
 impl<MIIM: Miim> PhyWithSpeed<MIIM> for KSZ8081R<MIIM> {
-    fn get_link_speed(&mut self) -> Option<AdvancedPhySpeed> {
-        self.link_speed().map(Into::into)
+    fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, MIIM::Error> {
+        Ok(self.link_speed()?.map(Into::into))
+    }
+}
+
+/// The events in the interrupt control/status register, in the order of
+/// their status bits. The enable bit of each event is 8 bits higher.
+const KSZ8081R_INTERRUPTS: [InterruptEvents; 8] = [
+    InterruptEvents::LINK_UP,
+    InterruptEvents::REMOTE_FAULT,
+    InterruptEvents::LINK_DOWN,
+    InterruptEvents::LINK_PARTNER_ACK,
+    InterruptEvents::PARALLEL_DETECT_FAULT,
+    InterruptEvents::PAGE_RECEIVED,
+    InterruptEvents::RECEIVE_ERROR,
+    InterruptEvents::JABBER,
+];
+
+impl<MIIM: Miim> KSZ8081R<MIIM> {
+    fn interrupt_bits(events: InterruptEvents) -> u16 {
+        KSZ8081R_INTERRUPTS
+            .iter()
+            .enumerate()
+            .filter(|(_, event)| events.contains(**event))
+            .fold(0, |bits, (n, _)| bits | (1 << n))
+    }
+
+    fn modify_interrupt_enables(&mut self, f: impl FnOnce(u16) -> u16) -> Result<(), MIIM::Error> {
+        let enabled = self.read(Self::INTERRUPT_REG)? >> 8;
+        self.write(Self::INTERRUPT_REG, f(enabled) << 8)
+    }
+}
+
+/// Note that the interrupt status bits of the KSZ8081R are cleared when the
+/// interrupt register is read, so enabling or disabling interrupts also clears
+/// any pending events.
+impl<MIIM: Miim> PhyWithInterrupts<MIIM> for KSZ8081R<MIIM> {
+    fn supported_interrupts(&self) -> InterruptEvents {
+        KSZ8081R_INTERRUPTS
+            .iter()
+            .fold(InterruptEvents::empty(), |all, event| all | *event)
+    }
+
+    fn enable_interrupts(&mut self, events: InterruptEvents) -> Result<(), MIIM::Error> {
+        let bits = Self::interrupt_bits(events);
+        self.modify_interrupt_enables(|enabled| enabled | bits)
+    }
+
+    fn disable_interrupts(&mut self, events: InterruptEvents) -> Result<(), MIIM::Error> {
+        let bits = Self::interrupt_bits(events);
+        self.modify_interrupt_enables(|enabled| enabled & !bits)
+    }
+
+    fn read_and_clear_interrupts(&mut self) -> Result<InterruptEvents, MIIM::Error> {
+        let status = self.read(Self::INTERRUPT_REG)?;
+        Ok(KSZ8081R_INTERRUPTS
+            .iter()
+            .enumerate()
+            .filter(|(n, _)| status & (1 << n) != 0)
+            .fold(InterruptEvents::empty(), |events, (_, event)| {
+                events | *event
+            }))
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use super::{registers::PhyControl1, KSZ8081R};
+    use crate::{
+        phy::{AdvancedPhySpeed, AsyncPhyWithSpeed, PhySpeed},
+        registers::Esr,
+        AsyncMiim, AsyncPhy, AutoNegotiationAdvertisement, ExtendedPhyStatus,
+    };
+
+    impl<MIIM: AsyncMiim> AsyncPhy<MIIM> for KSZ8081R<MIIM> {
+        fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+            AutoNegotiationAdvertisement {
+                hd_10base_t: true,
+                fd_10base_t: true,
+                hd_100base_tx: true,
+                fd_100base_tx: true,
+                base100_t4: true,
+                ..Default::default()
+            }
+        }
+
+        fn get_miim(&mut self) -> &mut MIIM {
+            &mut self.miim
+        }
+
+        fn get_phy_addr(&self) -> u8 {
+            self.phy_addr
+        }
+
+        async fn esr(&mut self) -> Result<Option<Esr>, MIIM::Error> {
+            Ok(None)
+        }
+
+        async fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, MIIM::Error> {
+            Ok(None)
+        }
+    }
+
+    impl<MIIM: AsyncMiim> AsyncPhyWithSpeed<MIIM> for KSZ8081R<MIIM> {
+        async fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, MIIM::Error> {
+            let phy_ctrl1 = PhyControl1::from_bits_truncate(self.read(PhyControl1::ADDRESS).await?);
+            let speed: Option<PhySpeed> = phy_ctrl1.into();
+            Ok(speed.map(Into::into))
+        }
     }
 }
 
@@ -150,3 +259,107 @@ pub mod registers {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{LinkPartner, SimChip, SimMiim, SimulatedPhy};
+    use crate::{Duplex, LinkSpeed};
+
+    const ADDR: u8 = 3;
+
+    fn all_modes() -> AutoNegotiationAdvertisement {
+        AutoNegotiationAdvertisement {
+            hd_10base_t: true,
+            fd_10base_t: true,
+            hd_100base_tx: true,
+            fd_100base_tx: true,
+            ..Default::default()
+        }
+    }
+
+    fn ksz8081r() -> KSZ8081R<SimMiim> {
+        let mut miim = SimMiim::new();
+        miim.add(ADDR, SimulatedPhy::for_chip(SimChip::Ksz8081r));
+        KSZ8081R::new(miim, ADDR)
+    }
+
+    fn sim(phy: &mut KSZ8081R<SimMiim>) -> &mut SimulatedPhy {
+        phy.get_miim().phy_mut(ADDR).unwrap()
+    }
+
+    fn connect(phy: &mut KSZ8081R<SimMiim>, advertisement: AutoNegotiationAdvertisement) {
+        sim(phy).connect(LinkPartner {
+            advertisement,
+            autonegotiation: true,
+        });
+    }
+
+    #[test]
+    fn autonegotiation() {
+        let mut phy = ksz8081r();
+        assert_eq!(phy.link_speed().unwrap(), None);
+
+        connect(&mut phy, all_modes());
+        assert!(phy.autoneg_completed().unwrap());
+        assert_eq!(
+            phy.link_speed().unwrap(),
+            Some(PhySpeed::FullDuplexBase100Tx)
+        );
+
+        phy.set_autonegotiation_advertisement(AutoNegotiationAdvertisement {
+            hd_10base_t: true,
+            fd_10base_t: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let result = phy.resolve_autonegotiation().unwrap().unwrap();
+        assert_eq!(
+            (result.speed, result.duplex),
+            (LinkSpeed::Mpbs10, Duplex::Full)
+        );
+        assert_eq!(phy.link_speed().unwrap(), Some(PhySpeed::FullDuplexBase10T));
+        assert_eq!(
+            phy.get_link_speed().unwrap(),
+            Some(AdvancedPhySpeed::FullDuplexBase10T)
+        );
+    }
+
+    #[test]
+    fn interrupt_status_is_cleared_on_read() {
+        let mut phy = ksz8081r();
+        phy.enable_interrupts(InterruptEvents::LINK_UP | InterruptEvents::LINK_DOWN)
+            .unwrap();
+        let enables = phy.get_interrupt_reg_val().unwrap();
+        assert_eq!(
+            enables,
+            KSZ8081R::<SimMiim>::INTERRUPT_REG_EN_LINK_UP
+                | KSZ8081R::<SimMiim>::INTERRUPT_REG_EN_LINK_DOWN
+        );
+
+        connect(&mut phy, all_modes());
+        assert!(sim(&mut phy).interrupt_asserted());
+        assert_eq!(
+            phy.read_and_clear_interrupts().unwrap(),
+            InterruptEvents::LINK_UP
+                | InterruptEvents::PAGE_RECEIVED
+                | InterruptEvents::LINK_PARTNER_ACK
+        );
+
+        // Reading 0x1B clears the status bits, but not the enables
+        assert!(!sim(&mut phy).interrupt_asserted());
+        assert_eq!(phy.get_interrupt_reg_val().unwrap(), enables);
+
+        sim(&mut phy).disconnect();
+        let status = phy.get_interrupt_reg_val().unwrap();
+        assert_eq!(
+            status,
+            enables | KSZ8081R::<SimMiim>::INTERRUPT_REG_INT_LINK_DOWN
+        );
+        assert_eq!(
+            phy.read_and_clear_interrupts().unwrap(),
+            InterruptEvents::empty()
+        );
+    }
+}
@@ -6,8 +6,13 @@ use crate::{
 };
 
 use self::registers::{Ssr, PHY_REG_WUCSR};
+#[cfg(feature = "lan8742a")]
+use self::registers::{
+    Wucsr, WufCfgA, PHY_REG_RX_ADDRA, PHY_REG_RX_ADDRB, PHY_REG_RX_ADDRC, PHY_REG_WUF_CFGA,
+    PHY_REG_WUF_CFGB, PHY_REG_WUF_MASK,
+};
 
-use super::{AdvancedPhySpeed, PhySpeed, PhyWithSpeed};
+use super::{AdvancedPhySpeed, InterruptEvents, PhySpeed, PhyWithInterrupts, PhyWithSpeed};
 
 /// SMSC LAN8720A Ethernet PHY
 pub type LAN8720A<MIIM> = LAN87xxA<MIIM, false>;
@@ -53,6 +58,116 @@ impl From<Interrupt> for InterruptReg {
     }
 }
 
+/// The Wake-on-LAN events supported by the LAN8742A.
+///
+/// This is used both to select the events that wake the system, and to
+/// report the events that have occured.
+#[cfg(feature = "lan8742a")]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WakeEvents {
+    /// A frame addressed to the address set with [`LAN8742A::set_wol_mac_address`]
+    pub perfect_da: bool,
+    /// A frame matching the filter set with [`LAN8742A::set_wakeup_filter`]
+    pub wakeup_frame: bool,
+    /// A magic packet
+    pub magic_packet: bool,
+    /// A broadcast frame
+    pub broadcast: bool,
+}
+
+#[cfg(feature = "lan8742a")]
+impl WakeEvents {
+    fn enable_flags(&self) -> Wucsr {
+        let mut wucsr = Wucsr::empty();
+        wucsr.set(Wucsr::PFDA_EN, self.perfect_da);
+        wucsr.set(Wucsr::WUEN, self.wakeup_frame);
+        wucsr.set(Wucsr::MPEN, self.magic_packet);
+        wucsr.set(Wucsr::BCST_EN, self.broadcast);
+        wucsr
+    }
+
+    fn from_received_flags(wucsr: Wucsr) -> Self {
+        Self {
+            perfect_da: wucsr.contains(Wucsr::PFDA_FR),
+            wakeup_frame: wucsr.contains(Wucsr::WUFR),
+            magic_packet: wucsr.contains(Wucsr::MPR),
+            broadcast: wucsr.contains(Wucsr::BCAST_FR),
+        }
+    }
+}
+
+/// A wake-up frame filter of the LAN8742A.
+///
+/// A frame matches the filter if the CRC-16 of the bytes selected by `mask`
+/// equals `crc`. Bit `n` of `mask` selects byte `offset + n` of the frame,
+/// where byte 0 is the first byte of the destination address.
+#[cfg(feature = "lan8742a")]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WakeUpFilter {
+    /// The offset of the first byte that `mask` applies to
+    pub offset: u8,
+    /// The byte mask
+    pub mask: u128,
+    /// The CRC-16 of the bytes selected by `mask`
+    pub crc: u16,
+    /// Only match frames addressed to the address set with
+    /// [`LAN8742A::set_wol_mac_address`]
+    pub address_match: bool,
+    /// Only match multicast frames
+    pub multicast: bool,
+    /// Only match broadcast frames
+    pub broadcast: bool,
+}
+
+#[cfg(feature = "lan8742a")]
+impl WakeUpFilter {
+    /// Create a filter that matches frames which contain the bytes of `pattern`
+    /// selected by `mask`, starting at `offset`.
+    ///
+    /// `pattern[n]` is compared to byte `offset + n` of the frame. Bits of
+    /// `mask` beyond the end of `pattern` are cleared.
+    pub fn new(offset: u8, pattern: &[u8], mut mask: u128) -> Self {
+        if pattern.len() < 128 {
+            mask &= (1 << pattern.len()) - 1;
+        }
+
+        let selected = pattern
+            .iter()
+            .take(128)
+            .enumerate()
+            .filter(|(n, _)| mask & (1 << n) != 0)
+            .map(|(_, byte)| *byte);
+
+        Self {
+            offset,
+            mask,
+            crc: Self::crc16(selected),
+            address_match: false,
+            multicast: false,
+            broadcast: false,
+        }
+    }
+
+    /// The CRC-16 used by the wake-up frame filter: CRC-16/ARC with an initial
+    /// value of 0xFFFF, bit-reversed.
+    pub fn crc16(data: impl IntoIterator<Item = u8>) -> u16 {
+        let mut crc: u16 = 0xFFFF;
+        for byte in data {
+            crc ^= byte as u16;
+            for _ in 0..8 {
+                if crc & 1 != 0 {
+                    crc = (crc >> 1) ^ 0xA001;
+                } else {
+                    crc >>= 1;
+                }
+            }
+        }
+        crc.reverse_bits()
+    }
+}
+
 /// An SMSC LAN87XXA Ethernet PHY.
 ///
 /// EXT_WUCSR_CLEAR is used to determine if the "WU CSR" bit
@@ -60,65 +175,77 @@ impl From<Interrupt> for InterruptReg {
 ///
 /// This type should not be used directly. Use [`LAN8720A`] or [`LAN8742A`] instead.
 #[derive(Debug)]
-pub struct LAN87xxA<M: Miim, const HAS_MMD: bool> {
+pub struct LAN87xxA<M, const HAS_MMD: bool> {
     phy_addr: u8,
     miim: M,
 }
 
-impl<M: Miim, const HAS_MMD: bool> LAN87xxA<M, HAS_MMD> {
+impl<M, const HAS_MMD: bool> LAN87xxA<M, HAS_MMD> {
     /// Create a new LAN87XXA based PHY
     pub fn new(miim: M, phy_addr: u8) -> Self {
         LAN87xxA { miim, phy_addr }
     }
 
+    /// Release the underlying [`Miim`]
+    pub fn release(self) -> M {
+        self.miim
+    }
+}
+
+impl<M: Miim, const HAS_MMD: bool> LAN87xxA<M, HAS_MMD> {
     /// Initialize the PHY
-    pub fn phy_init(&mut self) {
+    pub fn phy_init(&mut self) -> Result<(), M::Error> {
         if HAS_MMD {
             // Clear WU CSR
-            self.mmd_write(3, PHY_REG_WUCSR, 0);
+            self.mmd_write(3, PHY_REG_WUCSR, 0)?;
         }
 
-        self.set_autonegotiation_advertisement(self.best_supported_advertisement());
+        self.set_autonegotiation_advertisement(self.best_supported_advertisement())
     }
 
     /// Get the link speed
     ///
     /// If this returns `None`, some sort of corruption occured, or the PHY is
     /// in an illegal state
-    pub fn link_speed(&mut self) -> Option<PhySpeed> {
-        let ssr = Ssr::from_bits_truncate(self.read(Ssr::ADDRESS));
-        ssr.into()
+    pub fn link_speed(&mut self) -> Result<Option<PhySpeed>, M::Error> {
+        let ssr = Ssr::from_bits_truncate(self.read(Ssr::ADDRESS)?);
+        Ok(ssr.into())
     }
 
     /// Check if the link is up
-    pub fn link_established(&mut self) -> bool {
-        let bsr = self.bsr();
-        let ssr = Ssr::from_bits_truncate(self.read(Ssr::ADDRESS));
+    pub fn link_established(&mut self) -> Result<bool, M::Error> {
+        let bsr = self.bsr()?;
+        let ssr = Ssr::from_bits_truncate(self.read(Ssr::ADDRESS)?);
 
         // Link established only if it's up, and autonegotiation is completed
-        bsr.phy_link_up() && bsr.autoneg_completed() && ssr.contains(Ssr::AUTONEG_DONE)
+        Ok(bsr.phy_link_up() && bsr.autoneg_completed() && ssr.contains(Ssr::AUTONEG_DONE))
     }
 
-    /// Block until a link is established
-    pub fn block_until_link(&mut self) {
-        while !self.link_established() {}
+    /// Block until a link is established.
+    ///
+    /// This waits forever if no link is established. Use
+    /// [`bringup::wait_for_link`](crate::bringup::wait_for_link) to wait
+    /// with a timeout instead.
+    pub fn block_until_link(&mut self) -> Result<(), M::Error> {
+        while !self.link_established()? {}
+        Ok(())
     }
 
     /// Enable an interrupt
-    pub fn enable_interrupt(&mut self, interrupt: Interrupt) {
+    pub fn enable_interrupt(&mut self, interrupt: Interrupt) -> Result<(), M::Error> {
         let mut reg_val =
-            unsafe { InterruptReg::from_bits_unchecked(self.read(InterruptReg::MASK_ADDR)) };
+            unsafe { InterruptReg::from_bits_unchecked(self.read(InterruptReg::MASK_ADDR)?) };
         reg_val.insert(interrupt.into());
-        self.write(InterruptReg::MASK_ADDR, reg_val.bits());
+        self.write(InterruptReg::MASK_ADDR, reg_val.bits())
     }
 
     /// Read and clear all interrupts
     pub fn read_and_clear_active_interrupts(
         &mut self,
         interrupt_storage: &mut [Option<Interrupt>; 8],
-    ) {
+    ) -> Result<(), M::Error> {
         let reg_val =
-            unsafe { InterruptReg::from_bits_unchecked(self.read(InterruptReg::SOURCE_ADDR)) };
+            unsafe { InterruptReg::from_bits_unchecked(self.read(InterruptReg::SOURCE_ADDR)?) };
 
         let mut int_idx = 0;
         macro_rules! int {
@@ -154,11 +281,8 @@ impl<M: Miim, const HAS_MMD: bool> LAN87xxA<M, HAS_MMD> {
 
         #[cfg(feature = "lan8742a")]
         int!(InterruptReg::INT8_WOL, Interrupt::WoL);
-    }
 
-    /// Release the underlying [`Miim`]
-    pub fn release(self) -> M {
-        self.miim
+        Ok(())
     }
 }
 
@@ -182,8 +306,8 @@ impl<M: Miim, const E: bool> Phy<M> for LAN87xxA<M, E> {
         self.phy_addr
     }
 
-    fn status(&mut self) -> PhyStatus {
-        crate::PhyStatus {
+    fn status(&mut self) -> Result<PhyStatus, M::Error> {
+        Ok(crate::PhyStatus {
             base100_t4: false,
             fd_100base_x: true,
             hd_100base_x: true,
@@ -194,21 +318,273 @@ impl<M: Miim, const E: bool> Phy<M> for LAN87xxA<M, E> {
             preamble_suppression: false,
             autonegotiation: true,
             extended_caps: true,
-        }
+        })
     }
 
-    fn esr(&mut self) -> Option<Esr> {
-        None
+    fn esr(&mut self) -> Result<Option<Esr>, M::Error> {
+        Ok(None)
     }
 
-    fn extended_status(&mut self) -> Option<ExtendedPhyStatus> {
-        None
+    fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, M::Error> {
+        Ok(None)
     }
 }
 
 impl<M: Miim, const E: bool> PhyWithSpeed<M> for LAN87xxA<M, E> {
-    fn get_link_speed(&mut self) -> Option<AdvancedPhySpeed> {
-        self.link_speed().map(Into::into)
+    fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, M::Error> {
+        Ok(self.link_speed()?.map(Into::into))
+    }
+}
+
+/// The events that the LAN87xxA can signal, and their flags in
+/// the interrupt source and mask registers.
+const LAN87XXA_INTERRUPTS: &[(InterruptEvents, InterruptReg)] = &[
+    (
+        InterruptEvents::PAGE_RECEIVED,
+        InterruptReg::INT1_AUTO_NEG_PAGE_RECVD,
+    ),
+    (
+        InterruptEvents::PARALLEL_DETECT_FAULT,
+        InterruptReg::INT2_PARALLELL_DETECTION_FAULT,
+    ),
+    (
+        InterruptEvents::LINK_PARTNER_ACK,
+        InterruptReg::INT3_AUTO_NEG_LP_ACK,
+    ),
+    (InterruptEvents::LINK_DOWN, InterruptReg::INT4_LINK_DOWN),
+    (
+        InterruptEvents::REMOTE_FAULT,
+        InterruptReg::INT5_REMOTE_FAULT,
+    ),
+    (
+        InterruptEvents::AUTONEG_COMPLETE,
+        InterruptReg::INT6_AUTO_NEG_COMPLETE,
+    ),
+    (InterruptEvents::ENERGY_DETECT, InterruptReg::INT7_ENERGYON),
+    #[cfg(feature = "lan8742a")]
+    (InterruptEvents::WAKE_ON_LAN, InterruptReg::INT8_WOL),
+];
+
+impl<M: Miim, const HAS_MMD: bool> LAN87xxA<M, HAS_MMD> {
+    fn interrupt_reg(&self, events: InterruptEvents) -> InterruptReg {
+        let events = events & self.supported_interrupts();
+        LAN87XXA_INTERRUPTS
+            .iter()
+            .filter(|(event, _)| events.contains(*event))
+            .fold(InterruptReg::empty(), |reg, (_, flag)| reg | *flag)
+    }
+}
+
+impl<M: Miim, const HAS_MMD: bool> PhyWithInterrupts<M> for LAN87xxA<M, HAS_MMD> {
+    fn supported_interrupts(&self) -> InterruptEvents {
+        let all = LAN87XXA_INTERRUPTS
+            .iter()
+            .fold(InterruptEvents::empty(), |all, (event, _)| all | *event);
+
+        // Only the LAN8742A supports Wake-on-LAN
+        if HAS_MMD {
+            all
+        } else {
+            all - InterruptEvents::WAKE_ON_LAN
+        }
+    }
+
+    fn enable_interrupts(&mut self, events: InterruptEvents) -> Result<(), M::Error> {
+        let flags = self.interrupt_reg(events);
+        let mask = self.read(InterruptReg::MASK_ADDR)?;
+        self.write(InterruptReg::MASK_ADDR, mask | flags.bits())
+    }
+
+    fn disable_interrupts(&mut self, events: InterruptEvents) -> Result<(), M::Error> {
+        let flags = self.interrupt_reg(events);
+        let mask = self.read(InterruptReg::MASK_ADDR)?;
+        self.write(InterruptReg::MASK_ADDR, mask & !flags.bits())
+    }
+
+    fn read_and_clear_interrupts(&mut self) -> Result<InterruptEvents, M::Error> {
+        let source = InterruptReg::from_bits_truncate(self.read(InterruptReg::SOURCE_ADDR)?);
+        Ok(LAN87XXA_INTERRUPTS
+            .iter()
+            .filter(|(_, flag)| source.contains(*flag))
+            .fold(InterruptEvents::empty(), |events, (event, _)| {
+                events | *event
+            }))
+    }
+}
+
+#[cfg(feature = "lan8742a")]
+impl<M: Miim> LAN8742A<M> {
+    /// Set the MAC address used for perfect DA matching, and by
+    /// wake-up filters with [`WakeUpFilter::address_match`] set.
+    pub fn set_wol_mac_address(&mut self, mac: [u8; 6]) -> Result<(), M::Error> {
+        let addr = |lo: u8, hi: u8| ((hi as u16) << 8) | lo as u16;
+        self.mmd_write(3, PHY_REG_RX_ADDRA, addr(mac[4], mac[5]))?;
+        self.mmd_write(3, PHY_REG_RX_ADDRB, addr(mac[2], mac[3]))?;
+        self.mmd_write(3, PHY_REG_RX_ADDRC, addr(mac[0], mac[1]))
+    }
+
+    /// Configure and enable the wake-up frame filter
+    pub fn set_wakeup_filter(&mut self, filter: &WakeUpFilter) -> Result<(), M::Error> {
+        self.mmd_write(3, PHY_REG_WUF_CFGB, filter.crc)?;
+
+        for n in 0..8 {
+            let mask = (filter.mask >> (112 - 16 * n)) as u16;
+            self.mmd_write(3, PHY_REG_WUF_MASK + n, mask)?;
+        }
+
+        let mut cfga = WufCfgA::FILTER_EN;
+        cfga.set(WufCfgA::ADDRESS_MATCH_EN, filter.address_match);
+        cfga.set(WufCfgA::FILTER_ANY_MULTICAST, filter.multicast);
+        cfga.set(WufCfgA::FILTER_ANY_BROADCAST, filter.broadcast);
+        self.mmd_write(3, PHY_REG_WUF_CFGA, cfga.bits() | filter.offset as u16)
+    }
+
+    /// Disable the wake-up frame filter
+    pub fn disable_wakeup_filter(&mut self) -> Result<(), M::Error> {
+        self.mmd_write(3, PHY_REG_WUF_CFGA, 0)
+    }
+
+    /// Arm Wake-on-LAN for the events in `events`, and clear all received events.
+    ///
+    /// Enable [`Interrupt::WoL`] to be notified when one of these events occurs.
+    pub fn enable_wol(&mut self, events: WakeEvents) -> Result<(), M::Error> {
+        // The received flags are cleared by writing a 1
+        let wucsr = Wucsr::WOL_CONFIGURED
+            | events.enable_flags()
+            | Wucsr::PFDA_FR
+            | Wucsr::WUFR
+            | Wucsr::MPR
+            | Wucsr::BCAST_FR;
+        self.mmd_write(3, PHY_REG_WUCSR, wucsr.bits())
+    }
+
+    /// Disarm Wake-on-LAN
+    pub fn disable_wol(&mut self) -> Result<(), M::Error> {
+        self.mmd_write(3, PHY_REG_WUCSR, 0)
+    }
+
+    /// Read the Wake-on-LAN events that have occured, and clear them.
+    pub fn read_and_clear_wake_events(&mut self) -> Result<WakeEvents, M::Error> {
+        let wucsr = unsafe { Wucsr::from_bits_unchecked(self.mmd_read(3, PHY_REG_WUCSR)?) };
+        // Writing back the register clears the received flags
+        // that are set, and keeps the configuration.
+        self.mmd_write(3, PHY_REG_WUCSR, wucsr.bits())?;
+        Ok(WakeEvents::from_received_flags(wucsr))
+    }
+}
+
+#[cfg(feature = "lan8742a")]
+impl<M: Miim> super::PhyWithEee<M> for LAN8742A<M> {
+    /// Advertise the EEE modes in `modes` that are also reported by
+    /// [`Self::eee_capabilities`], and restart autonegotiation.
+    ///
+    /// The LAN8742A only performs EEE if `PHYEEEEN` is set, so it is
+    /// set if any mode is advertised, and cleared otherwise.
+    fn set_eee_advertisement(&mut self, modes: super::EeeModes) -> Result<(), M::Error> {
+        use super::eee::{EEE_ADVERTISEMENT, MMD_AN};
+        use registers::EdpdCfg;
+
+        let modes = modes & self.eee_capabilities()?;
+        self.mmd_write(MMD_AN, EEE_ADVERTISEMENT, modes.bits())?;
+
+        let mut cfg = unsafe { EdpdCfg::from_bits_unchecked(self.read(EdpdCfg::ADDRESS)?) };
+        cfg.set(EdpdCfg::PHYEEEEN, !modes.is_empty());
+        self.write(EdpdCfg::ADDRESS, cfg.bits())?;
+
+        self.modify_bcr(|bcr| {
+            bcr.restart_autonegotiation();
+        })
+    }
+}
+
+#[cfg(feature = "async")]
+mod asynch {
+    use super::{registers::Ssr, LAN87xxA, PHY_REG_WUCSR};
+    use crate::{
+        asynch::yield_now,
+        phy::{AdvancedPhySpeed, AsyncPhyWithSpeed, PhySpeed},
+        registers::Esr,
+        AsyncMiim, AsyncPhy, AutoNegotiationAdvertisement, ExtendedPhyStatus, PhyStatus,
+    };
+
+    impl<M: AsyncMiim, const HAS_MMD: bool> LAN87xxA<M, HAS_MMD> {
+        /// Initialize the PHY
+        ///
+        /// This is the asynchronous version of [`LAN87xxA::phy_init`].
+        pub async fn phy_init_async(&mut self) -> Result<(), M::Error> {
+            if HAS_MMD {
+                // Clear WU CSR
+                self.mmd_write(3, PHY_REG_WUCSR, 0).await?;
+            }
+
+            self.set_autonegotiation_advertisement(self.best_supported_advertisement())
+                .await
+        }
+    }
+
+    impl<M: AsyncMiim, const E: bool> AsyncPhy<M> for LAN87xxA<M, E> {
+        fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+            AutoNegotiationAdvertisement {
+                hd_10base_t: true,
+                fd_10base_t: true,
+                hd_100base_tx: true,
+                fd_100base_tx: true,
+                base100_t4: false,
+                ..Default::default()
+            }
+        }
+
+        fn get_miim(&mut self) -> &mut M {
+            &mut self.miim
+        }
+
+        fn get_phy_addr(&self) -> u8 {
+            self.phy_addr
+        }
+
+        async fn status(&mut self) -> Result<PhyStatus, M::Error> {
+            Ok(PhyStatus {
+                base100_t4: false,
+                fd_100base_x: true,
+                hd_100base_x: true,
+                fd_10mbps: true,
+                hd_10mbps: true,
+                extended_status: false,
+                unidirectional: false,
+                preamble_suppression: false,
+                autonegotiation: true,
+                extended_caps: true,
+            })
+        }
+
+        async fn esr(&mut self) -> Result<Option<Esr>, M::Error> {
+            Ok(None)
+        }
+
+        async fn extended_status(&mut self) -> Result<Option<ExtendedPhyStatus>, M::Error> {
+            Ok(None)
+        }
+
+        async fn wait_for_link(&mut self) -> Result<(), M::Error> {
+            loop {
+                let bsr = self.bsr().await?;
+                let ssr = Ssr::from_bits_truncate(self.read(Ssr::ADDRESS).await?);
+
+                // Link established only if it's up, and autonegotiation is completed
+                if bsr.phy_link_up() && bsr.autoneg_completed() && ssr.contains(Ssr::AUTONEG_DONE) {
+                    return Ok(());
+                }
+                yield_now().await;
+            }
+        }
+    }
+
+    impl<M: AsyncMiim, const E: bool> AsyncPhyWithSpeed<M> for LAN87xxA<M, E> {
+        async fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, M::Error> {
+            let ssr = Ssr::from_bits_truncate(self.read(Ssr::ADDRESS).await?);
+            let speed: Option<PhySpeed> = ssr.into();
+            Ok(speed.map(Into::into))
+        }
     }
 }
 
@@ -221,6 +597,14 @@ pub mod registers {
     use crate::phy::PhySpeed;
 
     pub const PHY_REG_WUCSR: u16 = 0x8010;
+    pub const PHY_REG_WUF_CFGA: u16 = 0x8011;
+    pub const PHY_REG_WUF_CFGB: u16 = 0x8012;
+    /// The first of 8 consecutive wake-up filter byte mask registers,
+    /// starting with bits 127:112 of the mask
+    pub const PHY_REG_WUF_MASK: u16 = 0x8021;
+    pub const PHY_REG_RX_ADDRA: u16 = 0x8061;
+    pub const PHY_REG_RX_ADDRB: u16 = 0x8062;
+    pub const PHY_REG_RX_ADDRC: u16 = 0x8063;
 
     bitflags! {
         pub struct InterruptReg: u16 {
@@ -235,6 +619,37 @@ pub mod registers {
             const INT8_WOL = (1 << 8);
         }
 
+        /// MMD 3, register 0x8010: the Wakeup Control and Status Register
+        pub struct Wucsr: u16 {
+            const WOL_CONFIGURED = (1 << 8);
+            const PFDA_FR = (1 << 7);
+            const WUFR = (1 << 6);
+            const MPR = (1 << 5);
+            const BCAST_FR = (1 << 4);
+            const PFDA_EN = (1 << 3);
+            const WUEN = (1 << 2);
+            const MPEN = (1 << 1);
+            const BCST_EN = (1 << 0);
+        }
+
+        /// MMD 3, register 0x8011: Wakeup Filter Configuration Register A
+        pub struct WufCfgA: u16 {
+            const FILTER_EN = (1 << 15);
+            const FILTER_TRIGGERED = (1 << 14);
+            const ADDRESS_MATCH_EN = (1 << 10);
+            const FILTER_ANY_MULTICAST = (1 << 9);
+            const FILTER_ANY_BROADCAST = (1 << 8);
+            const PATTERN_OFFSET_MASK = (0xFF);
+        }
+
+        pub struct EdpdCfg: u16 {
+            const EDPD_TX_NLP_EN = (1 << 15);
+            const EDPD_RX_SINGLE_NLP_WAKE_EN = (1 << 12);
+            const PHYEEEEN = (1 << 2);
+            const EDPD_EXT_CROSSOVER = (1 << 1);
+            const EXT_CROSSOVER_TIME = (1 << 0);
+        }
+
         pub struct Ssr: u16 {
             const AUTONEG_DONE = (1 << 12);
             const FULL_DUPLEX = (0b1 << 4);
@@ -248,6 +663,10 @@ pub mod registers {
         pub const MASK_ADDR: u8 = 30;
     }
 
+    impl EdpdCfg {
+        pub const ADDRESS: u8 = 16;
+    }
+
     impl Ssr {
         pub const ADDRESS: u8 = 31;
     }
@@ -282,3 +701,143 @@ pub mod registers {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{LinkPartner, SimChip, SimMiim, SimulatedPhy};
+    use crate::{Duplex, LinkSpeed};
+
+    const ADDR: u8 = 0;
+
+    fn all_modes() -> AutoNegotiationAdvertisement {
+        AutoNegotiationAdvertisement {
+            hd_10base_t: true,
+            fd_10base_t: true,
+            hd_100base_tx: true,
+            fd_100base_tx: true,
+            ..Default::default()
+        }
+    }
+
+    fn lan87xxa<const HAS_MMD: bool>(chip: SimChip) -> LAN87xxA<SimMiim, HAS_MMD> {
+        let mut miim = SimMiim::new();
+        miim.add(ADDR, SimulatedPhy::for_chip(chip));
+        LAN87xxA::new(miim, ADDR)
+    }
+
+    fn sim<const HAS_MMD: bool>(phy: &mut LAN87xxA<SimMiim, HAS_MMD>) -> &mut SimulatedPhy {
+        phy.get_miim().phy_mut(ADDR).unwrap()
+    }
+
+    fn check_autonegotiation<const HAS_MMD: bool>(chip: SimChip) {
+        let mut phy = lan87xxa::<HAS_MMD>(chip);
+        phy.phy_init().unwrap();
+        assert_eq!(phy.link_speed().unwrap(), None);
+
+        sim(&mut phy).connect(LinkPartner {
+            advertisement: AutoNegotiationAdvertisement {
+                hd_10base_t: true,
+                hd_100base_tx: true,
+                ..Default::default()
+            },
+            autonegotiation: true,
+        });
+        assert!(phy.link_established().unwrap());
+        assert_eq!(
+            phy.link_speed().unwrap(),
+            Some(PhySpeed::HalfDuplexBase100Tx)
+        );
+
+        phy.set_autonegotiation_advertisement(AutoNegotiationAdvertisement {
+            hd_10base_t: true,
+            fd_10base_t: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        let result = phy.resolve_autonegotiation().unwrap().unwrap();
+        assert_eq!(
+            (result.speed, result.duplex),
+            (LinkSpeed::Mpbs10, Duplex::Half)
+        );
+        assert_eq!(phy.link_speed().unwrap(), Some(PhySpeed::HalfDuplexBase10T));
+        assert_eq!(
+            phy.get_link_speed().unwrap(),
+            Some(AdvancedPhySpeed::HalfDuplexBase10T)
+        );
+    }
+
+    fn check_interrupts<const HAS_MMD: bool>(chip: SimChip) {
+        let mut phy = lan87xxa::<HAS_MMD>(chip);
+        phy.enable_interrupts(phy.supported_interrupts()).unwrap();
+
+        sim(&mut phy).connect(LinkPartner {
+            advertisement: all_modes(),
+            autonegotiation: true,
+        });
+        assert!(sim(&mut phy).interrupt_asserted());
+        assert_eq!(
+            phy.read_and_clear_interrupts().unwrap(),
+            InterruptEvents::PAGE_RECEIVED
+                | InterruptEvents::LINK_PARTNER_ACK
+                | InterruptEvents::AUTONEG_COMPLETE
+                | InterruptEvents::ENERGY_DETECT
+        );
+
+        // Reading the interrupt source register clears it
+        assert!(!sim(&mut phy).interrupt_asserted());
+        assert_eq!(phy.read(InterruptReg::SOURCE_ADDR).unwrap(), 0);
+
+        sim(&mut phy).disconnect();
+        let mut active = [None; 8];
+        phy.read_and_clear_active_interrupts(&mut active).unwrap();
+        assert_eq!(active[..2], [Some(Interrupt::LinkDown), None]);
+        assert_eq!(
+            phy.read_and_clear_interrupts().unwrap(),
+            InterruptEvents::empty()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "lan8720a")]
+    fn lan8720a_autonegotiation() {
+        check_autonegotiation::<false>(SimChip::Lan8720a);
+    }
+
+    #[test]
+    #[cfg(feature = "lan8720a")]
+    fn lan8720a_interrupt_source_is_cleared_on_read() {
+        check_interrupts::<false>(SimChip::Lan8720a);
+    }
+
+    #[test]
+    #[cfg(feature = "lan8742a")]
+    fn lan8742a_autonegotiation() {
+        check_autonegotiation::<true>(SimChip::Lan8742a);
+    }
+
+    #[test]
+    #[cfg(feature = "lan8742a")]
+    fn lan8742a_interrupt_source_is_cleared_on_read() {
+        check_interrupts::<true>(SimChip::Lan8742a);
+    }
+
+    #[test]
+    #[cfg(feature = "lan8742a")]
+    fn lan8742a_wol_mac_address() {
+        let mut phy = lan87xxa::<true>(SimChip::Lan8742a);
+        phy.set_wol_mac_address([0x00, 0x80, 0x0F, 0x12, 0x34, 0x56])
+            .unwrap();
+
+        let sim = phy.get_miim().phy(ADDR).unwrap();
+        assert_eq!(sim.mmd_register(3, 0x8061), 0x5634);
+        assert_eq!(sim.mmd_register(3, 0x8062), 0x120F);
+        assert_eq!(sim.mmd_register(3, 0x8063), 0x8000);
+
+        // The address is also read back through registers 13 and 14
+        assert_eq!(phy.mmd_read(3, 0x8061).unwrap(), 0x5634);
+        assert_eq!(phy.mmd_read(3, 0x8062).unwrap(), 0x120F);
+        assert_eq!(phy.mmd_read(3, 0x8063).unwrap(), 0x8000);
+    }
+}
@@ -1,6 +1,6 @@
 //! Implementations of MIIM for existing PHYs
 
-use crate::{Miim, Phy};
+use crate::{Duplex, ExtendedPhyStatus, LinkSpeed, Miim, Phy, PhyStatus};
 
 #[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
 pub mod lan87xxa;
@@ -8,18 +8,37 @@ pub mod lan87xxa;
 pub use lan87xxa::{LAN8720A, LAN8742A};
 
 #[cfg(feature = "ksz8081r")]
-mod ksz8081r;
+pub(crate) mod ksz8081r;
 #[cfg(feature = "ksz8081r")]
 pub use ksz8081r::KSZ8081R;
 
 #[cfg(any(feature = "dp83848", feature = "dp83640"))]
-mod dp83xxx;
+pub mod dp83xxx;
+#[cfg(feature = "dp83640")]
+pub use dp83xxx::{
+    EventConfig, EventEdge, EventTimestamp, PtpTransports, RxTimestamp, RxTimestampConfig,
+    TriggerConfig, TriggerStatus, TxTimestamp, TxTimestampConfig,
+};
 #[cfg(any(feature = "dp83848", feature = "dp83640"))]
 pub use dp83xxx::{DP83640, DP83848};
 
-mod bare;
+pub(crate) mod bare;
 pub use bare::BarePhy;
 
+#[cfg(feature = "mmd")]
+mod eee;
+#[cfg(feature = "mmd")]
+pub use eee::{EeeModes, LpiStatus, PhyWithEee};
+
+mod any;
+pub use any::AnyPhy;
+
+mod interrupt;
+pub use interrupt::{InterruptEvents, PhyWithInterrupts};
+
+mod monitor;
+pub use monitor::{LinkEvent, LinkMonitor};
+
 /// Basic link speeds, supported by (almost all) PHYs
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -36,7 +55,6 @@ pub enum PhySpeed {
 
 /// An "advanced link speed" enum that covers more than just the
 /// basic ones described by the standard.
-
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[allow(missing_docs)]
@@ -62,10 +80,184 @@ impl From<PhySpeed> for AdvancedPhySpeed {
     }
 }
 
+impl AdvancedPhySpeed {
+    /// Create the link speed for 10BASE-T, 100BASE-TX or 1000BASE-T at `speed`
+    /// and `duplex`.
+    ///
+    /// Returns `None` if `speed` is [`LinkSpeed::Illegal`].
+    pub fn new(speed: LinkSpeed, duplex: Duplex) -> Option<Self> {
+        let speed = match (speed, duplex) {
+            (LinkSpeed::Mpbs10, Duplex::Half) => Self::HalfDuplexBase10T,
+            (LinkSpeed::Mpbs10, Duplex::Full) => Self::FullDuplexBase10T,
+            (LinkSpeed::Mbps100, Duplex::Half) => Self::HalfDuplexBase100Tx,
+            (LinkSpeed::Mbps100, Duplex::Full) => Self::FullDuplexBase100Tx,
+            (LinkSpeed::Mpbs1000, Duplex::Half) => Self::HalfDuplexBase1000T,
+            (LinkSpeed::Mpbs1000, Duplex::Full) => Self::FullDuplexBase1000T,
+            (LinkSpeed::Illegal, _) => return None,
+        };
+        Some(speed)
+    }
+
+    /// The speed of this link
+    pub fn speed(&self) -> LinkSpeed {
+        match self {
+            Self::HalfDuplexBase10T | Self::FullDuplexBase10T => LinkSpeed::Mpbs10,
+            Self::HalfDuplexBase100Tx | Self::FullDuplexBase100Tx => LinkSpeed::Mbps100,
+            Self::HalfDuplexBase1000T
+            | Self::FullDuplexBase1000T
+            | Self::HalfDuplexBase1000Tx
+            | Self::FullDuplexBase1000Tx => LinkSpeed::Mpbs1000,
+        }
+    }
+
+    /// Check if a PHY reporting `status` and `extended_status` supports
+    /// this link speed.
+    ///
+    /// 1000BASE-TX is considered to be supported if the PHY supports
+    /// 1000BASE-X.
+    pub fn is_supported(
+        &self,
+        status: &PhyStatus,
+        extended_status: Option<&ExtendedPhyStatus>,
+    ) -> bool {
+        let ext = |f: fn(&ExtendedPhyStatus) -> bool| extended_status.map(f).unwrap_or(false);
+
+        match self {
+            Self::HalfDuplexBase10T => status.hd_10mbps,
+            Self::FullDuplexBase10T => status.fd_10mbps,
+            Self::HalfDuplexBase100Tx => status.hd_100base_x,
+            Self::FullDuplexBase100Tx => status.fd_100base_x,
+            Self::HalfDuplexBase1000T => ext(|e| e.hd_1000base_t),
+            Self::FullDuplexBase1000T => ext(|e| e.fd_1000base_t),
+            Self::HalfDuplexBase1000Tx => ext(|e| e.hd_1000base_x),
+            Self::FullDuplexBase1000Tx => ext(|e| e.fd_1000base_x),
+        }
+    }
+
+    /// Check if this link can be established with autonegotiation disabled.
+    ///
+    /// This is not the case for 1000BASE-T, which uses autonegotiation to
+    /// determine which PHY is the master (IEEE 802.3 Clause 40).
+    pub fn can_be_forced(&self) -> bool {
+        !matches!(self, Self::HalfDuplexBase1000T | Self::FullDuplexBase1000T)
+    }
+
+    /// The duplex mode of this link
+    pub fn duplex(&self) -> Duplex {
+        match self {
+            Self::HalfDuplexBase10T
+            | Self::HalfDuplexBase100Tx
+            | Self::HalfDuplexBase1000T
+            | Self::HalfDuplexBase1000Tx => Duplex::Half,
+            Self::FullDuplexBase10T
+            | Self::FullDuplexBase100Tx
+            | Self::FullDuplexBase1000T
+            | Self::FullDuplexBase1000Tx => Duplex::Full,
+        }
+    }
+}
+
 /// A PHY that also supports determining the link speed and duplex mode
 /// it is currently operating at.
 pub trait PhyWithSpeed<MIIM: Miim>: Phy<MIIM> {
     /// Get the link speed at which this PHY is currently
     /// operating.
-    fn get_link_speed(&mut self) -> Option<AdvancedPhySpeed>;
+    fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, MIIM::Error>;
+}
+
+/// An asynchronous version of [`PhyWithSpeed`].
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncPhyWithSpeed<MIIM: crate::AsyncMiim>: crate::AsyncPhy<MIIM> {
+    /// Get the link speed at which this PHY is currently
+    /// operating.
+    async fn get_link_speed(&mut self) -> Result<Option<AdvancedPhySpeed>, MIIM::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registers::Bsr;
+    use crate::sim::{LinkPartner, SimChip, SimMiim, SimulatedPhy};
+    use crate::{AutoNegotiationAdvertisement, ForceLinkError, Pause};
+
+    const ADDR: u8 = 1;
+
+    fn partner() -> LinkPartner {
+        LinkPartner {
+            advertisement: AutoNegotiationAdvertisement {
+                hd_10base_t: true,
+                fd_10base_t: true,
+                hd_100base_tx: true,
+                fd_100base_tx: true,
+                hd_1000base_t: true,
+                fd_1000base_t: true,
+                ..Default::default()
+            },
+            autonegotiation: true,
+        }
+    }
+
+    fn bare(sim: SimulatedPhy) -> BarePhy<SimMiim> {
+        let mut miim = SimMiim::<1>::new();
+        miim.add(ADDR, sim);
+        miim.phy_mut(ADDR).unwrap().connect(partner());
+        BarePhy::new(miim, ADDR, Pause::NoPause).unwrap()
+    }
+
+    #[test]
+    fn forces_10_and_100_mbps() {
+        let modes = [
+            AdvancedPhySpeed::HalfDuplexBase10T,
+            AdvancedPhySpeed::FullDuplexBase10T,
+            AdvancedPhySpeed::HalfDuplexBase100Tx,
+            AdvancedPhySpeed::FullDuplexBase100Tx,
+        ];
+
+        for speed in modes {
+            let mut phy = bare(SimulatedPhy::for_chip(SimChip::Generic));
+            phy.force_link(speed).unwrap();
+
+            let bcr = phy.bcr().unwrap();
+            assert!(!bcr.autonegotiation());
+            assert_eq!(LinkSpeed::from(bcr), speed.speed());
+            assert_eq!(bcr.full_duplex(), speed.duplex() == Duplex::Full);
+
+            let sim = phy.get_miim().phy(ADDR).unwrap();
+            assert_eq!(sim.link(), Some((speed.speed(), speed.duplex())));
+        }
+    }
+
+    #[test]
+    fn rejects_1000base_t() {
+        let bsr = Bsr::_100BASEXFD
+            | Bsr::_100BASEXHD
+            | Bsr::_10MPBSFD
+            | Bsr::_10MBPSHD
+            | Bsr::EXTENDED_STATUS
+            | Bsr::AUTONEG_ABLE
+            | Bsr::EXTENDED_CAPABILITIES;
+        let ext = ExtendedPhyStatus {
+            fd_1000base_x: false,
+            hd_1000base_x: false,
+            fd_1000base_t: true,
+            hd_1000base_t: true,
+        };
+
+        for speed in [
+            AdvancedPhySpeed::HalfDuplexBase1000T,
+            AdvancedPhySpeed::FullDuplexBase1000T,
+        ] {
+            let mut phy = bare(SimulatedPhy::new(0x0007_C0F0, bsr.into(), Some(ext)));
+            let bcr = phy.bcr().unwrap();
+
+            // The PHY supports 1000BASE-T, but only with autonegotiation
+            let status = phy.status().unwrap();
+            let ext = phy.extended_status().unwrap();
+            assert!(speed.is_supported(&status, ext.as_ref()));
+            assert_eq!(phy.force_link(speed), Err(ForceLinkError::Unsupported));
+            assert_eq!(phy.bcr().unwrap(), bcr);
+            assert!(bcr.autonegotiation());
+        }
+    }
 }
@@ -0,0 +1,343 @@
+//! Monitoring of the link state of a PHY.
+
+use core::marker::PhantomData;
+
+use super::{AdvancedPhySpeed, InterruptEvents, PhyWithSpeed};
+use crate::{Miim, PauseResolution};
+
+/// A change in the link state reported by a [`LinkMonitor`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkEvent {
+    /// The link went up
+    LinkUp {
+        /// The speed and duplex mode of the link
+        speed: AdvancedPhySpeed,
+        /// The resolved use of PAUSE frames.
+        ///
+        /// This is never used if autonegotiation is disabled.
+        pause: PauseResolution,
+    },
+    /// The link went down
+    LinkDown,
+    /// The link stayed up, but its speed or duplex mode changed
+    SpeedChanged {
+        /// The new speed and duplex mode of the link
+        speed: AdvancedPhySpeed,
+    },
+    /// The PHY reports that the link partner detected a fault
+    RemoteFault,
+}
+
+/// Tracks the link state of a PHY, and reports changes to it.
+///
+/// A new link state is only reported once it has been observed by
+/// a configurable amount of consecutive calls to [`LinkMonitor::poll`],
+/// which filters out a flapping link.
+#[derive(Debug)]
+pub struct LinkMonitor<M, P> {
+    phy: P,
+    link: Option<AdvancedPhySpeed>,
+    candidate: Option<AdvancedPhySpeed>,
+    candidate_count: u8,
+    debounce_up: u8,
+    debounce_down: u8,
+    remote_fault: bool,
+    /// An event that is reported by the next call to [`LinkMonitor::poll`]
+    /// or [`LinkMonitor::handle_interrupts`]
+    pending: Option<LinkEvent>,
+    _miim: PhantomData<M>,
+}
+
+impl<M, P> LinkMonitor<M, P> {
+    /// Create a new monitor for `phy`.
+    ///
+    /// The link is assumed to be down, and no debouncing is performed.
+    pub fn new(phy: P) -> Self {
+        Self {
+            phy,
+            link: None,
+            candidate: None,
+            candidate_count: 0,
+            debounce_up: 1,
+            debounce_down: 1,
+            remote_fault: false,
+            pending: None,
+            _miim: PhantomData,
+        }
+    }
+
+    /// Set the amount of consecutive polls in which the link must be observed
+    /// to be up (`up`) or down (`down`) before the change is reported.
+    ///
+    /// A link that comes up at a different speed is debounced using `up`.
+    /// Values of 0 are treated as 1.
+    pub fn set_debounce(&mut self, up: u8, down: u8) {
+        self.debounce_up = up.max(1);
+        self.debounce_down = down.max(1);
+    }
+
+    /// The last reported link speed, or `None` if the link is down
+    pub fn link(&self) -> Option<AdvancedPhySpeed> {
+        self.link
+    }
+
+    /// Get a reference to the monitored PHY
+    pub fn phy(&self) -> &P {
+        &self.phy
+    }
+
+    /// Get a mutable reference to the monitored PHY.
+    pub fn phy_mut(&mut self) -> &mut P {
+        &mut self.phy
+    }
+
+    /// Release the monitored PHY
+    pub fn release(self) -> P {
+        self.phy
+    }
+}
+
+impl<M, P> LinkMonitor<M, P>
+where
+    M: Miim,
+    P: PhyWithSpeed<M>,
+{
+    /// Read the link state of the PHY, and report a change if one occured.
+    ///
+    /// A remote fault is reported once when it is first detected, and
+    /// again only after the PHY has stopped reporting it. If a remote fault
+    /// and a link change are detected in the same call, the link change is
+    /// reported by the next call.
+    ///
+    /// An event that was queued by [`Self::handle_interrupts`] is returned
+    /// without reading the PHY.
+    pub fn poll(&mut self) -> Result<Option<LinkEvent>, M::Error> {
+        if let Some(event) = self.pending.take() {
+            return Ok(Some(event));
+        }
+
+        let bsr = self.phy.bsr()?;
+
+        let remote_fault = bsr.remote_fault();
+        if remote_fault != self.remote_fault {
+            self.remote_fault = remote_fault;
+            if remote_fault {
+                return Ok(Some(LinkEvent::RemoteFault));
+            }
+        }
+
+        self.update_link(bsr.phy_link_up())
+    }
+
+    /// Debounce the observed link state, and report a change if one occured.
+    fn update_link(&mut self, link_up: bool) -> Result<Option<LinkEvent>, M::Error> {
+        let observed = if link_up {
+            self.phy.get_link_speed()?
+        } else {
+            None
+        };
+
+        if observed == self.link {
+            self.candidate_count = 0;
+            return Ok(None);
+        }
+
+        if observed == self.candidate && self.candidate_count > 0 {
+            self.candidate_count = self.candidate_count.saturating_add(1);
+        } else {
+            self.candidate = observed;
+            self.candidate_count = 1;
+        }
+
+        let debounce = if observed.is_some() {
+            self.debounce_up
+        } else {
+            self.debounce_down
+        };
+
+        if self.candidate_count < debounce {
+            return Ok(None);
+        }
+
+        self.candidate_count = 0;
+        let previous = core::mem::replace(&mut self.link, observed);
+
+        let event = match (previous, observed) {
+            (_, None) => LinkEvent::LinkDown,
+            (None, Some(speed)) => LinkEvent::LinkUp {
+                speed,
+                pause: self.pause()?,
+            },
+            (Some(_), Some(speed)) => LinkEvent::SpeedChanged { speed },
+        };
+
+        Ok(Some(event))
+    }
+
+    /// Handle the `events` returned by
+    /// [`PhyWithInterrupts::read_and_clear_interrupts`](super::PhyWithInterrupts::read_and_clear_interrupts).
+    ///
+    /// If any of the events indicate that the link state may have changed,
+    /// this reads the link state like [`Self::poll`]. Note that if debouncing
+    /// is configured, the change is only reported by a later call to
+    /// [`Self::poll`].
+    ///
+    /// A remote fault is reported immediately. If a link change is detected
+    /// at the same time, it is queued, and reported by the next call to this
+    /// method or [`Self::poll`], as the PHY has already cleared the events.
+    pub fn handle_interrupts(
+        &mut self,
+        events: InterruptEvents,
+    ) -> Result<Option<LinkEvent>, M::Error> {
+        let link_events = InterruptEvents::LINK_UP
+            | InterruptEvents::LINK_DOWN
+            | InterruptEvents::AUTONEG_COMPLETE
+            | InterruptEvents::SPEED_CHANGE
+            | InterruptEvents::DUPLEX_CHANGE;
+
+        let mut remote_fault = events.contains(InterruptEvents::REMOTE_FAULT);
+        let mut link = None;
+        if events.intersects(link_events) {
+            let bsr = self.phy.bsr()?;
+            remote_fault |= bsr.remote_fault();
+            link = self.update_link(bsr.phy_link_up())?;
+        }
+
+        let new_fault = remote_fault && !self.remote_fault;
+        self.remote_fault |= remote_fault;
+
+        // Report one event and queue the next. There are never three: an
+        // event is only queued after a remote fault, which is then not new.
+        let mut reported = [
+            self.pending.take(),
+            new_fault.then_some(LinkEvent::RemoteFault),
+            link,
+        ]
+        .into_iter()
+        .flatten();
+        let event = reported.next();
+        self.pending = reported.next();
+        Ok(event)
+    }
+
+    fn pause(&mut self) -> Result<PauseResolution, M::Error> {
+        if !self.phy.bcr()?.autonegotiation() {
+            return Ok(PauseResolution::default());
+        }
+
+        Ok(self
+            .phy
+            .resolve_autonegotiation()?
+            .map(|result| result.pause)
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phy::BarePhy;
+    use crate::sim::{LinkPartner, SimChip, SimMiim, SimulatedPhy};
+    use crate::{AutoNegotiationAdvertisement, Pause, Phy};
+
+    const ADDR: u8 = 2;
+
+    fn partner() -> LinkPartner {
+        LinkPartner {
+            advertisement: AutoNegotiationAdvertisement {
+                hd_100base_tx: true,
+                fd_100base_tx: true,
+                ..Default::default()
+            },
+            autonegotiation: true,
+        }
+    }
+
+    fn sim(monitor: &mut LinkMonitor<SimMiim, BarePhy<SimMiim>>) -> &mut SimulatedPhy {
+        monitor.phy_mut().get_miim().phy_mut(ADDR).unwrap()
+    }
+
+    fn is_link_up(event: Option<LinkEvent>) -> bool {
+        matches!(
+            event,
+            Some(LinkEvent::LinkUp {
+                speed: AdvancedPhySpeed::FullDuplexBase100Tx,
+                ..
+            })
+        )
+    }
+
+    #[test]
+    fn debounce() {
+        let mut miim = SimMiim::<1>::new();
+        miim.add(ADDR, SimulatedPhy::for_chip(SimChip::Generic));
+        let phy = BarePhy::new(miim, ADDR, Pause::NoPause).unwrap();
+        let mut monitor = LinkMonitor::new(phy);
+        monitor.set_debounce(2, 3);
+
+        sim(&mut monitor).connect(partner());
+        assert_eq!(monitor.poll().unwrap(), None);
+        assert!(is_link_up(monitor.poll().unwrap()));
+        assert_eq!(monitor.link(), Some(AdvancedPhySpeed::FullDuplexBase100Tx));
+
+        // A link that flaps is not reported
+        sim(&mut monitor).disconnect();
+        assert_eq!(monitor.poll().unwrap(), None);
+        assert_eq!(monitor.poll().unwrap(), None);
+        sim(&mut monitor).connect(partner());
+        assert_eq!(monitor.poll().unwrap(), None);
+        assert_eq!(monitor.poll().unwrap(), None);
+        assert_eq!(monitor.poll().unwrap(), None);
+
+        sim(&mut monitor).disconnect();
+        assert_eq!(monitor.poll().unwrap(), None);
+        assert_eq!(monitor.poll().unwrap(), None);
+        assert_eq!(monitor.poll().unwrap(), Some(LinkEvent::LinkDown));
+        assert_eq!(monitor.link(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "ksz8081r")]
+    fn remote_fault_with_link_change() {
+        use crate::phy::{PhyWithInterrupts, KSZ8081R};
+
+        let mut miim = SimMiim::<1>::new();
+        miim.add(ADDR, SimulatedPhy::for_chip(SimChip::Ksz8081r));
+        let mut phy = KSZ8081R::new(miim, ADDR);
+        phy.enable_interrupts(
+            InterruptEvents::LINK_UP | InterruptEvents::LINK_DOWN | InterruptEvents::REMOTE_FAULT,
+        )
+        .unwrap();
+        let mut monitor = LinkMonitor::new(phy);
+
+        let sim = monitor.phy_mut().get_miim().phy_mut(ADDR).unwrap();
+        sim.connect(partner());
+        sim.signal_remote_fault();
+
+        let events = monitor.phy_mut().read_and_clear_interrupts().unwrap();
+        assert!(events.contains(InterruptEvents::LINK_UP | InterruptEvents::REMOTE_FAULT));
+
+        // The link change is queued, and reported without another interrupt
+        assert_eq!(
+            monitor.handle_interrupts(events).unwrap(),
+            Some(LinkEvent::RemoteFault)
+        );
+        assert!(is_link_up(
+            monitor.handle_interrupts(InterruptEvents::empty()).unwrap()
+        ));
+        assert_eq!(monitor.poll().unwrap(), None);
+
+        // The fault was cleared by the last poll, so a new one is reported
+        let sim = monitor.phy_mut().get_miim().phy_mut(ADDR).unwrap();
+        sim.signal_remote_fault();
+        sim.disconnect();
+        let events = monitor.phy_mut().read_and_clear_interrupts().unwrap();
+        assert_eq!(
+            monitor.handle_interrupts(events).unwrap(),
+            Some(LinkEvent::RemoteFault)
+        );
+        assert_eq!(monitor.poll().unwrap(), Some(LinkEvent::LinkDown));
+        assert_eq!(monitor.poll().unwrap(), None);
+    }
+}
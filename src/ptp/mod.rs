@@ -0,0 +1,145 @@
+mod servo;
+pub use servo::{PiServo, PiServoConfig, ServoAction};
+
+/// The amount of nanoseconds in a second
+pub const NANOS_PER_SECOND: u32 = 1_000_000_000;
+
+/// A time of a PTP clock
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PtpTime {
+    /// The seconds
+    pub seconds: u32,
+    /// The nanoseconds, which must be smaller than [`NANOS_PER_SECOND`]
+    pub nanoseconds: u32,
+}
+
+impl PtpTime {
+    /// Create a new time from `seconds` and `nanoseconds`.
+    ///
+    /// Returns `None` if `nanoseconds` is not smaller than [`NANOS_PER_SECOND`].
+    pub const fn new(seconds: u32, nanoseconds: u32) -> Option<Self> {
+        if nanoseconds < NANOS_PER_SECOND {
+            Some(Self {
+                seconds,
+                nanoseconds,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Create a new time from a total amount of nanoseconds.
+    ///
+    /// The seconds wrap around if they do not fit in 32 bits.
+    pub const fn from_nanos(nanos: u64) -> Self {
+        Self {
+            seconds: (nanos / NANOS_PER_SECOND as u64) as u32,
+            nanoseconds: (nanos % NANOS_PER_SECOND as u64) as u32,
+        }
+    }
+
+    /// The total amount of nanoseconds
+    pub const fn as_nanos(&self) -> u64 {
+        self.seconds as u64 * NANOS_PER_SECOND as u64 + self.nanoseconds as u64
+    }
+
+    /// The signed difference `self - other`, in nanoseconds
+    pub const fn nanos_since(&self, other: &Self) -> i64 {
+        self.as_nanos() as i64 - other.as_nanos() as i64
+    }
+}
+
+/// Trait for hardware support of Presicion Time Protocol (IEEE1588)
+pub trait PTP {
+    /// The error that can occur while accessing the PTP clock
+    type Error;
+
+    /// Enable PTP Clock
+    fn start_ptp(&mut self) -> Result<(), Self::Error>;
+    /// Disable PTP Clock
+    fn stop_ptp(&mut self) -> Result<(), Self::Error>;
+
+    /// Set the PTP clock to `time`
+    fn set_clock(&mut self, time: PtpTime) -> Result<(), Self::Error>;
+    /// Read the PTP clock
+    fn read_clock(&mut self) -> Result<PtpTime, Self::Error>;
+    /// Adjust the PTP clock by `offset` nanoseconds, which may be negative.
+    ///
+    /// Unlike reading the clock, adding `offset` and setting it, this does
+    /// not lose the time that passes between the read and the write.
+    fn step_clock(&mut self, offset: i64) -> Result<(), Self::Error>;
+    /// Reset PTP Clock
+    fn reset_clock(&mut self) -> Result<(), Self::Error>;
+
+    /// Check if PTP Clock is started
+    fn started(&mut self) -> Result<bool, Self::Error>;
+
+    /// Set rate control value
+    fn set_rate_control(&mut self, rate: u32) -> Result<(), Self::Error>;
+
+    /// The largest frequency adjustment, in parts per billion, that
+    /// the clock supports
+    fn max_frequency_adjustment(&self) -> u32;
+
+    /// Adjust the frequency of the clock by `ppb` parts per billion.
+    ///
+    /// A positive value makes the clock run faster. Values outside of
+    /// [`Self::max_frequency_adjustment`] are clamped.
+    fn adjust_frequency(&mut self, ppb: i32) -> Result<(), Self::Error>;
+
+    /// Adjust the frequency of the clock by `ppb` parts per billion for
+    /// `duration_ns` nanoseconds, after which the clock returns to the
+    /// frequency set by [`Self::adjust_frequency`].
+    ///
+    /// This can be used to correct a small offset without stepping the clock.
+    /// Values outside of [`Self::max_frequency_adjustment`] are clamped.
+    fn adjust_frequency_temporarily(
+        &mut self,
+        ppb: i32,
+        duration_ns: u32,
+    ) -> Result<(), Self::Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NS: u64 = NANOS_PER_SECOND as u64;
+
+    #[test]
+    fn from_nanos() {
+        assert_eq!(PtpTime::from_nanos(0), PtpTime::default());
+        assert_eq!(
+            PtpTime::from_nanos(NS - 1),
+            PtpTime::new(0, 999_999_999).unwrap()
+        );
+        assert_eq!(PtpTime::from_nanos(NS), PtpTime::new(1, 0).unwrap());
+        assert_eq!(PtpTime::from_nanos(3 * NS + 5), PtpTime::new(3, 5).unwrap());
+
+        // The seconds wrap around
+        let time = PtpTime::from_nanos((u32::MAX as u64 + 2) * NS + 7);
+        assert_eq!(time, PtpTime::new(1, 7).unwrap());
+    }
+
+    #[test]
+    fn nanos_since() {
+        let time = PtpTime::new(10, 0).unwrap();
+        let earlier = PtpTime::new(9, 999_999_999).unwrap();
+
+        assert_eq!(time.nanos_since(&time), 0);
+        assert_eq!(time.nanos_since(&earlier), 1);
+        assert_eq!(earlier.nanos_since(&time), -1);
+
+        let second_earlier = PtpTime::new(9, 0).unwrap();
+        assert_eq!(time.nanos_since(&second_earlier), NS as i64);
+        assert_eq!(second_earlier.nanos_since(&time), -(NS as i64));
+
+        // Across a second boundary
+        let before = PtpTime::new(4, 750_000_000).unwrap();
+        let after = PtpTime::new(5, 250_000_000).unwrap();
+        assert_eq!(after.nanos_since(&before), 500_000_000);
+        assert_eq!(before.nanos_since(&after), -500_000_000);
+        assert_eq!(PtpTime::from_nanos(before.as_nanos() + 500_000_000), after);
+    }
+}
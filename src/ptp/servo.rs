@@ -0,0 +1,300 @@
+//! A PI controller that disciplines a PTP clock.
+
+use super::PTP;
+
+/// The configuration of a [`PiServo`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PiServoConfig {
+    /// The proportional gain
+    pub kp: f32,
+    /// The integral gain
+    pub ki: f32,
+    /// The largest frequency adjustment that the servo applies, in parts
+    /// per billion.
+    ///
+    /// This is further limited by [`PTP::max_frequency_adjustment`] when
+    /// using [`PiServo::drive`].
+    pub max_ppb: u32,
+    /// Step the clock instead of adjusting its frequency if the absolute
+    /// offset is larger than this amount of nanoseconds.
+    ///
+    /// If this is `None`, the clock is never stepped after the first sample.
+    pub step_threshold_ns: Option<u64>,
+    /// Step the clock for the first sample, if the absolute offset is larger
+    /// than this amount of nanoseconds.
+    ///
+    /// If this is `None`, the clock is not stepped for the first sample.
+    pub first_step_threshold_ns: Option<u64>,
+}
+
+impl Default for PiServoConfig {
+    fn default() -> Self {
+        Self {
+            kp: 0.7,
+            ki: 0.3,
+            max_ppb: 900_000,
+            step_threshold_ns: None,
+            first_step_threshold_ns: Some(20_000),
+        }
+    }
+}
+
+/// The correction applied by a [`PiServo`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServoAction {
+    /// Step the clock by `offset_ns` nanoseconds
+    Step {
+        /// The signed amount of nanoseconds to add to the clock
+        offset_ns: i64,
+    },
+    /// Adjust the frequency of the clock
+    Adjust {
+        /// The frequency adjustment, in parts per billion
+        ppb: i32,
+    },
+}
+
+/// A proportional-integral clock servo.
+///
+/// The servo consumes measurements of the offset of the local clock from
+/// a reference clock, taken at a fixed interval, and computes the frequency
+/// adjustment that drives that offset to zero. The gains in
+/// [`PiServoConfig`] are per sample: with one sample per second, an
+/// offset of 1 ns results in a proportional term of `kp` ppb.
+#[derive(Clone, Debug)]
+pub struct PiServo {
+    config: PiServoConfig,
+    /// The integral term, in ppb
+    drift: f32,
+    first_sample: bool,
+}
+
+impl PiServo {
+    /// Create a new servo with `config`
+    pub fn new(config: PiServoConfig) -> Self {
+        Self {
+            config,
+            drift: 0.0,
+            first_sample: true,
+        }
+    }
+
+    /// The configuration of the servo
+    pub fn config(&self) -> &PiServoConfig {
+        &self.config
+    }
+
+    /// The integral term, i.e. the estimated frequency error of the local
+    /// clock in parts per billion.
+    pub fn drift_ppb(&self) -> f32 {
+        self.drift
+    }
+
+    /// Reset the servo, as if no samples were consumed.
+    pub fn reset(&mut self) {
+        self.drift = 0.0;
+        self.first_sample = true;
+    }
+
+    /// Consume a measurement of `offset_ns`, the local time minus the
+    /// reference time, and compute the correction to apply to the clock.
+    pub fn sample(&mut self, offset_ns: i64) -> ServoAction {
+        self.sample_with_limit(offset_ns, self.config.max_ppb)
+    }
+
+    /// [`Self::sample`], with the frequency adjustment limited to `max_ppb`
+    /// instead of the configured maximum.
+    fn sample_with_limit(&mut self, offset_ns: i64, max_ppb: u32) -> ServoAction {
+        let threshold = if self.first_sample {
+            self.config.first_step_threshold_ns
+        } else {
+            self.config.step_threshold_ns
+        };
+        self.first_sample = false;
+
+        if matches!(threshold, Some(threshold) if offset_ns.unsigned_abs() > threshold) {
+            return ServoAction::Step {
+                offset_ns: -offset_ns,
+            };
+        }
+
+        let max_ppb = max_ppb as f32;
+        let offset = offset_ns as f32;
+
+        self.drift = (self.drift + self.config.ki * offset).clamp(-max_ppb, max_ppb);
+        let ppb = (self.config.kp * offset + self.drift).clamp(-max_ppb, max_ppb);
+
+        // The local clock is ahead if the offset is positive, so it must
+        // run slower
+        ServoAction::Adjust { ppb: -(ppb as i32) }
+    }
+
+    /// Consume a measurement of `offset_ns` (see [`Self::sample`]), and
+    /// apply the resulting correction to `clock`.
+    pub fn drive<P: PTP>(
+        &mut self,
+        clock: &mut P,
+        offset_ns: i64,
+    ) -> Result<ServoAction, P::Error> {
+        let max_ppb = self.config.max_ppb.min(clock.max_frequency_adjustment());
+
+        let action = self.sample_with_limit(offset_ns, max_ppb);
+        match action {
+            ServoAction::Step { offset_ns } => clock.step_clock(offset_ns)?,
+            ServoAction::Adjust { ppb } => clock.adjust_frequency(ppb)?,
+        }
+        Ok(action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PtpTime;
+
+    /// Gains that are exact in binary, so that the results are integers
+    const CONFIG: PiServoConfig = PiServoConfig {
+        kp: 0.5,
+        ki: 0.25,
+        max_ppb: 1_000,
+        step_threshold_ns: None,
+        first_step_threshold_ns: Some(20_000),
+    };
+
+    /// A clock that records the last correction
+    struct Clock {
+        max_ppb: u32,
+        last: Option<ServoAction>,
+    }
+
+    impl PTP for Clock {
+        type Error = ();
+
+        fn start_ptp(&mut self) -> Result<(), ()> {
+            unreachable!()
+        }
+
+        fn stop_ptp(&mut self) -> Result<(), ()> {
+            unreachable!()
+        }
+
+        fn set_clock(&mut self, _: PtpTime) -> Result<(), ()> {
+            unreachable!()
+        }
+
+        fn read_clock(&mut self) -> Result<PtpTime, ()> {
+            unreachable!()
+        }
+
+        fn step_clock(&mut self, offset_ns: i64) -> Result<(), ()> {
+            self.last = Some(ServoAction::Step { offset_ns });
+            Ok(())
+        }
+
+        fn reset_clock(&mut self) -> Result<(), ()> {
+            unreachable!()
+        }
+
+        fn started(&mut self) -> Result<bool, ()> {
+            unreachable!()
+        }
+
+        fn set_rate_control(&mut self, _: u32) -> Result<(), ()> {
+            unreachable!()
+        }
+
+        fn max_frequency_adjustment(&self) -> u32 {
+            self.max_ppb
+        }
+
+        fn adjust_frequency(&mut self, ppb: i32) -> Result<(), ()> {
+            self.last = Some(ServoAction::Adjust { ppb });
+            Ok(())
+        }
+
+        fn adjust_frequency_temporarily(&mut self, _: i32, _: u32) -> Result<(), ()> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn steps_for_a_large_first_offset() {
+        let mut servo = PiServo::new(CONFIG);
+        assert_eq!(
+            servo.sample(30_000),
+            ServoAction::Step { offset_ns: -30_000 }
+        );
+        assert_eq!(servo.drift_ppb(), 0.0);
+
+        // Later offsets are never stepped without a step threshold
+        assert_eq!(servo.sample(30_000), ServoAction::Adjust { ppb: -1_000 });
+
+        // A reset makes the next sample the first one again
+        servo.reset();
+        assert_eq!(
+            servo.sample(-30_000),
+            ServoAction::Step { offset_ns: 30_000 }
+        );
+    }
+
+    #[test]
+    fn steps_above_the_threshold() {
+        let mut servo = PiServo::new(PiServoConfig {
+            step_threshold_ns: Some(500),
+            ..CONFIG
+        });
+        assert_eq!(servo.sample(400), ServoAction::Adjust { ppb: -300 });
+        assert_eq!(servo.sample(500), ServoAction::Adjust { ppb: -475 });
+        assert_eq!(servo.sample(-501), ServoAction::Step { offset_ns: 501 });
+    }
+
+    #[test]
+    fn adjusts_against_the_offset() {
+        let mut servo = PiServo::new(CONFIG);
+
+        // A local clock that is ahead must run slower
+        assert_eq!(servo.sample(100), ServoAction::Adjust { ppb: -75 });
+        assert_eq!(servo.drift_ppb(), 25.0);
+
+        // The integral term remains when the offset is 0
+        assert_eq!(servo.sample(0), ServoAction::Adjust { ppb: -25 });
+        assert_eq!(servo.sample(-200), ServoAction::Adjust { ppb: 125 });
+        assert_eq!(servo.drift_ppb(), -25.0);
+    }
+
+    #[test]
+    fn clamps_to_max_ppb() {
+        let mut servo = PiServo::new(CONFIG);
+        assert_eq!(servo.sample(10_000), ServoAction::Adjust { ppb: -1_000 });
+        assert_eq!(servo.drift_ppb(), 1_000.0);
+        assert_eq!(servo.sample(-10_000), ServoAction::Adjust { ppb: 1_000 });
+    }
+
+    #[test]
+    fn drive_limits_to_the_clock() {
+        let mut servo = PiServo::new(CONFIG);
+        let mut clock = Clock {
+            max_ppb: 400,
+            last: None,
+        };
+
+        let action = servo.drive(&mut clock, 10_000).unwrap();
+        assert_eq!(action, ServoAction::Adjust { ppb: -400 });
+        assert_eq!(clock.last, Some(action));
+        assert_eq!(servo.drift_ppb(), 400.0);
+
+        // The configuration is not changed by the limit of the clock
+        assert_eq!(servo.config(), &CONFIG);
+        assert_eq!(servo.sample(10_000), ServoAction::Adjust { ppb: -1_000 });
+
+        let action = servo.drive(&mut clock, -100_000).unwrap();
+        assert_eq!(action, ServoAction::Adjust { ppb: 400 });
+
+        servo.reset();
+        let action = servo.drive(&mut clock, -100_000).unwrap();
+        assert_eq!(action, ServoAction::Step { offset_ns: 100_000 });
+        assert_eq!(clock.last, Some(action));
+    }
+}
@@ -90,6 +90,27 @@ mod regs {
             const LINK_PARTNER_AUTONEG_ABLE = (1 << 0);
         }
 
+        /// Register 9, the 1000BASE-T Control Register
+        pub struct GbCtrl: u16 {
+            const TEST_MODE_MASK = (0b111 << 13);
+            const MASTER_SLAVE_MANUAL = (1 << 12);
+            const MASTER_SLAVE_MASTER = (1 << 11);
+            const MULTI_PORT = (1 << 10);
+            const _1000BASETFD = (1 << 9);
+            const _1000BASETHD = (1 << 8);
+        }
+
+        /// Register 10, the 1000BASE-T Status Register
+        pub struct GbStatus: u16 {
+            const MASTER_SLAVE_FAULT = (1 << 15);
+            const MASTER_SLAVE_RESOLVED_MASTER = (1 << 14);
+            const LOCAL_RECEIVER_OK = (1 << 13);
+            const REMOTE_RECEIVER_OK = (1 << 12);
+            const PARTNER_1000BASETFD = (1 << 11);
+            const PARTNER_1000BASETHD = (1 << 10);
+            const IDLE_ERROR_COUNT_MASK = (0xFF);
+        }
+
         /// Register 15, the Extended Status Register
         pub struct Esr: u16 {
             const _1000BASEXFD = (1 << 15);
@@ -205,6 +226,23 @@ impl Bsr {
     /// The register address of the BSR
     pub const ADDRESS: u8 = 1;
 
+    /// The bits of the BSR that describe the abilities of the PHY. These
+    /// never change.
+    pub const ABILITIES: Bsr = Bsr::from_bits_truncate(
+        Bsr::_100BASET4.bits()
+            | Bsr::_100BASEXFD.bits()
+            | Bsr::_100BASEXHD.bits()
+            | Bsr::_10MPBSFD.bits()
+            | Bsr::_10MBPSHD.bits()
+            | Bsr::_100BASET2FD.bits()
+            | Bsr::_100BASET2HD.bits()
+            | Bsr::EXTENDED_STATUS.bits()
+            | Bsr::UNIDRECTIONAL.bits()
+            | Bsr::MF_PREAMBLE_SUPPRESSION.bits()
+            | Bsr::AUTONEG_ABLE.bits()
+            | Bsr::EXTENDED_CAPABILITIES.bits(),
+    );
+
     /// Check if autonegotiation has completed
     pub fn autoneg_completed(&self) -> bool {
         self.contains(Bsr::AUTONEG_COMPLETE)
@@ -214,6 +252,11 @@ impl Bsr {
     pub fn phy_link_up(&self) -> bool {
         self.contains(Bsr::LINK_STATUS)
     }
+
+    /// Check if a remote fault was detected
+    pub fn remote_fault(&self) -> bool {
+        self.contains(Bsr::REMOTE_FAULT)
+    }
 }
 
 impl AutoNegCap {
@@ -270,6 +313,38 @@ impl NextPage {
     pub const TRANSMIT_ADDR: u8 = 7;
     /// The mask used for masking out the data portion of the register.
     pub const DATA_MASK: u16 = 0x7FF;
+    /// The address of the link partner next page register, if the PHY has one.
+    pub const RECEIVE_ADDR: u8 = 8;
+    /// The message code of a Null Message page.
+    pub const NULL_MESSAGE_CODE: u16 = 0x001;
+
+    /// Create a message page containing `code`
+    pub fn message(code: u16) -> Self {
+        let mut page = Self::MESSAGE_PAGE;
+        page.set_data(code);
+        page
+    }
+
+    /// Create an unformatted page containing `data`
+    pub fn unformatted(data: u16) -> Self {
+        let mut page = Self::empty();
+        page.set_data(data);
+        page
+    }
+
+    /// Create a Null Message page, which is sent when a device has no more
+    /// pages to send while its link partner does.
+    pub fn null_message() -> Self {
+        Self::message(Self::NULL_MESSAGE_CODE)
+    }
+
+    impl_flag!(
+        "Set the next page flag, indicating that more pages follow.",
+        set_next_page,
+        "Check if the next page flag is set.",
+        next_page,
+        Self::NEXT_PAGE
+    );
 
     impl_flag!(
         "Set the ACK flag.",
@@ -303,11 +378,126 @@ impl NextPage {
     /// Create a new next page, using the provided Auto-Negotiation Expansion register to
     /// determine the location of the next page, falling back to `default_next_page` if none
     /// is available.
-    pub fn new<M: Miim, P: Phy<M>>(ane: Ane, default_next_page: u8, phy: &mut P) -> Self {
+    pub fn new<M: Miim, P: Phy<M>>(
+        ane: Ane,
+        default_next_page: u8,
+        phy: &mut P,
+    ) -> Result<Self, M::Error> {
         let next_page = ane.next_page_location(default_next_page);
-        let next_page = phy.read(next_page);
+        let next_page = phy.read(next_page)?;
+
+        Ok(unsafe { Self::from_bits_unchecked(next_page) })
+    }
+}
+
+/// The MASTER-SLAVE configuration of a 1000BASE-T PHY
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MasterSlaveConfig {
+    /// MASTER-SLAVE is resolved during autonegotiation. If `prefer_master`
+    /// is set, the PHY advertises itself as a multi-port device, which
+    /// makes it more likely to be resolved as MASTER.
+    Automatic {
+        /// Advertise a multi-port device
+        prefer_master: bool,
+    },
+    /// The PHY is manually configured as MASTER
+    Master,
+    /// The PHY is manually configured as SLAVE
+    Slave,
+}
 
-        unsafe { Self::from_bits_unchecked(next_page) }
+impl GbCtrl {
+    /// The address of the 1000BASE-T Control Register.
+    pub const ADDRESS: u8 = 9;
+
+    impl_flag!(
+        "Advertise 1000BASE-T Full Duplex.",
+        set_advertise_1000base_t_fd,
+        "Check if 1000BASE-T Full Duplex is advertised.",
+        advertise_1000base_t_fd,
+        Self::_1000BASETFD
+    );
+    impl_flag!(
+        "Advertise 1000BASE-T Half Duplex.",
+        set_advertise_1000base_t_hd,
+        "Check if 1000BASE-T Half Duplex is advertised.",
+        advertise_1000base_t_hd,
+        Self::_1000BASETHD
+    );
+
+    /// Get the MASTER-SLAVE configuration
+    pub fn master_slave_config(&self) -> MasterSlaveConfig {
+        if self.contains(Self::MASTER_SLAVE_MANUAL) {
+            if self.contains(Self::MASTER_SLAVE_MASTER) {
+                MasterSlaveConfig::Master
+            } else {
+                MasterSlaveConfig::Slave
+            }
+        } else {
+            MasterSlaveConfig::Automatic {
+                prefer_master: self.contains(Self::MULTI_PORT),
+            }
+        }
+    }
+
+    /// Set the MASTER-SLAVE configuration
+    pub fn set_master_slave_config(&mut self, config: MasterSlaveConfig) -> &mut Self {
+        self.remove(Self::MASTER_SLAVE_MANUAL | Self::MASTER_SLAVE_MASTER | Self::MULTI_PORT);
+        match config {
+            MasterSlaveConfig::Automatic { prefer_master } => {
+                self.set(Self::MULTI_PORT, prefer_master);
+            }
+            MasterSlaveConfig::Master => {
+                self.insert(Self::MASTER_SLAVE_MANUAL | Self::MASTER_SLAVE_MASTER);
+            }
+            MasterSlaveConfig::Slave => {
+                self.insert(Self::MASTER_SLAVE_MANUAL);
+            }
+        }
+        self
+    }
+}
+
+impl GbStatus {
+    /// The address of the 1000BASE-T Status Register.
+    pub const ADDRESS: u8 = 10;
+
+    /// A MASTER-SLAVE configuration fault was detected
+    pub fn master_slave_fault(&self) -> bool {
+        self.contains(Self::MASTER_SLAVE_FAULT)
+    }
+
+    /// The PHY was resolved as MASTER. Otherwise, it was resolved as SLAVE.
+    pub fn resolved_master(&self) -> bool {
+        self.contains(Self::MASTER_SLAVE_RESOLVED_MASTER)
+    }
+
+    /// The local receiver status is OK
+    pub fn local_receiver_ok(&self) -> bool {
+        self.contains(Self::LOCAL_RECEIVER_OK)
+    }
+
+    /// The remote receiver status is OK
+    pub fn remote_receiver_ok(&self) -> bool {
+        self.contains(Self::REMOTE_RECEIVER_OK)
+    }
+
+    /// The link partner is capable of 1000BASE-T Full Duplex
+    pub fn partner_1000base_t_fd(&self) -> bool {
+        self.contains(Self::PARTNER_1000BASETFD)
+    }
+
+    /// The link partner is capable of 1000BASE-T Half Duplex
+    pub fn partner_1000base_t_hd(&self) -> bool {
+        self.contains(Self::PARTNER_1000BASETHD)
+    }
+
+    /// The amount of idle errors since this register was last read.
+    ///
+    /// This counter saturates at 0xFF.
+    pub fn idle_error_count(&self) -> u8 {
+        (self.bits() & Self::IDLE_ERROR_COUNT_MASK.bits()) as u8
     }
 }
 
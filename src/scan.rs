@@ -0,0 +1,164 @@
+//! Enumeration of the PHYs present on an MDIO bus.
+
+use crate::{Miim, PhyIdent};
+
+/// The highest address a PHY can have on an MDIO bus.
+pub const MAX_PHY_ADDRESS: u8 = 31;
+
+/// A PHY that responded while scanning an MDIO bus.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FoundPhy {
+    /// The address at which the PHY responded
+    pub address: u8,
+    /// The identifier read from registers 2 and 3 of the PHY
+    pub ident: PhyIdent,
+}
+
+impl FoundPhy {
+    /// The OUI of the PHY
+    pub fn oui(&self) -> u32 {
+        self.ident.oui()
+    }
+
+    /// The model number of the PHY
+    pub fn model_number(&self) -> u8 {
+        self.ident.model_number()
+    }
+
+    /// The revision number of the PHY
+    pub fn revision(&self) -> u8 {
+        self.ident.revision()
+    }
+}
+
+/// The PHYs found by [`scan_bus`], ordered by address.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScanResult {
+    phys: [Option<FoundPhy>; MAX_PHY_ADDRESS as usize + 1],
+    len: usize,
+}
+
+impl ScanResult {
+    /// The amount of PHYs that were found
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if no PHYs were found
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the `index`th PHY that was found
+    pub fn get(&self, index: usize) -> Option<&FoundPhy> {
+        self.phys[..self.len].get(index)?.as_ref()
+    }
+
+    /// Get the PHY found at `address`, if any
+    pub fn at_address(&self, address: u8) -> Option<&FoundPhy> {
+        self.iter().find(|phy| phy.address == address)
+    }
+
+    /// Iterate over all PHYs that were found
+    pub fn iter(&self) -> impl Iterator<Item = &FoundPhy> {
+        self.phys[..self.len].iter().flatten()
+    }
+
+    fn push(&mut self, phy: FoundPhy) {
+        self.phys[self.len] = Some(phy);
+        self.len += 1;
+    }
+}
+
+/// Probe all addresses on the bus behind `miim`, and return the PHYs that respond.
+///
+/// An address is considered empty if its identifier registers read as all-ones
+/// (no PHY is driving MDIO) or all-zeros.
+///
+/// The scan stops at the first error returned by `miim`. Some MDIO controllers
+/// report an error for an address at which no PHY responds; it is up to the
+/// caller to recognize such an error and, if needed, scan the remaining
+/// addresses itself.
+pub fn scan_bus<M: Miim>(miim: &mut M) -> Result<ScanResult, M::Error> {
+    let mut result = ScanResult {
+        phys: [None; MAX_PHY_ADDRESS as usize + 1],
+        len: 0,
+    };
+
+    for address in 0..=MAX_PHY_ADDRESS {
+        let msb = miim.read(address, 2)?;
+        let lsb = miim.read(address, 3)?;
+
+        let absent = (msb == 0xFFFF && lsb == 0xFFFF) || (msb == 0 && lsb == 0);
+        if !absent {
+            result.push(FoundPhy {
+                address,
+                ident: PhyIdent::new(msb, lsb),
+            });
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registers::Bsr;
+    use crate::sim::{SimChip, SimMiim, SimulatedPhy};
+
+    /// A bus on which reading from `failing` returns an error
+    struct FailingMiim {
+        bus: SimMiim<2>,
+        failing: u8,
+    }
+
+    impl Miim for FailingMiim {
+        type Error = u8;
+
+        fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error> {
+            if phy == self.failing {
+                return Err(phy);
+            }
+            Ok(self.bus.read(phy, reg).unwrap())
+        }
+
+        fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error> {
+            self.bus.write(phy, reg, data).map_err(|_| phy)
+        }
+    }
+
+    fn bus() -> SimMiim<2> {
+        let mut bus = SimMiim::new();
+        bus.add(3, SimulatedPhy::for_chip(SimChip::Generic));
+        bus.add(
+            17,
+            SimulatedPhy::new(0x0007_C0F1, Bsr::empty().into(), None),
+        );
+        bus
+    }
+
+    #[test]
+    fn finds_all_phys() {
+        let result = scan_bus(&mut bus()).unwrap();
+
+        // The generic PHY has an identifier of 0, and is not detected
+        assert_eq!(result.len(), 1);
+        let phy = result.get(0).unwrap();
+        assert_eq!(phy.address, 17);
+        assert_eq!(phy.ident, PhyIdent::new(0x0007, 0xC0F1));
+        assert_eq!(result.at_address(17), Some(phy));
+        assert_eq!(result.at_address(3), None);
+    }
+
+    #[test]
+    fn propagates_bus_errors() {
+        let mut miim = FailingMiim {
+            bus: bus(),
+            failing: 5,
+        };
+        assert_eq!(scan_bus(&mut miim), Err(5));
+    }
+}
@@ -0,0 +1,533 @@
+//! Models of the vendor-specific registers of the bundled PHY drivers.
+
+use super::SimulatedPhy;
+#[cfg(any(
+    feature = "lan8720a",
+    feature = "lan8742a",
+    feature = "ksz8081r",
+    feature = "dp83848",
+    feature = "dp83640"
+))]
+use crate::phy::bare;
+use crate::PhyStatus;
+#[cfg(any(
+    feature = "lan8720a",
+    feature = "lan8742a",
+    feature = "ksz8081r",
+    feature = "dp83848",
+    feature = "dp83640"
+))]
+use crate::{Duplex, LinkSpeed};
+
+#[cfg(feature = "ksz8081r")]
+use crate::registers::Bcr;
+
+// The register layouts below are taken from the datasheets rather than from
+// the drivers, so that the models catch a driver using the wrong register or bit.
+
+/// The LAN87xxA interrupt source flag register
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_INTERRUPT_SOURCE_REG: u8 = 29;
+/// The LAN87xxA interrupt mask register, with the same layout as the source register
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_INTERRUPT_MASK_REG: u8 = 30;
+/// The LAN87xxA PHY special control/status register
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_SSR_REG: u8 = 31;
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_SSR_AUTODONE: u16 = 1 << 12;
+/// The speed indication field (bits 4:2) of the SSR: `001` is 10BASE-T
+/// half duplex, `010` 100BASE-TX half duplex, `101` 10BASE-T full duplex
+/// and `110` 100BASE-TX full duplex.
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_SSR_SPEED: u16 = 0b111 << 2;
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_SSR_SPEED_10: u16 = 0b001 << 2;
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_SSR_SPEED_100: u16 = 0b010 << 2;
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_SSR_SPEED_FULL_DUPLEX: u16 = 0b100 << 2;
+/// All read-only status bits of the SSR
+#[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+const LAN87XXA_SSR_STATUS: u16 = LAN87XXA_SSR_AUTODONE | LAN87XXA_SSR_SPEED;
+
+/// The KSZ8081R interrupt control/status register. The status bits are
+/// in the low byte, the enable bits in the high byte.
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_INTERRUPT_REG: u8 = 0x1B;
+/// The KSZ8081R PHY control 1 register
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_REG: u8 = 0x1E;
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_ENABLE_PAUSE: u16 = 1 << 9;
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_LINK_STATUS: u16 = 1 << 8;
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_ENERGY_DETECT: u16 = 1 << 4;
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_PHY_ISOLATE: u16 = 1 << 3;
+/// The operation mode indication field (bits 2:0) of PHY control 1:
+/// `001` is 10BASE-T half duplex, `010` 100BASE-TX half duplex, `101`
+/// 10BASE-T full duplex and `110` 100BASE-TX full duplex.
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_MODE_10: u16 = 0b001;
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_MODE_100: u16 = 0b010;
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_MODE_FULL_DUPLEX: u16 = 0b100;
+/// All read-only status bits of PHY control 1: bits 9:7 and 5:0
+#[cfg(feature = "ksz8081r")]
+const KSZ8081R_PHY_CONTROL1_STATUS: u16 = 0x03BF;
+
+/// The PHY status register of the DP83xxx
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_PHYSTS_REG: u8 = 0x10;
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_PHYSTS_LINK_STATUS: u16 = 1 << 0;
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_PHYSTS_SPEED_10: u16 = 1 << 1;
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_PHYSTS_DUPLEX: u16 = 1 << 2;
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_PHYSTS_AUTONEG_COMPLETE: u16 = 1 << 4;
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_PHYSTS_MII_INTERRUPT: u16 = 1 << 7;
+/// The MII interrupt control register of the DP83xxx
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_MICR_REG: u8 = 0x11;
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_MICR_INTEN: u16 = 1 << 1;
+/// The MII interrupt status and event control register of the DP83xxx.
+/// The status bits are in the high byte, the enable bits of the same
+/// events in the low byte.
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_MISR_REG: u8 = 0x12;
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_MISR_ENABLES: u16 = 0x00FF;
+/// The page select register of the DP83xxx
+#[cfg(any(feature = "dp83848", feature = "dp83640"))]
+const DP83XXX_PAGE_REG: u8 = 0x13;
+/// The first register that is selected through [`DP83XXX_PAGE_REG`]
+#[cfg(feature = "dp83640")]
+const DP83640_FIRST_PAGED_REG: u8 = 0x14;
+
+/// The chip whose vendor-specific registers are modelled by a [`SimulatedPhy`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimChip {
+    /// A PHY that only implements the IEEE 802.3 registers. All vendor-specific
+    /// registers behave as plain read/write storage.
+    Generic,
+    /// The SMSC LAN8720A: the interrupt source and mask registers (29, 30)
+    /// and the special control/status register (31).
+    #[cfg(feature = "lan8720a")]
+    Lan8720a,
+    /// The SMSC LAN8742A: the same registers as the LAN8720A, and MMD access
+    /// through registers 13 and 14.
+    #[cfg(feature = "lan8742a")]
+    Lan8742a,
+    /// The Microchip KSZ8081R: the interrupt control/status register (0x1B)
+    /// and PHY control 1 register (0x1E).
+    #[cfg(feature = "ksz8081r")]
+    Ksz8081r,
+    /// The TI DP83848: the PHY status register (0x10), and the MII interrupt
+    /// control and status registers (0x11, 0x12).
+    #[cfg(feature = "dp83848")]
+    Dp83848,
+    /// The TI DP83640: the same registers as the DP83848, and the page
+    /// select register (0x13) which selects the page of registers 0x14 to 0x1F.
+    #[cfg(feature = "dp83640")]
+    Dp83640,
+}
+
+/// An event that may be signalled by the interrupt registers of a chip.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum SimEvent {
+    LinkUp,
+    LinkDown,
+    SpeedChange,
+    DuplexChange,
+    AutonegComplete,
+    PageReceived,
+    LinkPartnerAck,
+    ParallelDetectFault,
+    RemoteFault,
+    Jabber,
+    EnergyDetect,
+}
+
+/// The status of all 10 and 100 Mbps modes, without 100BASE-T4
+#[cfg(any(
+    feature = "lan8720a",
+    feature = "lan8742a",
+    feature = "ksz8081r",
+    feature = "dp83848",
+    feature = "dp83640"
+))]
+const fn status_10_100(preamble_suppression: bool) -> PhyStatus {
+    PhyStatus {
+        base100_t4: false,
+        fd_100base_x: true,
+        hd_100base_x: true,
+        fd_10mbps: true,
+        hd_10mbps: true,
+        extended_status: false,
+        unidirectional: false,
+        preamble_suppression,
+        autonegotiation: true,
+        extended_caps: true,
+    }
+}
+
+impl SimChip {
+    /// The PHY identifier of the chip, or `None` for [`SimChip::Generic`]
+    pub fn ident(&self) -> Option<u32> {
+        match self {
+            Self::Generic => None,
+            #[cfg(feature = "lan8720a")]
+            Self::Lan8720a => Some(bare::LAN8720A_IDENT),
+            #[cfg(feature = "lan8742a")]
+            Self::Lan8742a => Some(bare::LAN8742A_IDENT),
+            #[cfg(feature = "ksz8081r")]
+            Self::Ksz8081r => Some(bare::KSZ8081R_IDENT),
+            #[cfg(feature = "dp83848")]
+            Self::Dp83848 => Some(bare::DP83848_IDENT),
+            #[cfg(feature = "dp83640")]
+            Self::Dp83640 => Some(bare::DP83640_IDENT),
+        }
+    }
+
+    /// The contents of the BSR of the chip, or `None` for [`SimChip::Generic`]
+    pub fn status(&self) -> Option<PhyStatus> {
+        match self {
+            Self::Generic => None,
+            #[cfg(feature = "lan8720a")]
+            Self::Lan8720a => Some(status_10_100(false)),
+            #[cfg(feature = "lan8742a")]
+            Self::Lan8742a => Some(status_10_100(false)),
+            #[cfg(feature = "ksz8081r")]
+            Self::Ksz8081r => Some(status_10_100(true)),
+            #[cfg(feature = "dp83848")]
+            Self::Dp83848 => Some(status_10_100(true)),
+            #[cfg(feature = "dp83640")]
+            Self::Dp83640 => Some(status_10_100(true)),
+        }
+    }
+
+    /// Whether the chip provides MMD access through registers 13 and 14
+    pub fn has_mmd(&self) -> bool {
+        match self {
+            #[cfg(feature = "lan8742a")]
+            Self::Lan8742a => true,
+            _ => false,
+        }
+    }
+
+    #[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+    fn is_lan87xxa(&self) -> bool {
+        match self {
+            #[cfg(feature = "lan8720a")]
+            Self::Lan8720a => true,
+            #[cfg(feature = "lan8742a")]
+            Self::Lan8742a => true,
+            _ => false,
+        }
+    }
+
+    #[cfg(any(feature = "dp83848", feature = "dp83640"))]
+    fn is_dp83xxx(&self) -> bool {
+        match self {
+            #[cfg(feature = "dp83848")]
+            Self::Dp83848 => true,
+            #[cfg(feature = "dp83640")]
+            Self::Dp83640 => true,
+            _ => false,
+        }
+    }
+
+    /// The bits in the interrupt status of the chip that `event` sets.
+    #[allow(unused_variables)]
+    pub(super) fn interrupt_bits(&self, event: SimEvent) -> u16 {
+        #[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+        if self.is_lan87xxa() {
+            let bit = match event {
+                SimEvent::PageReceived => 1,
+                SimEvent::ParallelDetectFault => 2,
+                SimEvent::LinkPartnerAck => 3,
+                SimEvent::LinkDown => 4,
+                SimEvent::RemoteFault => 5,
+                SimEvent::AutonegComplete => 6,
+                SimEvent::EnergyDetect => 7,
+                _ => return 0,
+            };
+            return 1 << bit;
+        }
+
+        #[cfg(feature = "ksz8081r")]
+        if *self == Self::Ksz8081r {
+            let bit = match event {
+                SimEvent::LinkUp => 0,
+                SimEvent::RemoteFault => 1,
+                SimEvent::LinkDown => 2,
+                SimEvent::LinkPartnerAck => 3,
+                SimEvent::ParallelDetectFault => 4,
+                SimEvent::PageReceived => 5,
+                SimEvent::Jabber => 7,
+                _ => return 0,
+            };
+            return 1 << bit;
+        }
+
+        #[cfg(any(feature = "dp83848", feature = "dp83640"))]
+        if self.is_dp83xxx() {
+            let bit = match event {
+                SimEvent::LinkUp | SimEvent::LinkDown => 13,
+                SimEvent::SpeedChange => 12,
+                SimEvent::DuplexChange => 11,
+                SimEvent::AutonegComplete => 10,
+                SimEvent::EnergyDetect => 14,
+                _ => return 0,
+            };
+            return 1 << bit;
+        }
+
+        0
+    }
+
+    /// Check if the interrupt output of the chip is asserted
+    pub(super) fn interrupt_asserted(&self, phy: &SimulatedPhy) -> bool {
+        #[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+        if self.is_lan87xxa() {
+            return phy.interrupt_status & phy.regs[LAN87XXA_INTERRUPT_MASK_REG as usize] != 0;
+        }
+
+        #[cfg(feature = "ksz8081r")]
+        if *self == Self::Ksz8081r {
+            return phy.interrupt_status & phy.regs[KSZ8081R_INTERRUPT_REG as usize] >> 8 != 0;
+        }
+
+        #[cfg(any(feature = "dp83848", feature = "dp83640"))]
+        if self.is_dp83xxx() {
+            let inten = phy.regs[DP83XXX_MICR_REG as usize] & DP83XXX_MICR_INTEN != 0;
+            let enables = phy.regs[DP83XXX_MISR_REG as usize] & DP83XXX_MISR_ENABLES;
+            return inten && phy.interrupt_status & (enables << 8) != 0;
+        }
+
+        let _ = phy;
+        false
+    }
+
+    /// Read the vendor-specific register `reg`, or return `None` if
+    /// `reg` is a plain read/write register.
+    #[allow(unused_variables)]
+    pub(super) fn read(&self, phy: &mut SimulatedPhy, reg: u8) -> Option<u16> {
+        #[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+        if self.is_lan87xxa() {
+            match reg {
+                LAN87XXA_INTERRUPT_SOURCE_REG => {
+                    return Some(core::mem::take(&mut phy.interrupt_status));
+                }
+                LAN87XXA_SSR_REG => {
+                    let mut ssr = 0;
+                    if phy.autoneg_complete {
+                        ssr |= LAN87XXA_SSR_AUTODONE;
+                    }
+                    if let Some((speed, duplex)) = phy.link {
+                        ssr |= match speed {
+                            LinkSpeed::Mpbs10 => LAN87XXA_SSR_SPEED_10,
+                            LinkSpeed::Mbps100 => LAN87XXA_SSR_SPEED_100,
+                            _ => 0,
+                        };
+                        if duplex == Duplex::Full {
+                            ssr |= LAN87XXA_SSR_SPEED_FULL_DUPLEX;
+                        }
+                    }
+                    let other = phy.regs[reg as usize] & !LAN87XXA_SSR_STATUS;
+                    return Some(other | ssr);
+                }
+                _ => return None,
+            }
+        }
+
+        #[cfg(feature = "ksz8081r")]
+        if *self == Self::Ksz8081r {
+            match reg {
+                KSZ8081R_INTERRUPT_REG => {
+                    let status = core::mem::take(&mut phy.interrupt_status);
+                    return Some((phy.regs[reg as usize] & 0xFF00) | status);
+                }
+                KSZ8081R_PHY_CONTROL1_REG => {
+                    let bcr = Bcr::from_bits_truncate(phy.regs[Bcr::ADDRESS as usize]);
+                    let mut ctrl = 0;
+                    if phy.pause.tx || phy.pause.rx {
+                        ctrl |= KSZ8081R_PHY_CONTROL1_ENABLE_PAUSE;
+                    }
+                    if phy.partner.is_some() {
+                        ctrl |= KSZ8081R_PHY_CONTROL1_ENERGY_DETECT;
+                    }
+                    if bcr.isolated() {
+                        ctrl |= KSZ8081R_PHY_CONTROL1_PHY_ISOLATE;
+                    }
+                    if let Some((speed, duplex)) = phy.link {
+                        ctrl |= KSZ8081R_PHY_CONTROL1_LINK_STATUS;
+                        ctrl |= match speed {
+                            LinkSpeed::Mpbs10 => KSZ8081R_PHY_CONTROL1_MODE_10,
+                            LinkSpeed::Mbps100 => KSZ8081R_PHY_CONTROL1_MODE_100,
+                            _ => 0,
+                        };
+                        if duplex == Duplex::Full {
+                            ctrl |= KSZ8081R_PHY_CONTROL1_MODE_FULL_DUPLEX;
+                        }
+                    }
+                    let other = phy.regs[reg as usize] & !KSZ8081R_PHY_CONTROL1_STATUS;
+                    return Some(other | ctrl);
+                }
+                _ => return None,
+            }
+        }
+
+        #[cfg(any(feature = "dp83848", feature = "dp83640"))]
+        if self.is_dp83xxx() {
+            #[cfg(feature = "dp83640")]
+            if *self == Self::Dp83640 {
+                if let Some(page_reg) = phy.paged_register(reg) {
+                    return Some(*page_reg);
+                }
+            }
+
+            match reg {
+                DP83XXX_PHYSTS_REG => {
+                    let mut physts = 0;
+                    if let Some((speed, duplex)) = phy.link {
+                        physts |= DP83XXX_PHYSTS_LINK_STATUS;
+                        if speed == LinkSpeed::Mpbs10 {
+                            physts |= DP83XXX_PHYSTS_SPEED_10;
+                        }
+                        if duplex == Duplex::Full {
+                            physts |= DP83XXX_PHYSTS_DUPLEX;
+                        }
+                    }
+                    if phy.autoneg_complete {
+                        physts |= DP83XXX_PHYSTS_AUTONEG_COMPLETE;
+                    }
+                    if self.interrupt_asserted(phy) {
+                        physts |= DP83XXX_PHYSTS_MII_INTERRUPT;
+                    }
+                    return Some(physts);
+                }
+                DP83XXX_MISR_REG => {
+                    let status = core::mem::take(&mut phy.interrupt_status);
+                    let enables = phy.regs[reg as usize] & DP83XXX_MISR_ENABLES;
+                    return Some(enables | status);
+                }
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    /// Write the vendor-specific register `reg`, or return `false` if
+    /// `reg` is a plain read/write register.
+    #[allow(unused_variables)]
+    pub(super) fn write(&self, phy: &mut SimulatedPhy, reg: u8, value: u16) -> bool {
+        #[cfg(any(feature = "lan8720a", feature = "lan8742a"))]
+        if self.is_lan87xxa() {
+            match reg {
+                // Read-only
+                LAN87XXA_INTERRUPT_SOURCE_REG => return true,
+                LAN87XXA_SSR_REG => {
+                    phy.regs[reg as usize] = value & !LAN87XXA_SSR_STATUS;
+                    return true;
+                }
+                _ => return false,
+            }
+        }
+
+        #[cfg(feature = "ksz8081r")]
+        if *self == Self::Ksz8081r {
+            match reg {
+                KSZ8081R_INTERRUPT_REG => {
+                    phy.regs[reg as usize] = value & 0xFF00;
+                    return true;
+                }
+                KSZ8081R_PHY_CONTROL1_REG => {
+                    phy.regs[reg as usize] = value & !KSZ8081R_PHY_CONTROL1_STATUS;
+                    return true;
+                }
+                _ => return false,
+            }
+        }
+
+        #[cfg(any(feature = "dp83848", feature = "dp83640"))]
+        if self.is_dp83xxx() {
+            #[cfg(feature = "dp83640")]
+            if *self == Self::Dp83640 {
+                if let Some(page_reg) = phy.paged_register(reg) {
+                    *page_reg = value;
+                    return true;
+                }
+            }
+
+            match reg {
+                // Read-only
+                DP83XXX_PHYSTS_REG => return true,
+                DP83XXX_MISR_REG => {
+                    phy.regs[reg as usize] = value & DP83XXX_MISR_ENABLES;
+                    return true;
+                }
+                DP83XXX_PAGE_REG => {
+                    phy.regs[reg as usize] = value & 0x7;
+                    return true;
+                }
+                _ => return false,
+            }
+        }
+
+        false
+    }
+}
+
+impl SimulatedPhy {
+    /// Get the register of the currently selected page that `reg` accesses,
+    /// if `reg` is paged.
+    #[cfg(feature = "dp83640")]
+    fn paged_register(&mut self, reg: u8) -> Option<&mut u16> {
+        let page = self.regs[DP83XXX_PAGE_REG as usize] as usize & 0x7;
+        if !(DP83640_FIRST_PAGED_REG..=0x1F).contains(&reg) || page == 0 {
+            return None;
+        }
+
+        Some(&mut self.pages[page - 1][(reg - DP83640_FIRST_PAGED_REG) as usize])
+    }
+
+    /// Get the value of register `reg` on page `page` of a simulated
+    /// DP83640, without the side effects of reading it.
+    ///
+    /// # Panics
+    /// If `page` is larger than 7, or `reg` is not a paged register.
+    #[cfg(feature = "dp83640")]
+    pub fn page_register(&self, page: u16, reg: u8) -> u16 {
+        assert!(page <= 7 && (DP83640_FIRST_PAGED_REG..=0x1F).contains(&reg));
+        if page == 0 {
+            self.regs[reg as usize]
+        } else {
+            self.pages[page as usize - 1][(reg - DP83640_FIRST_PAGED_REG) as usize]
+        }
+    }
+
+    /// Set the value of register `reg` on page `page` of a simulated
+    /// DP83640, without the side effects of writing it.
+    ///
+    /// # Panics
+    /// If `page` is larger than 7, or `reg` is not a paged register.
+    #[cfg(feature = "dp83640")]
+    pub fn set_page_register(&mut self, page: u16, reg: u8, value: u16) {
+        assert!(page <= 7 && (DP83640_FIRST_PAGED_REG..=0x1F).contains(&reg));
+        if page == 0 {
+            self.regs[reg as usize] = value;
+        } else {
+            self.pages[page as usize - 1][(reg - DP83640_FIRST_PAGED_REG) as usize] = value;
+        }
+    }
+}
@@ -0,0 +1,1079 @@
+//! An in-memory simulation of PHYs on an MDIO bus, for testing drivers
+//! without hardware.
+//!
+//! A [`SimulatedPhy`] models the Clause 22 register semantics of a PHY, and
+//! negotiates a link with a configurable [`LinkPartner`]. A [`SimMiim`] places
+//! one or more simulated PHYs on a bus, and implements [`Miim`].
+//!
+//! The vendor-specific registers of the bundled drivers are modelled by
+//! creating a PHY with [`SimulatedPhy::for_chip`].
+
+use core::convert::Infallible;
+
+use crate::{
+    registers::{Ane, AutoNegCap, Bcr, Bsr, Esr, GbCtrl, GbStatus, NextPage},
+    AutoNegotiationAdvertisement, Clause45Miim, Duplex, ExtendedPhyStatus, LinkSpeed, Miim,
+    PauseResolution, PhyStatus,
+};
+
+mod chip;
+pub use chip::SimChip;
+use chip::SimEvent;
+
+/// The amount of MMD registers that a [`SimulatedPhy`] can store.
+pub const SIM_MMD_CAPACITY: usize = 32;
+
+/// The amount of next pages that the link partner of a [`SimulatedPhy`] can send.
+pub const SIM_NEXT_PAGE_CAPACITY: usize = 8;
+
+/// The ACK bit of the link partner ability register
+const PARTNER_CAP_ACK: u16 = 1 << 14;
+
+/// A simulated link partner, connected to a [`SimulatedPhy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LinkPartner {
+    /// The modes that the link partner supports.
+    ///
+    /// If `autonegotiation` is set, these modes are advertised as they are.
+    pub advertisement: AutoNegotiationAdvertisement,
+    /// The link partner performs autonegotiation.
+    ///
+    /// If this is not set, the link partner operates in the best half duplex
+    /// mode of `advertisement`, and the simulated PHY establishes the link
+    /// using parallel detection.
+    pub autonegotiation: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct MmdRegister {
+    device: u8,
+    address: u16,
+    value: u16,
+}
+
+/// A simulated PHY.
+///
+/// The following Clause 22 semantics are modelled:
+/// * `RESET` and `RESTART_AUTONEG` in the BCR are self-clearing. A reset
+///   restores all Clause 22 registers to their power-on values.
+/// * The BSR, identifier registers, link partner ability registers,
+///   autonegotiation expansion register and ESR are read-only.
+/// * `LINK_STATUS` in the BSR latches low, `JABBER_DETECT` and `REMOTE_FAULT`
+///   latch high.
+/// * `PAGE_RECEIVED` and `PARALLEL_DECT_FAULT` in the autonegotiation
+///   expansion register latch high.
+/// * If both the local base page and the link partner base page have the
+///   next page flag set, autonegotiation only completes after next pages have
+///   been exchanged. The link partner sends one of its next pages, set with
+///   [`SimulatedPhy::set_partner_next_pages`], each time a next page is
+///   written to register 7.
+/// * If enabled with [`SimulatedPhy::set_mmd`], registers 13 and 14 provide
+///   indirect access to MMD registers.
+/// * The vendor-specific registers of the [`SimChip`], including the
+///   clear-on-read behaviour of their interrupt status registers.
+///
+/// All other registers behave as plain read/write storage.
+#[derive(Clone, Debug)]
+pub struct SimulatedPhy {
+    chip: SimChip,
+    ident: u32,
+    status: PhyStatus,
+    extended_status: Option<ExtendedPhyStatus>,
+    regs: [u16; 32],
+
+    partner: Option<LinkPartner>,
+    link: Option<(LinkSpeed, Duplex)>,
+    pause: PauseResolution,
+    autoneg_complete: bool,
+    partner_autoneg_able: bool,
+
+    partner_next_pages: [NextPage; SIM_NEXT_PAGE_CAPACITY],
+    partner_next_pages_len: usize,
+    /// The amount of next pages sent by the link partner in the current
+    /// exchange, or `None` if no next pages are being exchanged.
+    next_pages_sent: Option<usize>,
+    partner_toggle: bool,
+
+    link_status_latch: bool,
+    jabber_latch: bool,
+    remote_fault_latch: bool,
+    page_received_latch: bool,
+    parallel_detection_fault_latch: bool,
+
+    autoneg_polls: u32,
+    autoneg_remaining: Option<u32>,
+    reset_polls: Option<u32>,
+    resetting: bool,
+    reset_remaining: Option<u32>,
+
+    mmd_enabled: bool,
+    mmd_addresses: [u16; 32],
+    mmd: [Option<MmdRegister>; SIM_MMD_CAPACITY],
+
+    interrupt_status: u16,
+    #[cfg(feature = "dp83640")]
+    pages: [[u16; 12]; 7],
+}
+
+impl SimulatedPhy {
+    /// Create a new simulated PHY with the identifier `ident`, that supports
+    /// the modes in `status` and `extended_status`.
+    ///
+    /// The PHY starts out without a link partner, with autonegotiation enabled
+    /// if it is supported, and advertising all supported modes.
+    pub fn new(ident: u32, status: PhyStatus, extended_status: Option<ExtendedPhyStatus>) -> Self {
+        let mut me = Self {
+            chip: SimChip::Generic,
+            ident,
+            status,
+            extended_status,
+            regs: [0; 32],
+            partner: None,
+            link: None,
+            pause: PauseResolution::default(),
+            autoneg_complete: false,
+            partner_autoneg_able: false,
+            partner_next_pages: [NextPage::empty(); SIM_NEXT_PAGE_CAPACITY],
+            partner_next_pages_len: 0,
+            next_pages_sent: None,
+            partner_toggle: false,
+            link_status_latch: true,
+            jabber_latch: false,
+            remote_fault_latch: false,
+            page_received_latch: false,
+            parallel_detection_fault_latch: false,
+            autoneg_polls: 0,
+            autoneg_remaining: None,
+            reset_polls: Some(0),
+            resetting: false,
+            reset_remaining: None,
+            mmd_enabled: false,
+            mmd_addresses: [0; 32],
+            mmd: [None; SIM_MMD_CAPACITY],
+            interrupt_status: 0,
+            #[cfg(feature = "dp83640")]
+            pages: [[0; 12]; 7],
+        };
+        me.load_defaults();
+        me
+    }
+
+    /// Create a new simulated PHY that models `chip`.
+    ///
+    /// For [`SimChip::Generic`], this creates a 10/100 Mbps PHY without a
+    /// meaningful identifier.
+    pub fn for_chip(chip: SimChip) -> Self {
+        let status = chip.status().unwrap_or(PhyStatus {
+            base100_t4: false,
+            fd_100base_x: true,
+            hd_100base_x: true,
+            fd_10mbps: true,
+            hd_10mbps: true,
+            extended_status: false,
+            unidirectional: false,
+            preamble_suppression: false,
+            autonegotiation: true,
+            extended_caps: true,
+        });
+
+        let mut me = Self::new(chip.ident().unwrap_or(0), status, None);
+        me.chip = chip;
+        me.mmd_enabled = chip.has_mmd();
+        me
+    }
+
+    /// The chip whose vendor-specific registers this PHY models
+    pub fn chip(&self) -> SimChip {
+        self.chip
+    }
+
+    /// Check if the interrupt output of the PHY is asserted, i.e. if any
+    /// of the enabled interrupts are pending.
+    ///
+    /// This is always `false` for [`SimChip::Generic`].
+    pub fn interrupt_asserted(&self) -> bool {
+        self.chip.interrupt_asserted(self)
+    }
+
+    /// Connect a link partner, and start negotiating a link with it.
+    pub fn connect(&mut self, partner: LinkPartner) {
+        self.partner = Some(partner);
+        self.raise(SimEvent::EnergyDetect);
+        self.restart_link();
+    }
+
+    /// Disconnect the link partner, taking the link down.
+    pub fn disconnect(&mut self) {
+        self.partner = None;
+        self.restart_link();
+    }
+
+    /// The link partner that is currently connected
+    pub fn partner(&self) -> Option<&LinkPartner> {
+        self.partner.as_ref()
+    }
+
+    /// The speed and duplex mode of the current link, or `None` if
+    /// the link is down.
+    pub fn link(&self) -> Option<(LinkSpeed, Duplex)> {
+        self.link
+    }
+
+    /// Check if autonegotiation has completed
+    pub fn autoneg_complete(&self) -> bool {
+        self.autoneg_complete
+    }
+
+    /// Set the next pages that the link partner sends, after which it sends
+    /// Null Message pages.
+    ///
+    /// The next page, ACK and toggle flags of `pages` are ignored, as they are
+    /// managed by the link partner. This takes effect when the link is
+    /// negotiated again.
+    ///
+    /// # Panics
+    /// If more than [`SIM_NEXT_PAGE_CAPACITY`] pages are given.
+    pub fn set_partner_next_pages(&mut self, pages: &[NextPage]) {
+        self.partner_next_pages[..pages.len()].copy_from_slice(pages);
+        self.partner_next_pages_len = pages.len();
+    }
+
+    /// Set the amount of reads of the BSR after which autonegotiation completes.
+    ///
+    /// This is 0 by default, in which case autonegotiation completes immediately.
+    pub fn set_autoneg_polls(&mut self, polls: u32) {
+        self.autoneg_polls = polls;
+    }
+
+    /// Set the amount of reads of the BCR after which a reset completes, or
+    /// `None` if a reset never completes.
+    ///
+    /// This is `Some(0)` by default, in which case the reset has completed
+    /// by the first read.
+    pub fn set_reset_polls(&mut self, polls: Option<u32>) {
+        self.reset_polls = polls;
+    }
+
+    /// Enable or disable MMD access through registers 13 and 14.
+    ///
+    /// This is disabled by default.
+    pub fn set_mmd(&mut self, enabled: bool) {
+        self.mmd_enabled = enabled;
+    }
+
+    /// Signal that jabber was detected
+    pub fn signal_jabber(&mut self) {
+        self.jabber_latch = true;
+        self.raise(SimEvent::Jabber);
+    }
+
+    /// Signal that the link partner detected a remote fault
+    pub fn signal_remote_fault(&mut self) {
+        self.remote_fault_latch = true;
+        self.raise(SimEvent::RemoteFault);
+    }
+
+    /// Get the value of the Clause 22 register `reg`, without the side
+    /// effects of reading it.
+    ///
+    /// The values of the BSR and the autonegotiation expansion register do not
+    /// include latched bits. For vendor-specific registers whose value is
+    /// computed when they are read, this returns the stored value instead.
+    pub fn register(&self, reg: u8) -> u16 {
+        match reg {
+            1 => self.bsr(self.link.is_some(), false, false).bits(),
+            6 => self.ane(false, false).bits(),
+            _ => self.regs[reg as usize & 0x1F],
+        }
+    }
+
+    /// Set the value of the Clause 22 register `reg`, without the side
+    /// effects of writing it.
+    pub fn set_register(&mut self, reg: u8, value: u16) {
+        self.regs[reg as usize & 0x1F] = value;
+    }
+
+    /// Get the value of MMD register `address` of MMD `device`.
+    pub fn mmd_register(&self, device: u8, address: u16) -> u16 {
+        self.mmd
+            .iter()
+            .flatten()
+            .find(|reg| reg.device == device && reg.address == address)
+            .map(|reg| reg.value)
+            .unwrap_or(0)
+    }
+
+    /// Set the value of MMD register `address` of MMD `device`.
+    ///
+    /// # Panics
+    /// If more than [`SIM_MMD_CAPACITY`] different MMD registers are written.
+    pub fn set_mmd_register(&mut self, device: u8, address: u16, value: u16) {
+        let existing = self
+            .mmd
+            .iter_mut()
+            .flatten()
+            .find(|reg| reg.device == device && reg.address == address);
+
+        if let Some(reg) = existing {
+            reg.value = value;
+            return;
+        }
+
+        let slot = self
+            .mmd
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .expect("Simulated MMD register storage is full");
+
+        *slot = Some(MmdRegister {
+            device,
+            address,
+            value,
+        });
+    }
+
+    /// Read register `reg`, as the PHY would respond to a management frame.
+    pub fn read(&mut self, reg: u8) -> u16 {
+        match reg {
+            0 => {
+                self.tick_reset();
+                self.regs[0]
+            }
+            1 => {
+                self.tick_autoneg();
+                let link_status = self.link_status_latch && self.link.is_some();
+                let bsr = self.bsr(link_status, self.jabber_latch, self.remote_fault_latch);
+                self.link_status_latch = true;
+                self.jabber_latch = false;
+                self.remote_fault_latch = false;
+                bsr.bits()
+            }
+            6 => {
+                let ane = self.ane(
+                    self.page_received_latch,
+                    self.parallel_detection_fault_latch,
+                );
+                self.page_received_latch = false;
+                self.parallel_detection_fault_latch = false;
+                ane.bits()
+            }
+            13 | 14 if self.mmd_enabled => self.read_mmd(reg),
+            reg => {
+                let chip = self.chip;
+                chip.read(self, reg & 0x1F)
+                    .unwrap_or(self.regs[reg as usize & 0x1F])
+            }
+        }
+    }
+
+    /// Write `value` to register `reg`, as the PHY would handle a management frame.
+    pub fn write(&mut self, reg: u8, value: u16) {
+        match reg {
+            0 => self.write_bcr(Bcr::from_bits_truncate(value)),
+            // Read-only registers
+            1 | 2 | 3 | 5 | 6 | 8 | 10 | 15 => {}
+            7 => {
+                self.regs[7] = value;
+                self.transmit_next_page(NextPage::from_bits_truncate(value));
+            }
+            13 | 14 if self.mmd_enabled => self.write_mmd(reg, value),
+            reg => {
+                let chip = self.chip;
+                if !chip.write(self, reg & 0x1F, value) {
+                    self.regs[reg as usize & 0x1F] = value;
+                }
+            }
+        }
+    }
+
+    fn raise(&mut self, event: SimEvent) {
+        self.interrupt_status |= self.chip.interrupt_bits(event);
+    }
+
+    fn load_defaults(&mut self) {
+        self.regs = [0; 32];
+        self.interrupt_status = 0;
+        #[cfg(feature = "dp83640")]
+        {
+            self.pages = [[0; 12]; 7];
+        }
+
+        let mut bcr = Bcr::empty();
+        bcr.set_autonegotiation(self.status.autonegotiation);
+        if self.status.fd_100base_x || self.status.hd_100base_x {
+            bcr.insert(Bcr::SPEED_SEL_LSB);
+        }
+        bcr.set_full_duplex(self.status.fd_100base_x || self.status.fd_10mbps);
+        self.regs[Bcr::ADDRESS as usize] = bcr.bits();
+
+        self.regs[2] = (self.ident >> 16) as u16;
+        self.regs[3] = self.ident as u16;
+
+        let mut ad = self.status.best_autoneg_ad();
+        if let Some(ext) = self.extended_status {
+            ad.fd_1000base_t = ext.fd_1000base_t;
+            ad.hd_1000base_t = ext.hd_1000base_t;
+        }
+        self.regs[AutoNegCap::LOCAL_CAP_ADDRESS as usize] = encode_advertisement(&ad).bits();
+
+        if let Some(ext) = self.extended_status {
+            let mut gb_ctrl = GbCtrl::empty();
+            gb_ctrl
+                .set_advertise_1000base_t_fd(ext.fd_1000base_t)
+                .set_advertise_1000base_t_hd(ext.hd_1000base_t);
+            self.regs[GbCtrl::ADDRESS as usize] = gb_ctrl.bits();
+
+            let mut esr = Esr::empty();
+            esr.set(Esr::_1000BASEXFD, ext.fd_1000base_x);
+            esr.set(Esr::_1000BASEXHD, ext.hd_1000base_x);
+            esr.set(Esr::_1000BASETFD, ext.fd_1000base_t);
+            esr.set(Esr::_1000BASETHD, ext.hd_1000base_t);
+            self.regs[Esr::ADDRESS as usize] = esr.bits();
+        }
+    }
+
+    fn supports_1000base_t(&self) -> bool {
+        self.extended_status
+            .map(|ext| ext.fd_1000base_t || ext.hd_1000base_t)
+            .unwrap_or(false)
+    }
+
+    fn bsr(&self, link_status: bool, jabber: bool, remote_fault: bool) -> Bsr {
+        let status = &self.status;
+        let mut bsr = Bsr::empty();
+        bsr.set(Bsr::_100BASET4, status.base100_t4);
+        bsr.set(Bsr::_100BASEXFD, status.fd_100base_x);
+        bsr.set(Bsr::_100BASEXHD, status.hd_100base_x);
+        bsr.set(Bsr::_10MPBSFD, status.fd_10mbps);
+        bsr.set(Bsr::_10MBPSHD, status.hd_10mbps);
+        bsr.set(Bsr::EXTENDED_STATUS, status.extended_status);
+        bsr.set(Bsr::UNIDRECTIONAL, status.unidirectional);
+        bsr.set(Bsr::MF_PREAMBLE_SUPPRESSION, status.preamble_suppression);
+        bsr.set(Bsr::AUTONEG_ABLE, status.autonegotiation);
+        bsr.set(Bsr::EXTENDED_CAPABILITIES, status.extended_caps);
+
+        bsr.set(Bsr::AUTONEG_COMPLETE, self.autoneg_complete);
+        bsr.set(Bsr::LINK_STATUS, link_status);
+        bsr.set(Bsr::JABBER_DETECT, jabber);
+        bsr.set(Bsr::REMOTE_FAULT, remote_fault);
+        bsr
+    }
+
+    fn ane(&self, page_received: bool, parallel_detection_fault: bool) -> Ane {
+        let mut ane = Ane::empty();
+        ane.set(Ane::LINK_PARTNER_AUTONEG_ABLE, self.partner_autoneg_able);
+        ane.set(
+            Ane::LINK_PARTNER_NEXT_PAGE_ABLE,
+            self.partner_autoneg_able
+                && self.regs[AutoNegCap::PARTNER_CAP_ADDRESS as usize]
+                    & AutoNegCap::NEXT_PAGE.bits()
+                    != 0,
+        );
+        ane.set(Ane::NEXT_PAGE_ABLE, self.status.autonegotiation);
+        ane.set(Ane::PAGE_RECEIVED, page_received);
+        ane.set(Ane::PARALLEL_DECT_FAULT, parallel_detection_fault);
+        ane
+    }
+
+    fn write_bcr(&mut self, value: Bcr) {
+        if value.is_resetting() {
+            self.regs[Bcr::ADDRESS as usize] = value.bits();
+            self.resetting = true;
+            self.reset_remaining = self.reset_polls;
+            return;
+        }
+
+        let previous = Bcr::from_bits_truncate(self.regs[Bcr::ADDRESS as usize]);
+        let stored = value - Bcr::RESTART_AUTONEG;
+        self.regs[Bcr::ADDRESS as usize] = stored.bits();
+
+        let link_config = Bcr::AUTONEG_ENABLE
+            | Bcr::POWER_DOWN
+            | Bcr::ISOLATE
+            | Bcr::SPEED_SEL_MSB
+            | Bcr::SPEED_SEL_LSB
+            | Bcr::DUPLEX_MODE;
+
+        let restart = value.contains(Bcr::RESTART_AUTONEG) && value.autonegotiation();
+        if restart || (previous & link_config) != (stored & link_config) {
+            self.restart_link();
+        }
+    }
+
+    fn tick_reset(&mut self) {
+        if !self.resetting {
+            return;
+        }
+
+        match self.reset_remaining {
+            Some(0) => {
+                self.resetting = false;
+                self.load_defaults();
+                self.restart_link();
+            }
+            Some(n) => self.reset_remaining = Some(n - 1),
+            None => {}
+        }
+    }
+
+    fn tick_autoneg(&mut self) {
+        match self.autoneg_remaining {
+            Some(0) => {
+                self.autoneg_remaining = None;
+                self.negotiate();
+            }
+            Some(n) => self.autoneg_remaining = Some(n - 1),
+            None => {}
+        }
+    }
+
+    fn set_link(&mut self, link: Option<(LinkSpeed, Duplex)>) {
+        match (self.link, link) {
+            (None, Some(_)) => self.raise(SimEvent::LinkUp),
+            (Some(_), None) => self.raise(SimEvent::LinkDown),
+            (Some((old_speed, old_duplex)), Some((speed, duplex))) => {
+                if old_speed != speed {
+                    self.raise(SimEvent::SpeedChange);
+                }
+                if old_duplex != duplex {
+                    self.raise(SimEvent::DuplexChange);
+                }
+            }
+            (None, None) => {}
+        }
+
+        if link.is_none() {
+            // Only a link failure clears the latched link status
+            if self.link.is_some() {
+                self.link_status_latch = false;
+            }
+            self.pause = PauseResolution::default();
+        }
+        self.link = link;
+    }
+
+    fn complete_autoneg(&mut self) {
+        self.autoneg_complete = true;
+        self.raise(SimEvent::AutonegComplete);
+    }
+
+    /// Take the link down, and start establishing a new one.
+    fn restart_link(&mut self) {
+        self.set_link(None);
+        self.autoneg_complete = false;
+        self.partner_autoneg_able = false;
+        self.next_pages_sent = None;
+        self.regs[AutoNegCap::PARTNER_CAP_ADDRESS as usize] = 0;
+        self.regs[NextPage::RECEIVE_ADDR as usize] = 0;
+        self.regs[GbStatus::ADDRESS as usize] = 0;
+
+        let bcr = Bcr::from_bits_truncate(self.regs[Bcr::ADDRESS as usize]);
+        if bcr.autonegotiation() && self.autoneg_polls > 0 {
+            self.autoneg_remaining = Some(self.autoneg_polls - 1);
+        } else {
+            self.autoneg_remaining = None;
+            self.negotiate();
+        }
+    }
+
+    /// Establish the link with the link partner, if there is one.
+    fn negotiate(&mut self) {
+        let bcr = Bcr::from_bits_truncate(self.regs[Bcr::ADDRESS as usize]);
+
+        let partner = match self.partner {
+            Some(partner) if !bcr.power_down() && !bcr.isolated() => partner,
+            _ => return,
+        };
+
+        if !bcr.autonegotiation() {
+            let speed = LinkSpeed::from(bcr);
+            let duplex = if bcr.full_duplex() {
+                Duplex::Full
+            } else {
+                Duplex::Half
+            };
+
+            if supports_speed(&partner.advertisement, speed) {
+                self.set_link(Some((speed, duplex)));
+            }
+            return;
+        }
+
+        if partner.autonegotiation {
+            let local_cap =
+                AutoNegCap::from_bits_truncate(self.regs[AutoNegCap::LOCAL_CAP_ADDRESS as usize]);
+            let mut partner_cap = encode_advertisement(&partner.advertisement);
+            partner_cap.set(AutoNegCap::NEXT_PAGE, self.partner_next_pages_len > 0);
+            self.regs[AutoNegCap::PARTNER_CAP_ADDRESS as usize] =
+                partner_cap.bits() | PARTNER_CAP_ACK;
+            self.partner_autoneg_able = true;
+            self.page_received_latch = true;
+            self.raise(SimEvent::PageReceived);
+            self.raise(SimEvent::LinkPartnerAck);
+
+            // Next pages are only exchanged if both base pages have the
+            // next page flag set.
+            if local_cap.contains(AutoNegCap::NEXT_PAGE)
+                && partner_cap.contains(AutoNegCap::NEXT_PAGE)
+            {
+                self.next_pages_sent = Some(0);
+                self.partner_toggle = !partner_cap.contains(AutoNegCap::ASSYMETRIC_PAUSE);
+                return;
+            }
+
+            self.resolve(&partner.advertisement);
+        } else {
+            // Parallel detection only detects 100BASE-TX and 10BASE-T,
+            // and always results in a half duplex link.
+            let partner_ad = &partner.advertisement;
+            let detected = if partner_ad.hd_100base_tx || partner_ad.fd_100base_tx {
+                Some((LinkSpeed::Mbps100, AutoNegCap::_100BASETX))
+            } else if partner_ad.hd_10base_t || partner_ad.fd_10base_t {
+                Some((LinkSpeed::Mpbs10, AutoNegCap::_10BASET))
+            } else {
+                None
+            };
+
+            match detected {
+                Some((speed, cap)) if supports_speed(&self.local_advertisement(), speed) => {
+                    self.regs[AutoNegCap::PARTNER_CAP_ADDRESS as usize] = cap.bits();
+                    self.complete_autoneg();
+                    self.set_link(Some((speed, Duplex::Half)));
+                }
+                _ => {
+                    self.parallel_detection_fault_latch = true;
+                    self.raise(SimEvent::ParallelDetectFault);
+                }
+            }
+        }
+    }
+
+    /// The modes advertised in the local base page and 1000BASE-T control register
+    fn local_advertisement(&self) -> AutoNegotiationAdvertisement {
+        let local_cap =
+            AutoNegCap::from_bits_truncate(self.regs[AutoNegCap::LOCAL_CAP_ADDRESS as usize]);
+        let mut local: AutoNegotiationAdvertisement = local_cap.into();
+        if self.supports_1000base_t() {
+            let gb_ctrl = GbCtrl::from_bits_truncate(self.regs[GbCtrl::ADDRESS as usize]);
+            local.fd_1000base_t = gb_ctrl.advertise_1000base_t_fd();
+            local.hd_1000base_t = gb_ctrl.advertise_1000base_t_hd();
+        }
+        local
+    }
+
+    /// Complete autonegotiation with a link partner that advertises `partner`,
+    /// and establish the link in the best common mode.
+    fn resolve(&mut self, partner: &AutoNegotiationAdvertisement) {
+        let result = self.local_advertisement().resolve(partner);
+
+        if self.supports_1000base_t() {
+            let mut gb_status = GbStatus::empty();
+            gb_status.set(GbStatus::PARTNER_1000BASETFD, partner.fd_1000base_t);
+            gb_status.set(GbStatus::PARTNER_1000BASETHD, partner.hd_1000base_t);
+            if matches!(result, Some(r) if r.speed == LinkSpeed::Mpbs1000) {
+                gb_status.insert(GbStatus::LOCAL_RECEIVER_OK | GbStatus::REMOTE_RECEIVER_OK);
+            }
+            self.regs[GbStatus::ADDRESS as usize] = gb_status.bits();
+        }
+
+        self.complete_autoneg();
+        self.set_link(result.map(|result| (result.speed, result.duplex)));
+        self.pause = result.map(|result| result.pause).unwrap_or_default();
+    }
+
+    /// Respond to the next page `local` with the next page of the link
+    /// partner, and complete autonegotiation once neither has more pages.
+    fn transmit_next_page(&mut self, local: NextPage) {
+        let (sent, partner) = match (self.next_pages_sent, self.partner) {
+            (Some(sent), Some(partner)) => (sent, partner),
+            _ => return,
+        };
+
+        let mut page = self.partner_next_pages[..self.partner_next_pages_len]
+            .get(sent)
+            .copied()
+            .unwrap_or_else(NextPage::null_message);
+        let more = sent + 1 < self.partner_next_pages_len;
+        page.set_next_page(more)
+            .set_ack(true)
+            .set_toggle_bit(self.partner_toggle);
+
+        self.regs[NextPage::RECEIVE_ADDR as usize] = page.bits();
+        self.partner_toggle = !self.partner_toggle;
+        self.next_pages_sent = Some(sent + 1);
+        self.page_received_latch = true;
+        self.raise(SimEvent::PageReceived);
+
+        if !local.next_page() && !more {
+            self.next_pages_sent = None;
+            self.resolve(&partner.advertisement);
+        }
+    }
+
+    fn read_mmd(&mut self, reg: u8) -> u16 {
+        let control = self.regs[13];
+        let device = (control & 0x1F) as u8;
+        let function = control >> 14;
+
+        if reg == 13 {
+            return control;
+        }
+
+        let address = &mut self.mmd_addresses[device as usize];
+        if function == 0b00 {
+            return *address;
+        }
+
+        let current = *address;
+        if function == 0b10 {
+            *address = address.wrapping_add(1);
+        }
+        self.mmd_register(device, current)
+    }
+
+    fn write_mmd(&mut self, reg: u8, value: u16) {
+        if reg == 13 {
+            self.regs[13] = value;
+            return;
+        }
+
+        let control = self.regs[13];
+        let device = (control & 0x1F) as u8;
+        let function = control >> 14;
+
+        let address = &mut self.mmd_addresses[device as usize];
+        if function == 0b00 {
+            *address = value;
+            return;
+        }
+
+        let current = *address;
+        if function == 0b10 || function == 0b11 {
+            *address = address.wrapping_add(1);
+        }
+        self.set_mmd_register(device, current, value);
+    }
+}
+
+fn supports_speed(ad: &AutoNegotiationAdvertisement, speed: LinkSpeed) -> bool {
+    match speed {
+        LinkSpeed::Mpbs10 => ad.hd_10base_t || ad.fd_10base_t,
+        LinkSpeed::Mbps100 => ad.hd_100base_tx || ad.fd_100base_tx || ad.base100_t4,
+        LinkSpeed::Mpbs1000 => ad.hd_1000base_t || ad.fd_1000base_t,
+        LinkSpeed::Illegal => false,
+    }
+}
+
+/// Encode the Clause 22 part of `ad` in the format of registers 4 and 5.
+fn encode_advertisement(ad: &AutoNegotiationAdvertisement) -> AutoNegCap {
+    let mut cap = AutoNegCap::from(ad.pause);
+    cap.set(AutoNegCap::_10BASET, ad.hd_10base_t);
+    cap.set(AutoNegCap::_10BASETFD, ad.fd_10base_t);
+    cap.set(AutoNegCap::_100BASETX, ad.hd_100base_tx);
+    cap.set(AutoNegCap::_100BASETXFD, ad.fd_100base_tx);
+    cap.set(AutoNegCap::_100BASET4, ad.base100_t4);
+    if let Some(selector) = ad.selector_field {
+        cap.insert(selector.into());
+    }
+    cap
+}
+
+/// A simulated MDIO bus with up to `N` [`SimulatedPhy`]s on it.
+///
+/// Reads from addresses without a PHY return `0xFFFF`, as no PHY
+/// drives MDIO.
+#[derive(Clone, Debug)]
+pub struct SimMiim<const N: usize = 1> {
+    phys: [Option<(u8, SimulatedPhy)>; N],
+    clause45: bool,
+}
+
+impl<const N: usize> SimMiim<N> {
+    /// Create a new simulated bus without any PHYs on it
+    pub fn new() -> Self {
+        Self {
+            phys: core::array::from_fn(|_| None),
+            clause45: false,
+        }
+    }
+
+    /// Configure whether the bus supports Clause 45 frames, which access the
+    /// MMD registers of the PHYs directly.
+    ///
+    /// This is `false` by default.
+    pub fn set_clause45(&mut self, clause45: bool) {
+        self.clause45 = clause45;
+    }
+
+    /// Place `phy` on the bus at `address`, replacing the PHY that was
+    /// previously at that address.
+    ///
+    /// # Panics
+    /// If there are already `N` PHYs on the bus.
+    pub fn add(&mut self, address: u8, phy: SimulatedPhy) {
+        if let Some(existing) = self.phy_mut(address) {
+            *existing = phy;
+            return;
+        }
+
+        let slot = self
+            .phys
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .expect("Simulated MDIO bus is full");
+        *slot = Some((address, phy));
+    }
+
+    /// Get the PHY at `address`
+    pub fn phy(&self, address: u8) -> Option<&SimulatedPhy> {
+        self.phys
+            .iter()
+            .flatten()
+            .find(|(addr, _)| *addr == address)
+            .map(|(_, phy)| phy)
+    }
+
+    /// Get the PHY at `address` mutably
+    pub fn phy_mut(&mut self, address: u8) -> Option<&mut SimulatedPhy> {
+        self.phys
+            .iter_mut()
+            .flatten()
+            .find(|(addr, _)| *addr == address)
+            .map(|(_, phy)| phy)
+    }
+}
+
+impl<const N: usize> Default for SimMiim<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Miim for SimMiim<N> {
+    type Error = Infallible;
+
+    fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error> {
+        Ok(self.phy_mut(phy).map(|phy| phy.read(reg)).unwrap_or(0xFFFF))
+    }
+
+    fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error> {
+        if let Some(phy) = self.phy_mut(phy) {
+            phy.write(reg, data);
+        }
+        Ok(())
+    }
+
+    fn clause45(&mut self) -> Option<&mut dyn Clause45Miim<Error = Self::Error>> {
+        if self.clause45 {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl<const N: usize> Clause45Miim for SimMiim<N> {
+    type Error = Infallible;
+
+    fn read_c45(&mut self, port: u8, devad: u8, reg: u16) -> Result<u16, Self::Error> {
+        Ok(self
+            .phy(port)
+            .map(|phy| phy.mmd_register(devad, reg))
+            .unwrap_or(0xFFFF))
+    }
+
+    fn write_c45(&mut self, port: u8, devad: u8, reg: u16, data: u16) -> Result<(), Self::Error> {
+        if let Some(phy) = self.phy_mut(port) {
+            phy.set_mmd_register(devad, reg, data);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partner() -> LinkPartner {
+        LinkPartner {
+            advertisement: AutoNegotiationAdvertisement {
+                hd_100base_tx: true,
+                fd_100base_tx: true,
+                ..Default::default()
+            },
+            autonegotiation: true,
+        }
+    }
+
+    fn bsr(phy: &mut SimulatedPhy) -> Bsr {
+        Bsr::from_bits_truncate(phy.read(Bsr::ADDRESS))
+    }
+
+    #[test]
+    fn link_status_latches_low() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        assert!(!bsr(&mut phy).contains(Bsr::LINK_STATUS));
+
+        phy.connect(partner());
+        assert!(bsr(&mut phy).contains(Bsr::LINK_STATUS));
+
+        // A link failure that recovers before the BSR is read is still reported once
+        phy.disconnect();
+        phy.connect(partner());
+        assert_eq!(phy.link(), Some((LinkSpeed::Mbps100, Duplex::Full)));
+        assert!(!bsr(&mut phy).contains(Bsr::LINK_STATUS));
+        assert!(bsr(&mut phy).contains(Bsr::LINK_STATUS));
+    }
+
+    #[test]
+    fn remote_fault_and_jabber_latch_high() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        phy.connect(partner());
+
+        phy.signal_remote_fault();
+        phy.signal_jabber();
+        let first = bsr(&mut phy);
+        assert!(first.contains(Bsr::REMOTE_FAULT | Bsr::JABBER_DETECT));
+
+        let second = bsr(&mut phy);
+        assert!(!second.intersects(Bsr::REMOTE_FAULT | Bsr::JABBER_DETECT));
+    }
+
+    #[test]
+    fn reset_is_self_clearing_and_restores_defaults() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        let default_ad = phy.register(AutoNegCap::LOCAL_CAP_ADDRESS);
+        phy.set_reset_polls(Some(1));
+
+        phy.write(AutoNegCap::LOCAL_CAP_ADDRESS, AutoNegCap::_10BASET.bits());
+        phy.write(Bcr::ADDRESS, Bcr::RESET.bits());
+
+        assert!(Bcr::from_bits_truncate(phy.read(Bcr::ADDRESS)).contains(Bcr::RESET));
+        assert!(!Bcr::from_bits_truncate(phy.read(Bcr::ADDRESS)).contains(Bcr::RESET));
+        assert_eq!(phy.register(AutoNegCap::LOCAL_CAP_ADDRESS), default_ad);
+    }
+
+    #[test]
+    fn reset_that_never_completes() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        phy.set_reset_polls(None);
+        phy.write(Bcr::ADDRESS, Bcr::RESET.bits());
+
+        for _ in 0..10 {
+            assert!(Bcr::from_bits_truncate(phy.read(Bcr::ADDRESS)).contains(Bcr::RESET));
+        }
+    }
+
+    #[test]
+    fn restart_autoneg_is_self_clearing() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        phy.set_autoneg_polls(2);
+        phy.connect(partner());
+        phy.write(
+            Bcr::ADDRESS,
+            (Bcr::AUTONEG_ENABLE | Bcr::RESTART_AUTONEG).bits(),
+        );
+
+        let bcr = Bcr::from_bits_truncate(phy.read(Bcr::ADDRESS));
+        assert!(bcr.contains(Bcr::AUTONEG_ENABLE));
+        assert!(!bcr.contains(Bcr::RESTART_AUTONEG));
+
+        // Autonegotiation completes on the second read of the BSR
+        assert!(!bsr(&mut phy).contains(Bsr::AUTONEG_COMPLETE));
+        assert!(bsr(&mut phy).contains(Bsr::AUTONEG_COMPLETE));
+        assert!(phy.link().is_some());
+    }
+
+    #[test]
+    fn page_received_latches_high() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        phy.connect(partner());
+
+        let ane = Ane::from_bits_truncate(phy.read(Ane::ADDRESS));
+        assert!(ane.contains(Ane::PAGE_RECEIVED | Ane::LINK_PARTNER_AUTONEG_ABLE));
+
+        let ane = Ane::from_bits_truncate(phy.read(Ane::ADDRESS));
+        assert!(!ane.contains(Ane::PAGE_RECEIVED));
+        assert!(ane.contains(Ane::LINK_PARTNER_AUTONEG_ABLE));
+    }
+
+    #[test]
+    fn parallel_detection() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        phy.connect(LinkPartner {
+            autonegotiation: false,
+            ..partner()
+        });
+
+        assert_eq!(phy.link(), Some((LinkSpeed::Mbps100, Duplex::Half)));
+        let ane = Ane::from_bits_truncate(phy.read(Ane::ADDRESS));
+        assert!(!ane.intersects(Ane::PAGE_RECEIVED | Ane::LINK_PARTNER_AUTONEG_ABLE));
+    }
+
+    #[test]
+    fn read_only_registers() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        let bsr = phy.read(Bsr::ADDRESS);
+        phy.write(Bsr::ADDRESS, !bsr);
+        phy.write(2, 0x1234);
+        assert_eq!(phy.read(Bsr::ADDRESS), bsr);
+        assert_eq!(phy.read(2), 0);
+    }
+
+    #[test]
+    fn mmd_indirection_with_post_increment() {
+        let mut phy = SimulatedPhy::for_chip(SimChip::Generic);
+        phy.set_mmd(true);
+
+        // Set the address of MMD 3 to 0x10
+        phy.write(13, 3);
+        phy.write(14, 0x10);
+        assert_eq!(phy.read(14), 0x10);
+
+        // Post-increment on reads and writes
+        phy.write(13, 0b10 << 14 | 3);
+        phy.write(14, 0xAAAA);
+        phy.write(14, 0xBBBB);
+        assert_eq!(phy.mmd_register(3, 0x10), 0xAAAA);
+        assert_eq!(phy.mmd_register(3, 0x11), 0xBBBB);
+
+        phy.write(13, 3);
+        phy.write(14, 0x10);
+        phy.write(13, 0b10 << 14 | 3);
+        assert_eq!(phy.read(14), 0xAAAA);
+        assert_eq!(phy.read(14), 0xBBBB);
+
+        // Without post-increment, the address stays the same
+        phy.write(13, 3);
+        phy.write(14, 0x10);
+        phy.write(13, 0b01 << 14 | 3);
+        assert_eq!(phy.read(14), 0xAAAA);
+        assert_eq!(phy.read(14), 0xAAAA);
+
+        // Post-increment on writes only
+        phy.write(13, 0b11 << 14 | 3);
+        phy.write(14, 0xCCCC);
+        assert_eq!(phy.mmd_register(3, 0x10), 0xCCCC);
+        assert_eq!(phy.read(14), 0xBBBB);
+        assert_eq!(phy.read(14), 0xBBBB);
+
+        // Other MMDs have their own address
+        phy.write(13, 7);
+        phy.write(14, 0x3C);
+        phy.write(13, 3);
+        assert_eq!(phy.read(14), 0x11);
+    }
+
+    #[test]
+    fn absent_phys_read_all_ones() {
+        let mut bus: SimMiim<2> = SimMiim::new();
+        bus.add(1, SimulatedPhy::for_chip(SimChip::Generic));
+
+        assert_eq!(bus.read(0, 2), Ok(0xFFFF));
+        assert_eq!(bus.read(1, Bcr::ADDRESS), Ok(phy_bcr(&bus, 1)));
+    }
+
+    fn phy_bcr(bus: &SimMiim<2>, address: u8) -> u16 {
+        bus.phy(address).unwrap().register(Bcr::ADDRESS)
+    }
+}
@@ -0,0 +1,513 @@
+//! Recording and replaying of MDIO transactions.
+//!
+//! A [`RecordingMiim`] wraps a [`Miim`] and logs every transaction that
+//! passes through it. A captured trace can then be fed to a [`ReplayMiim`],
+//! which answers reads from the trace and checks that a driver issues the
+//! same transactions, in the same order.
+
+use crate::registers::Bsr;
+use crate::{Clause45Miim, Miim};
+
+/// The direction of an MDIO transaction
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// A register was read
+    Read,
+    /// A register was written
+    Write,
+}
+
+/// A single MDIO transaction
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    /// The address of the PHY
+    pub phy: u8,
+    /// The MMD that was accessed with a Clause 45 frame, or `None` for a
+    /// Clause 22 frame
+    pub devad: Option<u8>,
+    /// The register that was accessed. This is at most 31 for Clause 22 frames.
+    pub reg: u16,
+    /// The value that was read or written
+    pub value: u16,
+    /// Whether the register was read or written
+    pub direction: Direction,
+}
+
+impl Transaction {
+    /// A read of `value` from register `reg` of the PHY at `phy`
+    pub const fn read(phy: u8, reg: u8, value: u16) -> Self {
+        Self {
+            phy,
+            devad: None,
+            reg: reg as u16,
+            value,
+            direction: Direction::Read,
+        }
+    }
+
+    /// A write of `value` to register `reg` of the PHY at `phy`
+    pub const fn write(phy: u8, reg: u8, value: u16) -> Self {
+        Self {
+            phy,
+            devad: None,
+            reg: reg as u16,
+            value,
+            direction: Direction::Write,
+        }
+    }
+
+    /// A Clause 45 read of `value` from register `reg` of MMD `devad` of the
+    /// PHY at `phy`
+    pub const fn read_c45(phy: u8, devad: u8, reg: u16, value: u16) -> Self {
+        Self {
+            phy,
+            devad: Some(devad),
+            reg,
+            value,
+            direction: Direction::Read,
+        }
+    }
+
+    /// A Clause 45 write of `value` to register `reg` of MMD `devad` of the
+    /// PHY at `phy`
+    pub const fn write_c45(phy: u8, devad: u8, reg: u16, value: u16) -> Self {
+        Self {
+            phy,
+            devad: Some(devad),
+            reg,
+            value,
+            direction: Direction::Write,
+        }
+    }
+}
+
+/// A destination for the transactions recorded by a [`RecordingMiim`].
+///
+/// This is implemented for closures taking a [`Transaction`], for
+/// [`TraceBuffer`] and, with the `defmt` feature, for [`DefmtLog`].
+pub trait TransactionLog {
+    /// Record `transaction`
+    fn record(&mut self, transaction: Transaction);
+}
+
+impl<F: FnMut(Transaction)> TransactionLog for F {
+    fn record(&mut self, transaction: Transaction) {
+        self(transaction)
+    }
+}
+
+/// A ring buffer of [`Transaction`]s, backed by a caller-provided slice.
+///
+/// Once the buffer is full, the oldest transactions are overwritten.
+#[derive(Debug)]
+pub struct TraceBuffer<'a> {
+    buffer: &'a mut [Transaction],
+    start: usize,
+    len: usize,
+    dropped: usize,
+}
+
+impl<'a> TraceBuffer<'a> {
+    /// Create a new, empty trace buffer that stores its transactions in `buffer`.
+    ///
+    /// The initial contents of `buffer` are ignored.
+    pub fn new(buffer: &'a mut [Transaction]) -> Self {
+        Self {
+            buffer,
+            start: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// The amount of transactions in the buffer
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the buffer is empty
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The amount of transactions that were overwritten because the
+    /// buffer was full
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Remove all transactions from the buffer
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+        self.dropped = 0;
+    }
+
+    /// Iterate over the transactions in the buffer, from oldest to newest
+    pub fn iter(&self) -> impl Iterator<Item = &Transaction> + '_ {
+        let capacity = self.buffer.len();
+        let end = self.start + self.len;
+
+        let first = &self.buffer[self.start..end.min(capacity)];
+        let second = &self.buffer[..end.saturating_sub(capacity)];
+        first.iter().chain(second.iter())
+    }
+}
+
+impl<'a> TransactionLog for TraceBuffer<'a> {
+    fn record(&mut self, transaction: Transaction) {
+        let capacity = self.buffer.len();
+        if capacity == 0 {
+            self.dropped += 1;
+            return;
+        }
+
+        if self.len < capacity {
+            self.buffer[(self.start + self.len) % capacity] = transaction;
+            self.len += 1;
+        } else {
+            self.buffer[self.start] = transaction;
+            self.start = (self.start + 1) % capacity;
+            self.dropped += 1;
+        }
+    }
+}
+
+/// A [`TransactionLog`] that logs every transaction using [`defmt::debug`].
+#[cfg(feature = "defmt")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefmtLog;
+
+#[cfg(feature = "defmt")]
+impl TransactionLog for DefmtLog {
+    fn record(&mut self, transaction: Transaction) {
+        defmt::debug!("MDIO {}", transaction);
+    }
+}
+
+/// A [`Miim`] that records all successful transactions on the wrapped
+/// [`Miim`] into a [`TransactionLog`].
+///
+/// If the wrapped [`Miim`] supports Clause 45 frames, so does this one, and
+/// Clause 45 frames are recorded with the MMD that they access.
+#[derive(Debug)]
+pub struct RecordingMiim<M, L> {
+    miim: M,
+    log: L,
+}
+
+impl<M, L> RecordingMiim<M, L> {
+    /// Record the transactions on `miim` into `log`
+    pub fn new(miim: M, log: L) -> Self {
+        Self { miim, log }
+    }
+
+    /// Get a reference to the log
+    pub fn log(&self) -> &L {
+        &self.log
+    }
+
+    /// Get a mutable reference to the log
+    pub fn log_mut(&mut self) -> &mut L {
+        &mut self.log
+    }
+
+    /// Get a mutable reference to the wrapped [`Miim`].
+    ///
+    /// Transactions on the returned reference are not recorded.
+    pub fn inner_mut(&mut self) -> &mut M {
+        &mut self.miim
+    }
+
+    /// Release the wrapped [`Miim`] and the log
+    pub fn release(self) -> (M, L) {
+        (self.miim, self.log)
+    }
+}
+
+impl<M: Miim, L: TransactionLog> Miim for RecordingMiim<M, L> {
+    type Error = M::Error;
+
+    fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error> {
+        let value = self.miim.read(phy, reg)?;
+        self.log.record(Transaction::read(phy, reg, value));
+        Ok(value)
+    }
+
+    fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error> {
+        self.miim.write(phy, reg, data)?;
+        self.log.record(Transaction::write(phy, reg, data));
+        Ok(())
+    }
+
+    /// Forwarded to the wrapped [`Miim`], and recorded as a read of the BSR
+    /// that returned only the ability bits, whether or not the wrapped
+    /// [`Miim`] accessed the bus.
+    fn read_abilities(&mut self, phy: u8) -> Result<u16, Self::Error> {
+        let value = self.miim.read_abilities(phy)?;
+        self.log.record(Transaction::read(phy, Bsr::ADDRESS, value));
+        Ok(value)
+    }
+
+    fn clause45(&mut self) -> Option<&mut dyn Clause45Miim<Error = Self::Error>> {
+        self.miim.clause45()?;
+        Some(self)
+    }
+}
+
+/// # Panics
+/// The methods of this trait panic if the wrapped [`Miim`] does not support
+/// Clause 45 frames. [`Miim::clause45`] only returns this implementation if
+/// it does.
+impl<M: Miim, L: TransactionLog> Clause45Miim for RecordingMiim<M, L> {
+    type Error = M::Error;
+
+    fn read_c45(&mut self, port: u8, devad: u8, reg: u16) -> Result<u16, Self::Error> {
+        let value = self
+            .miim
+            .clause45()
+            .expect("The wrapped Miim does not support Clause 45 frames")
+            .read_c45(port, devad, reg)?;
+        self.log
+            .record(Transaction::read_c45(port, devad, reg, value));
+        Ok(value)
+    }
+
+    fn write_c45(&mut self, port: u8, devad: u8, reg: u16, data: u16) -> Result<(), Self::Error> {
+        self.miim
+            .clause45()
+            .expect("The wrapped Miim does not support Clause 45 frames")
+            .write_c45(port, devad, reg, data)?;
+        self.log
+            .record(Transaction::write_c45(port, devad, reg, data));
+        Ok(())
+    }
+}
+
+/// An error reported by a [`ReplayMiim`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// A transaction did not match the trace.
+    ///
+    /// For reads, `actual.value` is always 0.
+    Mismatch {
+        /// The index of the transaction in the trace
+        index: usize,
+        /// The transaction in the trace, or `None` if the trace has ended
+        expected: Option<Transaction>,
+        /// The transaction that was issued
+        actual: Transaction,
+    },
+    /// The trace was not completely replayed
+    Incomplete {
+        /// The amount of transactions that remain in the trace
+        remaining: usize,
+    },
+}
+
+/// A [`Miim`] that replays a captured trace.
+///
+/// Every read must match the phy, MMD and register of the next transaction in
+/// the trace, and returns its value. Every write must match the next
+/// transaction exactly. Once a transaction does not match, all further
+/// transactions fail.
+///
+/// To replay a trace that contains Clause 45 frames, enable them with
+/// [`ReplayMiim::set_clause45`].
+#[derive(Clone, Debug)]
+pub struct ReplayMiim<'a> {
+    trace: &'a [Transaction],
+    position: usize,
+    mismatch: Option<ReplayError>,
+    clause45: bool,
+}
+
+impl<'a> ReplayMiim<'a> {
+    /// Replay `trace`
+    pub fn new(trace: &'a [Transaction]) -> Self {
+        Self {
+            trace,
+            position: 0,
+            mismatch: None,
+            clause45: false,
+        }
+    }
+
+    /// Configure whether Clause 45 frames are supported, which determines
+    /// whether MMD registers are accessed using Clause 45 frames or
+    /// registers 13 and 14.
+    ///
+    /// This is `false` by default.
+    pub fn set_clause45(&mut self, clause45: bool) {
+        self.clause45 = clause45;
+    }
+
+    /// The amount of transactions that have been replayed
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The amount of transactions that have not been replayed yet
+    pub fn remaining(&self) -> usize {
+        self.trace.len() - self.position
+    }
+
+    /// Check that the whole trace was replayed.
+    ///
+    /// This returns the first mismatch if one occured.
+    pub fn finish(&self) -> Result<(), ReplayError> {
+        if let Some(mismatch) = self.mismatch {
+            Err(mismatch)
+        } else if self.remaining() > 0 {
+            Err(ReplayError::Incomplete {
+                remaining: self.remaining(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn next(&mut self, actual: Transaction) -> Result<u16, ReplayError> {
+        let expected = self.trace.get(self.position).copied();
+
+        match expected {
+            Some(expected)
+                if self.mismatch.is_none()
+                    && expected.direction == actual.direction
+                    && expected.phy == actual.phy
+                    && expected.devad == actual.devad
+                    && expected.reg == actual.reg
+                    && (actual.direction == Direction::Read || expected.value == actual.value) =>
+            {
+                self.position += 1;
+                Ok(expected.value)
+            }
+            _ => {
+                let mismatch = ReplayError::Mismatch {
+                    index: self.position,
+                    expected,
+                    actual,
+                };
+                self.mismatch.get_or_insert(mismatch);
+                Err(mismatch)
+            }
+        }
+    }
+}
+
+impl<'a> Miim for ReplayMiim<'a> {
+    type Error = ReplayError;
+
+    fn read(&mut self, phy: u8, reg: u8) -> Result<u16, Self::Error> {
+        self.next(Transaction::read(phy, reg, 0))
+    }
+
+    fn write(&mut self, phy: u8, reg: u8, data: u16) -> Result<(), Self::Error> {
+        self.next(Transaction::write(phy, reg, data)).map(|_| ())
+    }
+
+    fn clause45(&mut self) -> Option<&mut dyn Clause45Miim<Error = Self::Error>> {
+        if self.clause45 {
+            Some(self)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Clause45Miim for ReplayMiim<'a> {
+    type Error = ReplayError;
+
+    fn read_c45(&mut self, port: u8, devad: u8, reg: u16) -> Result<u16, Self::Error> {
+        self.next(Transaction::read_c45(port, devad, reg, 0))
+    }
+
+    fn write_c45(&mut self, port: u8, devad: u8, reg: u16, data: u16) -> Result<(), Self::Error> {
+        self.next(Transaction::write_c45(port, devad, reg, data))
+            .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{SimChip, SimMiim, SimulatedPhy};
+
+    const ADDR: u8 = 1;
+
+    const TRACE: [Transaction; 4] = [
+        Transaction::write(ADDR, 4, 0x01E1),
+        Transaction::write_c45(ADDR, 3, 0x8010, 0x1234),
+        Transaction::read_c45(ADDR, 3, 0x8010, 0x1234),
+        Transaction::read(ADDR, 4, 0x01E1),
+    ];
+
+    fn sim(clause45: bool) -> SimMiim {
+        let mut miim = SimMiim::new();
+        miim.add(ADDR, SimulatedPhy::for_chip(SimChip::Generic));
+        miim.set_clause45(clause45);
+        miim
+    }
+
+    /// Issue the transactions in [`TRACE`]
+    fn access<M: Miim>(miim: &mut M) -> Result<(), M::Error> {
+        miim.write(ADDR, 4, 0x01E1)?;
+
+        let c45 = miim.clause45().unwrap();
+        c45.write_c45(ADDR, 3, 0x8010, 0x1234)?;
+        assert_eq!(c45.read_c45(ADDR, 3, 0x8010)?, 0x1234);
+
+        assert_eq!(miim.read(ADDR, 4)?, 0x01E1);
+        Ok(())
+    }
+
+    #[test]
+    fn records_clause45_frames() {
+        let mut buffer = [Transaction::read(0, 0, 0); 8];
+        let mut miim = RecordingMiim::new(sim(true), TraceBuffer::new(&mut buffer));
+
+        access(&mut miim).unwrap();
+        assert!(miim.log().iter().eq(TRACE.iter()));
+    }
+
+    #[test]
+    fn clause45_support_follows_the_wrapped_miim() {
+        let mut miim = RecordingMiim::new(sim(false), |_| {});
+        assert!(miim.clause45().is_none());
+
+        let mut replay = ReplayMiim::new(&TRACE);
+        assert!(replay.clause45().is_none());
+    }
+
+    #[test]
+    fn replays_clause45_frames() {
+        let mut replay = ReplayMiim::new(&TRACE);
+        replay.set_clause45(true);
+
+        access(&mut replay).unwrap();
+        assert_eq!(replay.finish(), Ok(()));
+    }
+
+    #[test]
+    fn replay_checks_the_mmd() {
+        let mut replay = ReplayMiim::new(&TRACE[1..]);
+        replay.set_clause45(true);
+
+        let actual = Transaction::write_c45(ADDR, 7, 0x8010, 0x1234);
+        let c45 = replay.clause45().unwrap();
+        let expected = ReplayError::Mismatch {
+            index: 0,
+            expected: Some(TRACE[1]),
+            actual,
+        };
+        assert_eq!(c45.write_c45(ADDR, 7, 0x8010, 0x1234), Err(expected));
+
+        // A Clause 22 access never matches a Clause 45 frame
+        let trace = [Transaction::read_c45(ADDR, 0, 4, 0x01E1)];
+        let mut replay = ReplayMiim::new(&trace);
+        assert!(replay.read(ADDR, 4).is_err());
+    }
+}